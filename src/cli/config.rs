@@ -0,0 +1,69 @@
+use std::{collections::HashMap, fs};
+
+use anyhow::{Context, Result};
+
+/* Config file
+Defaults of the options, written as `key = value` lines.
+Lines starting with `#` are comments. Options on the command line take precedence.
+
+ex:
+    # ~/.config/baby-nes.conf
+    palette = composite
+    screenshot_dir = /home/user/Pictures/baby-nes
+*/
+#[derive(Debug, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+        Self::parse(&text).with_context(|| format!("Invalid config file {}", path))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut values = HashMap::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected `key = value`", index + 1))?;
+            let key = key.trim();
+            if key.is_empty() {
+                bail!("line {}: empty key", index + 1);
+            }
+            values.insert(key.to_string(), value.trim().to_string());
+        }
+
+        Ok(Config { values })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod config_test {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let config = Config::parse("# comment\n\npalette = composite\nkey=value \n").unwrap();
+        assert_eq!(config.get("palette"), Some("composite"));
+        assert_eq!(config.get("key"), Some("value"));
+        assert_eq!(config.get("comment"), None);
+    }
+
+    #[test]
+    fn parse_error_test() {
+        assert!(Config::parse("palette").is_err());
+        assert!(Config::parse("= composite").is_err());
+    }
+}
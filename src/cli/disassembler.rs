@@ -0,0 +1,223 @@
+use std::fmt;
+
+use crate::nes::{
+    cartridge::{error::RomError, Cartridge},
+    cpu::opecode::{AddressingMode, OPECODE_MAP},
+    mapper::build_mapper,
+};
+
+use super::rom_info::RESET_VECTOR;
+
+/* Disassembler
+https://www.nesdev.org/obelisk-6502-guide/addressing.html
+
+Instructions are printed in the common assembler syntax. ($: hex, #: immediate)
+Bytes which are not an opecode are printed as data. (.db $XX)
+*/
+pub struct Instruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub text: String,
+}
+
+impl Instruction {
+    pub fn len(&self) -> u16 {
+        self.bytes.len() as u16
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Address of the instruction after this one.
+    pub fn next_address(&self) -> u16 {
+        self.address.wrapping_add(self.len())
+    }
+}
+
+/// ex: `C000  4C F5 C5  JMP $C5F5`
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes: Vec<String> = self
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        write!(
+            f,
+            "{:04X}  {:<8}  {}",
+            self.address,
+            bytes.join(" "),
+            self.text
+        )
+    }
+}
+
+/// Bytes following the opecode.
+pub fn operand_length(mode: &AddressingMode) -> u16 {
+    match mode {
+        AddressingMode::Implied | AddressingMode::Accumulator => 0,
+        AddressingMode::Immediate
+        | AddressingMode::ZeroPage
+        | AddressingMode::ZeroPageIndexedX
+        | AddressingMode::ZeroPageIndexedY
+        | AddressingMode::Relative
+        | AddressingMode::IndexedIndirect
+        | AddressingMode::IndirectIndexed => 1,
+        AddressingMode::Absolute
+        | AddressingMode::AbsoluteIndexedX
+        | AddressingMode::AbsoluteIndexedY
+        | AddressingMode::AbsoluteIndirect => 2,
+    }
+}
+
+/// Disassemble the instruction at `address`. `read` reads the CPU address space.
+pub fn disassemble<F>(mut read: F, address: u16) -> Instruction
+where
+    F: FnMut(u16) -> u8,
+{
+    let code = read(address);
+    let opecode = match OPECODE_MAP.get(&code) {
+        Some(opecode) => opecode,
+        None => {
+            return Instruction {
+                address,
+                bytes: vec![code],
+                text: format!(".db ${:02X}", code),
+            }
+        }
+    };
+
+    let length = operand_length(&opecode.mode);
+    let operand: Vec<u8> = (1..=length)
+        .map(|offset| read(address.wrapping_add(offset)))
+        .collect();
+    let byte = operand.first().copied().unwrap_or(0);
+    let word = u16::from_le_bytes([byte, operand.get(1).copied().unwrap_or(0)]);
+
+    let operand_text = match opecode.mode {
+        AddressingMode::Implied => String::new(),
+        AddressingMode::Accumulator => "A".to_string(),
+        AddressingMode::Immediate => format!("#${:02X}", byte),
+        AddressingMode::ZeroPage => format!("${:02X}", byte),
+        AddressingMode::ZeroPageIndexedX => format!("${:02X},X", byte),
+        AddressingMode::ZeroPageIndexedY => format!("${:02X},Y", byte),
+        AddressingMode::Absolute => format!("${:04X}", word),
+        AddressingMode::AbsoluteIndexedX => format!("${:04X},X", word),
+        AddressingMode::AbsoluteIndexedY => format!("${:04X},Y", word),
+        AddressingMode::Relative => {
+            let target = address.wrapping_add(2).wrapping_add(byte as i8 as u16);
+            format!("${:04X}", target)
+        }
+        AddressingMode::IndexedIndirect => format!("(${:02X},X)", byte),
+        AddressingMode::IndirectIndexed => format!("(${:02X}),Y", byte),
+        AddressingMode::AbsoluteIndirect => format!("(${:04X})", word),
+    };
+
+    let text = match operand_text.is_empty() {
+        true => format!("{:?}", opecode.code),
+        false => format!("{:?} {}", opecode.code, operand_text),
+    };
+
+    Instruction {
+        address,
+        bytes: [vec![code], operand].concat(),
+        text,
+    }
+}
+
+/// Disassemble `count` instructions from `address`.
+pub fn disassemble_range<F>(mut read: F, address: u16, count: usize) -> Vec<Instruction>
+where
+    F: FnMut(u16) -> u8,
+{
+    let mut instructions = Vec::with_capacity(count);
+    let mut address = address;
+    for _ in 0..count {
+        let instruction = disassemble(&mut read, address);
+        address = instruction.next_address();
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+/// Disassemble PRG-ROM as mapped at power-on. `start` defaults to the reset vector.
+pub fn disassemble_cartridge(
+    cartridge: &Cartridge,
+    start: Option<u16>,
+    count: usize,
+) -> Result<Vec<Instruction>, RomError> {
+    let mapper = build_mapper(cartridge)?;
+    let read = |address: u16| match address {
+        0x4020..=0xFFFF => mapper.borrow_mut().cpu_read(address),
+        _ => 0,
+    };
+
+    let start = match start {
+        Some(start) => start,
+        None => u16::from_le_bytes([read(RESET_VECTOR), read(RESET_VECTOR + 1)]),
+    };
+    Ok(disassemble_range(read, start, count))
+}
+
+/// Parse an address written as `C000`, `$C000` or `0xC000`.
+pub fn parse_address(text: &str) -> Result<u16, String> {
+    let digits = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text);
+    u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address: {}", text))
+}
+
+#[cfg(test)]
+mod disassembler_test {
+    use super::*;
+
+    fn read_from(program: &[u8]) -> impl FnMut(u16) -> u8 + '_ {
+        move |address| program.get(address as usize).copied().unwrap_or(0)
+    }
+
+    #[test]
+    fn disassemble_test() {
+        let program = [0x4C, 0xF5, 0xC5, 0xA9, 0x10, 0x0A, 0xB1, 0x20];
+        let instructions = disassemble_range(read_from(&program), 0, 4);
+
+        let texts: Vec<&str> = instructions.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, ["JMP $C5F5", "LDA #$10", "ASL A", "LDA ($20),Y"]);
+        assert_eq!(instructions[1].address, 3);
+        assert_eq!(instructions[3].next_address(), 8);
+    }
+
+    #[test]
+    fn relative_test() {
+        // BNE -2 loops to itself.
+        let program = [0xD0, 0xFE];
+        assert_eq!(disassemble(read_from(&program), 0).text, "BNE $0000");
+
+        let program = [0xF0, 0x10];
+        assert_eq!(disassemble(read_from(&program), 0).text, "BEQ $0012");
+    }
+
+    #[test]
+    fn unknown_opecode_test() {
+        let program = [0x0B];
+        let instruction = disassemble(read_from(&program), 0);
+        assert_eq!(instruction.text, ".db $0B");
+        assert_eq!(instruction.len(), 1);
+    }
+
+    #[test]
+    fn display_test() {
+        let program = [0x4C, 0xF5, 0xC5];
+        let instruction = disassemble(read_from(&program), 0);
+        assert_eq!(instruction.to_string(), "0000  4C F5 C5  JMP $C5F5");
+    }
+
+    #[test]
+    fn parse_address_test() {
+        assert_eq!(parse_address("C000"), Ok(0xC000));
+        assert_eq!(parse_address("$c000"), Ok(0xC000));
+        assert_eq!(parse_address("0x8000"), Ok(0x8000));
+        assert!(parse_address("G000").is_err());
+    }
+}
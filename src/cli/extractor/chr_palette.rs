@@ -0,0 +1,95 @@
+use crate::nes::ppu::system_palette::SystemPalette;
+
+/// Grays of the color numbers 0-3. The grayscale PNG uses them too.
+const GRAYSCALE_COLORS: [[u8; 3]; 4] =
+    [[0, 0, 0], [117, 117, 117], [188, 188, 188], [255, 255, 255]];
+
+/* CHR palette
+https://www.nesdev.org/wiki/PPU_palettes
+
+Colors of the color numbers (0-3) of tiles on exporting CHR.
+A palette is 4 palette numbers of the system palette, like a palette in palette RAM.
+ex: `0F,16,27,30`
+
+The color number 0 is transparent on the screen, so it is exported as transparent.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChrPalette {
+    colors: [[u8; 3]; 4],
+}
+
+impl ChrPalette {
+    pub fn grayscale() -> Self {
+        ChrPalette {
+            colors: GRAYSCALE_COLORS,
+        }
+    }
+
+    pub fn from_palette_numbers(palette_numbers: &[u8; 4], system_palette: &SystemPalette) -> Self {
+        let mut colors = [[0u8; 3]; 4];
+        for (color, palette_number) in colors.iter_mut().zip(palette_numbers) {
+            let (r, g, b) = system_palette.get_color_code(*palette_number, 0);
+            *color = [r, g, b];
+        }
+        ChrPalette { colors }
+    }
+
+    pub fn color(&self, color_number: u8) -> [u8; 3] {
+        self.colors[(color_number & 0x03) as usize]
+    }
+
+    pub fn colors(&self) -> &[[u8; 3]; 4] {
+        &self.colors
+    }
+}
+
+/// Parse 4 palette numbers in hex separated by commas. (ex: `0F,16,27,30`)
+pub fn parse_palette_numbers(text: &str) -> Result<[u8; 4], String> {
+    let numbers = text
+        .split(',')
+        .map(|number| {
+            let number = number.trim();
+            match u8::from_str_radix(number, 16) {
+                Ok(number) if number < 0x40 => Ok(number),
+                _ => Err(format!("invalid palette number: {}", number)),
+            }
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+
+    match numbers.len() {
+        4 => Ok([numbers[0], numbers[1], numbers[2], numbers[3]]),
+        count => Err(format!("4 palette numbers are required, got {}", count)),
+    }
+}
+
+#[cfg(test)]
+mod chr_palette_test {
+    use crate::nes::ppu::palette::NES_COLORS;
+
+    use super::*;
+
+    #[test]
+    fn from_palette_numbers_test() {
+        let system_palette = SystemPalette::builtin("default").unwrap();
+        let palette = ChrPalette::from_palette_numbers(&[0x0F, 0x16, 0x27, 0x30], &system_palette);
+
+        assert_eq!(palette.color(0), NES_COLORS[0x0F]);
+        assert_eq!(palette.color(1), NES_COLORS[0x16]);
+        assert_eq!(palette.color(3), NES_COLORS[0x30]);
+    }
+
+    #[test]
+    fn parse_palette_numbers_test() {
+        assert_eq!(
+            parse_palette_numbers("0F,16,27,30"),
+            Ok([0x0F, 0x16, 0x27, 0x30])
+        );
+        assert_eq!(
+            parse_palette_numbers("0f, 16, 27, 30"),
+            Ok([0x0F, 0x16, 0x27, 0x30])
+        );
+        assert!(parse_palette_numbers("0F,16,27").is_err());
+        assert!(parse_palette_numbers("0F,16,27,40").is_err());
+        assert!(parse_palette_numbers("0F,16,27,XX").is_err());
+    }
+}
@@ -1,39 +1,70 @@
+pub mod chr_palette;
 mod sprite_writer;
 
-use std::fmt::Result;
+use anyhow::Result;
 
 use crate::nes::{
     cartridge::Cartridge,
+    mapper::Mapper,
     ppu::sprite::{build_sprite, Sprite},
 };
 
-use self::sprite_writer::SpriteImageWriter;
+use self::{chr_palette::ChrPalette, sprite_writer::SpriteImageWriter};
+
+/// Format of the image of the extracted tiles.
+pub enum ChrImageFormat {
+    /// Fixed grays
+    Grayscale,
+    /// RGBA with the transparent color number 0
+    Rgba(ChrPalette),
+    /// Indexed by the color numbers
+    Indexed(ChrPalette),
+}
 
 pub struct SpriteExtractor<'a> {
-    cartridge: &'a Cartridge,
+    character: &'a [u8],
 }
 
 impl<'a> SpriteExtractor<'a> {
     pub fn new(cartridge: &'a Cartridge) -> Self {
-        Self { cartridge }
+        Self {
+            character: &cartridge.character_rom,
+        }
+    }
+
+    /// Extract from the CHR-ROM or CHR-RAM of the running mapper.
+    /// CHR-RAM is filled by the program, so the cartridge itself has no sprites.
+    pub fn from_mapper(mapper: &'a dyn Mapper) -> Self {
+        Self {
+            character: mapper.character_memory().as_slice(),
+        }
     }
 
-    pub fn extract_sprite(self, path: &str) -> Result {
-        // ToDo
-        let sprite_length = self.cartridge.character_rom.len() / 16;
+    /// Write the tiles into a PNG, `count_per_row` tiles per row.
+    pub fn extract_sprite(
+        self,
+        path: &str,
+        count_per_row: u32,
+        format: &ChrImageFormat,
+    ) -> Result<()> {
+        let sprite_length = self.character.len() / 16;
 
-        let sprites: Vec<Sprite> = (0..sprite_length)
+        let sprites = (0..sprite_length)
             .map(|i| {
                 let start = i * 16;
                 let end = start + 16;
-                let data = &self.cartridge.character_rom[start..end];
+                let data = &self.character[start..end];
 
-                build_sprite(data).unwrap()
+                build_sprite(data)
             })
-            .collect();
+            .collect::<Result<Vec<Sprite>>>()?;
 
-        let writer = SpriteImageWriter::new(&sprites, 50);
-        writer.save(path);
+        let writer = SpriteImageWriter::new(&sprites, count_per_row);
+        match format {
+            ChrImageFormat::Grayscale => writer.save(path)?,
+            ChrImageFormat::Rgba(palette) => writer.save_rgba(path, palette)?,
+            ChrImageFormat::Indexed(palette) => writer.save_indexed(path, palette)?,
+        }
 
         Ok(())
     }
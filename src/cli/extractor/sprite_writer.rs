@@ -1,7 +1,12 @@
+use std::{fs::File, io::BufWriter};
+
+use anyhow::Result;
 use image;
 
 use crate::nes::ppu::sprite::Sprite;
 
+use super::chr_palette::ChrPalette;
+
 pub struct SpriteImageWriter<'a> {
     /// Count of sprite per row
     count_per_row: u32,
@@ -16,46 +21,104 @@ impl<'a> SpriteImageWriter<'a> {
         }
     }
 
-    pub fn save(self, path: &str) {
-        let width = 8 * self.count_per_row;
-        let height = 8 * self.row_count();
-        let mut image_buffer = image::ImageBuffer::new(width, height);
+    /// Grayscale PNG.
+    pub fn save(self, path: &str) -> image::ImageResult<()> {
+        let color_numbers = self.color_numbers();
+        let image_buffer = image::ImageBuffer::from_fn(self.width(), self.height(), |x, y| {
+            let color_number = color_numbers[(y * self.width() + x) as usize];
+            SpriteImageWriter::color_number_to_pixel(color_number)
+        });
+
+        image_buffer.save(path)
+    }
+
+    /// RGBA PNG. The color number 0 is transparent.
+    pub fn save_rgba(self, path: &str, palette: &ChrPalette) -> image::ImageResult<()> {
+        let color_numbers = self.color_numbers();
+        let image_buffer = image::RgbaImage::from_fn(self.width(), self.height(), |x, y| {
+            let color_number = color_numbers[(y * self.width() + x) as usize];
+            let [r, g, b] = palette.color(color_number);
+            let alpha = match color_number {
+                0 => 0,
+                _ => 255,
+            };
+            image::Rgba([r, g, b, alpha])
+        });
+
+        image_buffer.save(path)
+    }
+
+    /// Indexed PNG. The indexes are the color numbers, and the index 0 is transparent.
+    pub fn save_indexed(self, path: &str, palette: &ChrPalette) -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+
+        let mut encoder = png::Encoder::new(file, self.width(), self.height());
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(palette.colors().concat());
+        encoder.set_trns(vec![0u8, 255, 255, 255]);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.color_numbers())?;
+        Ok(())
+    }
+
+    fn width(&self) -> u32 {
+        8 * self.count_per_row
+    }
+
+    fn height(&self) -> u32 {
+        8 * self.row_count()
+    }
+
+    /// Color numbers of all pixels, row by row.
+    fn color_numbers(&self) -> Vec<u8> {
+        let width = self.width();
+        let mut color_numbers = vec![0u8; (width * self.height()) as usize];
 
         for (i, sprite) in self.sprites.iter().enumerate() {
             for y in 0..8 {
                 for x in 0..8 {
                     let pos_x = x + (i as u32 % self.count_per_row * 8);
                     let pos_y = y + (i as u32 / self.count_per_row * 8);
-                    let color_number = sprite[y as usize][x as usize];
-                    let pixel = SpriteImageWriter::color_number_to_pixel(color_number);
-
-                    image_buffer.put_pixel(pos_x, pos_y, pixel);
+                    color_numbers[(pos_y * width + pos_x) as usize] =
+                        sprite[y as usize][x as usize];
                 }
             }
         }
 
-        image_buffer.save(path).unwrap();
+        color_numbers
     }
 
     fn row_count(&self) -> u32 {
         (self.sprites.len() as f32 / self.count_per_row as f32).ceil() as u32
     }
 
+    /// Red, green and blue of the grays are the same, so red is the luma.
     fn color_number_to_pixel(color_number: u8) -> image::Luma<u8> {
-        match color_number {
-            0 => image::Luma([0u8]),
-            1 => image::Luma([117u8]),
-            2 => image::Luma([188u8]),
-            3 => image::Luma([255u8]),
-            _ => image::Luma([0u8]),
-        }
+        let [luma, _, _] = ChrPalette::grayscale().color(color_number);
+        image::Luma([luma])
     }
 }
 
 #[cfg(test)]
 mod sprite_image_writer_test {
+    use std::{env, fs};
+
     use super::*;
 
+    fn temp_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("baby_nes_{}_{}.png", name, std::process::id()));
+        path.to_string_lossy().into_owned()
+    }
+
+    fn build_sprites() -> Vec<Sprite> {
+        let mut sprites = vec![vec![vec![0u8; 8]; 8]; 2];
+        sprites[0][0][1] = 1;
+        sprites[1][0][0] = 3;
+        sprites
+    }
+
     #[test]
     fn row_count_by_divisible() {
         let sprites = vec![vec![vec![0u8; 8]; 8]; 10];
@@ -86,4 +149,60 @@ mod sprite_image_writer_test {
         assert_eq!(SpriteImageWriter::color_number_to_pixel(1), gray);
         assert_eq!(SpriteImageWriter::color_number_to_pixel(0), black);
     }
+
+    #[test]
+    fn color_numbers_layout() {
+        let mut sprites = vec![vec![vec![0u8; 8]; 8]; 3];
+        sprites[1][0][0] = 1;
+        sprites[2][7][7] = 3;
+
+        let writer = SpriteImageWriter::new(&sprites, 2);
+        let color_numbers = writer.color_numbers();
+        assert_eq!(color_numbers.len(), 16 * 16);
+        // The second sprite is on the right of the first one.
+        assert_eq!(color_numbers[8], 1);
+        // The third sprite is on the next row.
+        assert_eq!(color_numbers[15 * 16 + 7], 3);
+        assert_eq!(color_numbers.iter().filter(|n| **n != 0).count(), 2);
+    }
+
+    #[test]
+    fn save_rgba_test() {
+        let palette = ChrPalette::grayscale();
+        let path = temp_path("rgba");
+        let sprites = build_sprites();
+        SpriteImageWriter::new(&sprites, 2)
+            .save_rgba(&path, &palette)
+            .unwrap();
+
+        let image = image::open(&path).unwrap().to_rgba8();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.dimensions(), (16, 8));
+        assert_eq!(image.get_pixel(0, 0), &image::Rgba([0, 0, 0, 0]));
+        assert_eq!(image.get_pixel(1, 0), &image::Rgba([117, 117, 117, 255]));
+        assert_eq!(image.get_pixel(8, 0), &image::Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn save_indexed_test() {
+        let palette = ChrPalette::grayscale();
+        let path = temp_path("indexed");
+        let sprites = build_sprites();
+        SpriteImageWriter::new(&sprites, 2)
+            .save_indexed(&path, &palette)
+            .unwrap();
+
+        let decoder = png::Decoder::new(fs::File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        let info = reader.info();
+        assert_eq!(info.color_type, png::ColorType::Indexed);
+        assert_eq!(info.trns.as_deref(), Some(&[0u8, 255, 255, 255][..]));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(&data[0..2], &[0, 1]);
+        assert_eq!(data[8], 3);
+    }
 }
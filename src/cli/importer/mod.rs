@@ -0,0 +1,167 @@
+mod sprite_reader;
+
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+
+use crate::nes::{cartridge::Cartridge, ppu::sprite::encode_sprite};
+
+use self::sprite_reader::SpriteImageReader;
+
+use super::extractor::chr_palette::ChrPalette;
+
+/// Pixels with less alpha are the transparent color number 0.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// How the pixels of an image are mapped to the color numbers.
+pub enum Quantizer {
+    /// The nearest of the grays of the grayscale export by the luminance.
+    Grayscale,
+    /// The nearest of the colors of the palette.
+    Palette(ChrPalette),
+}
+
+impl Quantizer {
+    pub fn color_number(&self, pixel: &Rgba<u8>) -> u8 {
+        let [r, g, b, alpha] = pixel.0;
+        if alpha < ALPHA_THRESHOLD {
+            return 0;
+        }
+
+        let color = [r, g, b];
+        let nearest = |palette: &ChrPalette, distance: fn(&[u8; 3], &[u8; 3]) -> u32| {
+            (0..4u8)
+                .min_by_key(|color_number| distance(&color, &palette.color(*color_number)))
+                .unwrap()
+        };
+
+        match self {
+            Quantizer::Grayscale => nearest(&ChrPalette::grayscale(), luminance_distance),
+            Quantizer::Palette(palette) => nearest(palette, rgb_distance),
+        }
+    }
+
+    /// Pixel as the color which is counted for the limit of colors per tile.
+    /// All transparent pixels are the same color.
+    pub fn normalize(&self, pixel: &Rgba<u8>) -> [u8; 4] {
+        let [r, g, b, alpha] = pixel.0;
+        match alpha < ALPHA_THRESHOLD {
+            true => [0, 0, 0, 0],
+            false => [r, g, b, 255],
+        }
+    }
+}
+
+/// Difference of ITU-R BT.601 luma x 1000
+fn luminance_distance(a: &[u8; 3], b: &[u8; 3]) -> u32 {
+    let luminance =
+        |color: &[u8; 3]| 299 * color[0] as u32 + 587 * color[1] as u32 + 114 * color[2] as u32;
+    luminance(a).abs_diff(luminance(b))
+}
+
+/// Squared distance in the RGB space
+fn rgb_distance(a: &[u8; 3], b: &[u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (*a as i32 - *b as i32).pow(2) as u32)
+        .sum()
+}
+
+/* CHR importer
+Write tiles edited in an image back into CHR-ROM of a ROM file.
+The image must be laid out like the output of `extract-chr`, with all the tiles of CHR-ROM.
+*/
+pub fn import_chr(binary: &[u8], image: &RgbaImage, quantizer: &Quantizer) -> Result<Vec<u8>> {
+    let cartridge = Cartridge::new(binary)?;
+    ensure!(
+        !cartridge.character_rom.is_empty(),
+        "the ROM has no CHR-ROM (CHR-RAM is filled by the program)"
+    );
+
+    let sprite_count = cartridge.character_rom.len() / 16;
+    let sprites = SpriteImageReader::new(image, sprite_count)?.read(quantizer)?;
+
+    let mut character_rom = Vec::with_capacity(cartridge.character_rom.len());
+    for sprite in &sprites {
+        character_rom.extend(encode_sprite(sprite)?);
+    }
+
+    let offset = cartridge.character_rom_offset();
+    let mut patched = binary.to_vec();
+    patched[offset..offset + character_rom.len()].copy_from_slice(&character_rom);
+    Ok(patched)
+}
+
+#[cfg(test)]
+mod importer_test {
+    use crate::nes::{cartridge::header::MAGIC_BYTES, ppu::system_palette::SystemPalette};
+
+    use super::*;
+
+    fn build_binary() -> Vec<u8> {
+        let header = [
+            vec![
+                MAGIC_BYTES[0],
+                MAGIC_BYTES[1],
+                MAGIC_BYTES[2],
+                MAGIC_BYTES[3],
+                1,
+                1,
+            ],
+            vec![0; 10],
+        ]
+        .concat();
+        [header, vec![0xEA; 0x4000], vec![0; 0x2000]].concat()
+    }
+
+    #[test]
+    fn grayscale_quantizer_test() {
+        let quantizer = Quantizer::Grayscale;
+        assert_eq!(quantizer.color_number(&Rgba([0, 0, 0, 255])), 0);
+        assert_eq!(quantizer.color_number(&Rgba([110, 120, 115, 255])), 1);
+        assert_eq!(quantizer.color_number(&Rgba([190, 190, 190, 255])), 2);
+        assert_eq!(quantizer.color_number(&Rgba([250, 250, 250, 255])), 3);
+        // Transparent
+        assert_eq!(quantizer.color_number(&Rgba([255, 255, 255, 0])), 0);
+    }
+
+    #[test]
+    fn palette_quantizer_test() {
+        let system_palette = SystemPalette::builtin("default").unwrap();
+        let palette = ChrPalette::from_palette_numbers(&[0x0F, 0x16, 0x2A, 0x12], &system_palette);
+        let quantizer = Quantizer::Palette(palette.clone());
+
+        for color_number in 0..4 {
+            let [r, g, b] = palette.color(color_number);
+            assert_eq!(quantizer.color_number(&Rgba([r, g, b, 255])), color_number);
+        }
+    }
+
+    #[test]
+    fn import_chr_test() {
+        let binary = build_binary();
+        // 512 tiles, 16 tiles per row
+        let mut image = RgbaImage::from_pixel(128, 256, Rgba([0, 0, 0, 255]));
+        // The top left pixel of the tile 1 is 3.
+        image.put_pixel(8, 0, Rgba([255, 255, 255, 255]));
+
+        let patched = import_chr(&binary, &image, &Quantizer::Grayscale).unwrap();
+        let cartridge = Cartridge::new(&patched).unwrap();
+
+        assert_eq!(cartridge.program_rom, vec![0xEA; 0x4000]);
+        assert_eq!(
+            &cartridge.character_rom[16..32],
+            &[0x80, 0, 0, 0, 0, 0, 0, 0, 0x80, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            cartridge.character_rom.iter().filter(|b| **b != 0).count(),
+            2
+        );
+    }
+
+    #[test]
+    fn import_chr_rejects_wrong_dimensions() {
+        let binary = build_binary();
+        let image = RgbaImage::new(128, 248);
+        assert!(import_chr(&binary, &image, &Quantizer::Grayscale).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use image::RgbaImage;
+
+use crate::nes::ppu::sprite::Sprite;
+
+use super::Quantizer;
+
+/// Colors a tile can have.
+const MAX_COLORS_PER_TILE: usize = 4;
+
+/// Read sprites from an image laid out like the output of `SpriteImageWriter`.
+/// The count of sprite per row is decided by the width of the image.
+pub struct SpriteImageReader<'a> {
+    image: &'a RgbaImage,
+    count_per_row: u32,
+    sprite_count: usize,
+}
+
+impl<'a> SpriteImageReader<'a> {
+    pub fn new(image: &'a RgbaImage, sprite_count: usize) -> Result<Self> {
+        let (width, height) = image.dimensions();
+        ensure!(
+            width > 0 && width % 8 == 0,
+            "invalid image width {} (must be a multiple of 8)",
+            width
+        );
+
+        let count_per_row = width / 8;
+        let row_count = (sprite_count as f32 / count_per_row as f32).ceil() as u32;
+        ensure!(
+            height == row_count * 8,
+            "invalid image height {} (must be {} for {} tiles of {} tiles per row)",
+            height,
+            row_count * 8,
+            sprite_count,
+            count_per_row
+        );
+
+        Ok(SpriteImageReader {
+            image,
+            count_per_row,
+            sprite_count,
+        })
+    }
+
+    pub fn read(&self, quantizer: &Quantizer) -> Result<Vec<Sprite>> {
+        (0..self.sprite_count)
+            .map(|i| self.read_sprite(i, quantizer))
+            .collect()
+    }
+
+    fn read_sprite(&self, index: usize, quantizer: &Quantizer) -> Result<Sprite> {
+        let left = index as u32 % self.count_per_row * 8;
+        let top = index as u32 / self.count_per_row * 8;
+
+        let mut colors = HashSet::new();
+        let sprite: Sprite = (0..8)
+            .map(|y| {
+                (0..8)
+                    .map(|x| {
+                        let pixel = self.image.get_pixel(left + x, top + y);
+                        colors.insert(quantizer.normalize(pixel));
+                        quantizer.color_number(pixel)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        ensure!(
+            colors.len() <= MAX_COLORS_PER_TILE,
+            "tile {} at ({}, {}) has {} colors (must be {} or less)",
+            index,
+            left,
+            top,
+            colors.len(),
+            MAX_COLORS_PER_TILE
+        );
+
+        Ok(sprite)
+    }
+}
+
+#[cfg(test)]
+mod sprite_image_reader_test {
+    use image::Rgba;
+
+    use super::*;
+
+    const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    #[test]
+    fn invalid_dimensions() {
+        let image = RgbaImage::new(12, 8);
+        assert!(SpriteImageReader::new(&image, 1).is_err());
+
+        // 3 tiles in 2 tiles per row are 2 rows.
+        let image = RgbaImage::new(16, 8);
+        assert!(SpriteImageReader::new(&image, 3).is_err());
+        let image = RgbaImage::new(16, 16);
+        assert!(SpriteImageReader::new(&image, 3).is_ok());
+    }
+
+    #[test]
+    fn read_layout() {
+        let mut image = RgbaImage::from_pixel(16, 16, BLACK);
+        image.put_pixel(8, 0, Rgba([255, 255, 255, 255]));
+        image.put_pixel(7, 15, Rgba([117, 117, 117, 255]));
+
+        let reader = SpriteImageReader::new(&image, 3).unwrap();
+        let sprites = reader.read(&Quantizer::Grayscale).unwrap();
+
+        assert_eq!(sprites.len(), 3);
+        assert_eq!(sprites[1][0][0], 3);
+        assert_eq!(sprites[2][7][7], 1);
+        assert_eq!(sprites[0], vec![vec![0; 8]; 8]);
+    }
+
+    #[test]
+    fn too_many_colors() {
+        let mut image = RgbaImage::from_pixel(8, 8, BLACK);
+        for (x, gray) in [50u8, 100, 150, 200].iter().enumerate() {
+            image.put_pixel(x as u32, 0, Rgba([*gray, *gray, *gray, 255]));
+        }
+
+        let reader = SpriteImageReader::new(&image, 1).unwrap();
+        assert!(reader.read(&Quantizer::Grayscale).is_err());
+    }
+}
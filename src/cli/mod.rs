@@ -1 +1,8 @@
+pub mod config;
+pub mod disassembler;
 pub mod extractor;
+pub mod hash;
+pub mod importer;
+pub mod name_table_export;
+pub mod rom_info;
+pub mod trace;
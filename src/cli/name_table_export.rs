@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::nes::ppu::name_table_dump::NameTableDump;
+
+/// PPU addresses of the name tables
+const NAME_TABLE_ADDRESSES: [u16; 4] = [0x2000, 0x2400, 0x2800, 0x2C00];
+const TILE_COLUMNS: usize = 32;
+
+/// 512x480 RGB PNG of the 4 name tables.
+pub fn save_png<P: AsRef<Path>>(dump: &NameTableDump, path: P) -> Result<()> {
+    image::save_buffer_with_format(
+        path,
+        &dump.pixels,
+        NameTableDump::WIDTH as u32,
+        NameTableDump::HEIGHT as u32,
+        image::ColorType::Rgb8,
+        image::ImageFormat::Png,
+    )?;
+
+    Ok(())
+}
+
+/* Name table JSON
+Tile indices and palette IDs of the name tables, as rows of 32 tiles.
+
+{
+  "mirroring": "Vertical",
+  "name_tables": [
+    {
+      "address": "$2000",
+      "tiles": [[0, 1, ...], ...],
+      "palettes": [[0, 0, ...], ...]
+    },
+    ...
+  ]
+}
+*/
+pub fn to_json(dump: &NameTableDump) -> String {
+    let tables: Vec<String> = dump
+        .tables
+        .iter()
+        .zip(NAME_TABLE_ADDRESSES)
+        .map(|(entries, address)| {
+            let tiles = rows_to_json(entries.iter().map(|entry| entry.tile_index).collect());
+            let palettes = rows_to_json(entries.iter().map(|entry| entry.palette_id).collect());
+            format!(
+                "    {{\n      \"address\": \"${:04X}\",\n      \"tiles\": {},\n      \"palettes\": {}\n    }}",
+                address, tiles, palettes
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\n  \"mirroring\": \"{:?}\",\n  \"name_tables\": [\n{}\n  ]\n}}",
+        dump.mirroring,
+        tables.join(",\n")
+    )
+}
+
+fn rows_to_json(values: Vec<u8>) -> String {
+    let rows: Vec<String> = values
+        .chunks(TILE_COLUMNS)
+        .map(|row| {
+            let row: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+            format!("[{}]", row.join(", "))
+        })
+        .collect();
+
+    format!("[\n        {}\n      ]", rows.join(",\n        "))
+}
+
+#[cfg(test)]
+mod name_table_export_test {
+    use crate::nes::{cartridge::header::MirroringType, ppu::name_table_dump::NameTableEntry};
+
+    use super::*;
+
+    fn build_dump() -> NameTableDump {
+        let mut tables = vec![
+            vec![
+                NameTableEntry {
+                    tile_index: 0,
+                    palette_id: 0
+                };
+                32 * 30
+            ];
+            4
+        ];
+        tables[1][1] = NameTableEntry {
+            tile_index: 0x24,
+            palette_id: 3,
+        };
+
+        NameTableDump {
+            mirroring: MirroringType::Horizontal,
+            tables,
+            pixels: vec![0; NameTableDump::WIDTH * NameTableDump::HEIGHT * 3],
+        }
+    }
+
+    #[test]
+    fn to_json_test() {
+        let json = to_json(&build_dump());
+
+        assert!(json.starts_with("{\n  \"mirroring\": \"Horizontal\",\n"));
+        assert_eq!(json.matches("\"address\"").count(), 4);
+        assert!(json.contains("\"address\": \"$2400\",\n      \"tiles\": [\n        [0, 36, 0,"));
+        assert!(json.contains("\"palettes\": [\n        [0, 3, 0,"));
+        // 30 rows of the 4 tables
+        assert_eq!(json.matches("[0, ").count(), 30 * 4 * 2);
+    }
+}
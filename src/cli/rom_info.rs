@@ -0,0 +1,359 @@
+use std::{fs, path::Path};
+
+use crate::nes::{
+    cartridge::{
+        error::RomError,
+        header::{ConsoleType, HeaderFormat, TimingMode},
+        Cartridge,
+    },
+    mapper::{build_mapper, mapper_name},
+};
+
+use super::hash::{crc32, sha1, to_hex};
+
+pub const NMI_VECTOR: u16 = 0xFFFA;
+pub const RESET_VECTOR: u16 = 0xFFFC;
+pub const IRQ_VECTOR: u16 = 0xFFFE;
+
+pub struct Checksum {
+    pub crc32: u32,
+    pub sha1: [u8; 20],
+}
+
+impl Checksum {
+    fn new(data: &[u8]) -> Self {
+        Checksum {
+            crc32: crc32(data),
+            sha1: sha1(data),
+        }
+    }
+}
+
+/// Interrupt vectors at power-on.
+pub struct Vectors {
+    pub nmi: u16,
+    pub reset: u16,
+    pub irq: u16,
+}
+
+/* ROM information
+Summary of a ROM dump printed by the `rom-info` command.
+
+Checksums are calculated for PRG-ROM, CHR-ROM, the ROM without the header (as ROM databases do),
+and the whole file.
+*/
+pub struct RomInfo {
+    pub cartridge: Cartridge,
+    pub program_checksum: Checksum,
+    pub character_checksum: Checksum,
+    pub rom_checksum: Checksum,
+    pub file_checksum: Checksum,
+    pub vectors: Vectors,
+}
+
+impl RomInfo {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RomError> {
+        let binary = fs::read(path.as_ref()).map_err(|error| RomError::from_io(path, error))?;
+        Self::new(&binary)
+    }
+
+    pub fn new(binary: &[u8]) -> Result<Self, RomError> {
+        let cartridge = Cartridge::new(binary)?;
+        let rom = [
+            cartridge.program_rom.as_slice(),
+            cartridge.character_rom.as_slice(),
+        ]
+        .concat();
+
+        Ok(RomInfo {
+            program_checksum: Checksum::new(&cartridge.program_rom),
+            character_checksum: Checksum::new(&cartridge.character_rom),
+            rom_checksum: Checksum::new(&rom),
+            file_checksum: Checksum::new(binary),
+            vectors: read_vectors(&cartridge),
+            cartridge,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let header = &self.cartridge.header;
+        let mut lines = vec![
+            format!("Format     : {}", format_name(header.format)),
+            format!(
+                "Mapper     : {} ({}), submapper {}",
+                header.mapper_number,
+                mapper_name(header.mapper_number).unwrap_or("unknown"),
+                header.submapper_number
+            ),
+            format!(
+                "PRG-ROM    : {}",
+                format_size(header.program_rom_byte_size())
+            ),
+            format!(
+                "CHR-ROM    : {}",
+                format_size(header.character_rom_byte_size())
+            ),
+            format!(
+                "PRG-RAM    : {}",
+                format_size(header.program_ram_byte_size())
+            ),
+            format!(
+                "PRG-NVRAM  : {}",
+                format_size(header.program_nvram_byte_size())
+            ),
+            format!(
+                "CHR-RAM    : {}",
+                format_size(header.character_ram_byte_size())
+            ),
+            format!("Mirroring  : {:?}", header.nametable_mirroring()),
+            format!("Battery    : {}", yes_no(header.has_battery_backup)),
+            format!("Trainer    : {}", yes_no(header.has_trainer)),
+            format!("Region     : {}", timing_name(header.timing)),
+            format!("Console    : {}", console_name(header.console_type)),
+            format!(
+                "Vectors    : NMI ${:04X} / RESET ${:04X} / IRQ ${:04X}",
+                self.vectors.nmi, self.vectors.reset, self.vectors.irq
+            ),
+        ];
+
+        for (name, checksum) in self.checksums() {
+            lines.push(format!(
+                "{:<11}: CRC32 {:08X} / SHA-1 {}",
+                name,
+                checksum.crc32,
+                to_hex(&checksum.sha1)
+            ));
+        }
+
+        lines.join("\n")
+    }
+
+    /// JSON for scripts. Sizes are in bytes.
+    pub fn to_json(&self) -> String {
+        let header = &self.cartridge.header;
+        let mapper_name = match mapper_name(header.mapper_number) {
+            Some(name) => format!("\"{}\"", name),
+            None => "null".to_string(),
+        };
+
+        let checksums: Vec<String> = self
+            .checksums()
+            .iter()
+            .map(|(name, checksum)| {
+                format!(
+                    "    \"{}\": {{ \"crc32\": \"{:08x}\", \"sha1\": \"{}\" }}",
+                    name.to_lowercase(),
+                    checksum.crc32,
+                    to_hex(&checksum.sha1)
+                )
+            })
+            .collect();
+
+        let fields = [
+            format!("  \"format\": \"{}\"", format_name(header.format)),
+            format!("  \"mapper\": {}", header.mapper_number),
+            format!("  \"mapper_name\": {}", mapper_name),
+            format!("  \"submapper\": {}", header.submapper_number),
+            format!("  \"prg_rom_size\": {}", header.program_rom_byte_size()),
+            format!("  \"chr_rom_size\": {}", header.character_rom_byte_size()),
+            format!("  \"prg_ram_size\": {}", header.program_ram_byte_size()),
+            format!("  \"prg_nvram_size\": {}", header.program_nvram_byte_size()),
+            format!("  \"chr_ram_size\": {}", header.character_ram_byte_size()),
+            format!("  \"mirroring\": \"{:?}\"", header.nametable_mirroring()),
+            format!("  \"battery\": {}", header.has_battery_backup),
+            format!("  \"trainer\": {}", header.has_trainer),
+            format!("  \"region\": \"{}\"", timing_name(header.timing)),
+            format!("  \"console\": \"{}\"", console_name(header.console_type)),
+            format!(
+                "  \"vectors\": {{ \"nmi\": {}, \"reset\": {}, \"irq\": {} }}",
+                self.vectors.nmi, self.vectors.reset, self.vectors.irq
+            ),
+            format!("  \"checksums\": {{\n{}\n  }}", checksums.join(",\n")),
+        ];
+
+        format!("{{\n{}\n}}", fields.join(",\n"))
+    }
+
+    fn checksums(&self) -> [(&str, &Checksum); 4] {
+        [
+            ("PRG", &self.program_checksum),
+            ("CHR", &self.character_checksum),
+            ("ROM", &self.rom_checksum),
+            ("File", &self.file_checksum),
+        ]
+    }
+}
+
+/// Read through the mapper to see the banks at power-on.
+/// Unsupported mappers fall back to the end of PRG-ROM, where most boards fix the last bank.
+fn read_vectors(cartridge: &Cartridge) -> Vectors {
+    let mapper = build_mapper(cartridge).ok();
+    let read = |address: u16| match &mapper {
+        Some(mapper) => mapper.borrow_mut().cpu_read(address),
+        None => {
+            let program_rom = &cartridge.program_rom;
+            program_rom
+                .len()
+                .checked_sub(0x10000 - address as usize)
+                .map_or(0, |offset| program_rom[offset])
+        }
+    };
+    let read_u16 = |address: u16| u16::from_le_bytes([read(address), read(address + 1)]);
+
+    Vectors {
+        nmi: read_u16(NMI_VECTOR),
+        reset: read_u16(RESET_VECTOR),
+        irq: read_u16(IRQ_VECTOR),
+    }
+}
+
+fn format_name(format: HeaderFormat) -> &'static str {
+    match format {
+        HeaderFormat::ArchaicINes => "archaic iNES",
+        HeaderFormat::INes => "iNES",
+        HeaderFormat::Nes2 => "NES 2.0",
+    }
+}
+
+fn timing_name(timing: TimingMode) -> &'static str {
+    match timing {
+        TimingMode::Ntsc => "NTSC",
+        TimingMode::Pal => "PAL",
+        TimingMode::MultipleRegion => "multiple region",
+        TimingMode::Dendy => "Dendy",
+    }
+}
+
+fn console_name(console_type: ConsoleType) -> String {
+    match console_type {
+        ConsoleType::Nes => "NES/Famicom".to_string(),
+        ConsoleType::VsSystem => "VS System".to_string(),
+        ConsoleType::Playchoice10 => "PlayChoice-10".to_string(),
+        ConsoleType::Extended(number) => format!("extended console type {}", number),
+    }
+}
+
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        0 => "none".to_string(),
+        bytes if bytes % 1024 == 0 => format!("{} KB", bytes / 1024),
+        bytes => format!("{} bytes", bytes),
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    match value {
+        true => "yes",
+        false => "no",
+    }
+}
+
+#[cfg(test)]
+mod rom_info_test {
+    use crate::nes::cartridge::header::MAGIC_BYTES;
+
+    use super::*;
+
+    fn build_binary(mapper_number: u8) -> Vec<u8> {
+        let header = [
+            vec![
+                MAGIC_BYTES[0],
+                MAGIC_BYTES[1],
+                MAGIC_BYTES[2],
+                MAGIC_BYTES[3],
+                1,
+                1,
+                (mapper_number << 4) | 0b00000011,
+            ],
+            vec![0; 9],
+        ]
+        .concat();
+
+        let mut program_rom = vec![0; 0x4000];
+        program_rom[0x3FFA..].copy_from_slice(&[0x00, 0x90, 0x00, 0x80, 0x00, 0xA0]);
+
+        [header, program_rom, vec![0; 0x2000]].concat()
+    }
+
+    #[test]
+    fn vectors_test() {
+        let info = RomInfo::new(&build_binary(0)).unwrap();
+        assert_eq!(info.vectors.nmi, 0x9000);
+        assert_eq!(info.vectors.reset, 0x8000);
+        assert_eq!(info.vectors.irq, 0xA000);
+    }
+
+    #[test]
+    fn vectors_of_unsupported_mapper_test() {
+        let info = RomInfo::new(&build_binary(15)).unwrap();
+        assert_eq!(info.vectors.reset, 0x8000);
+    }
+
+    #[test]
+    fn vectors_of_too_small_program_rom_test() {
+        // NES 2.0 MMC3 with 8KB PRG-ROM (exponent 13, multiplier 1)
+        let header = [
+            vec![
+                MAGIC_BYTES[0],
+                MAGIC_BYTES[1],
+                MAGIC_BYTES[2],
+                MAGIC_BYTES[3],
+                13 << 2,
+                1,
+                0b01000000,
+                0b00001000,
+                0,
+                0x0F,
+            ],
+            vec![0; 6],
+        ]
+        .concat();
+        let mut program_rom = vec![0; 0x2000];
+        program_rom[0x1FFA..].copy_from_slice(&[0x00, 0x90, 0x00, 0x80, 0x00, 0xA0]);
+        let binary = [header, program_rom, vec![0; 0x2000]].concat();
+
+        let info = RomInfo::new(&binary).unwrap();
+        assert_eq!(info.vectors.reset, 0x8000);
+    }
+
+    #[test]
+    fn no_program_rom_test() {
+        let mut binary = build_binary(0);
+        binary[4] = 0;
+        binary.drain(16..16 + 0x4000);
+
+        assert_eq!(RomInfo::new(&binary).err(), Some(RomError::NoProgramRom));
+    }
+
+    #[test]
+    fn checksum_test() {
+        let binary = build_binary(0);
+        let info = RomInfo::new(&binary).unwrap();
+
+        assert_eq!(info.file_checksum.crc32, crc32(&binary));
+        assert_eq!(info.rom_checksum.crc32, crc32(&binary[16..]));
+        assert_eq!(info.character_checksum.crc32, crc32(&[0; 0x2000]));
+    }
+
+    #[test]
+    fn to_text_test() {
+        let text = RomInfo::new(&build_binary(4)).unwrap().to_text();
+        assert!(text.contains("Format     : iNES"));
+        assert!(text.contains("Mapper     : 4 (MMC3), submapper 0"));
+        assert!(text.contains("PRG-ROM    : 16 KB"));
+        assert!(text.contains("Mirroring  : Vertical"));
+        assert!(text.contains("Battery    : yes"));
+        assert!(text.contains("Region     : NTSC"));
+    }
+
+    #[test]
+    fn to_json_test() {
+        let json = RomInfo::new(&build_binary(15)).unwrap().to_json();
+        assert!(json.starts_with("{\n"));
+        assert!(json.contains("\"mapper\": 15,"));
+        assert!(json.contains("\"mapper_name\": null,"));
+        assert!(json.contains("\"battery\": true,"));
+        assert!(json.contains("\"vectors\": { \"nmi\": 36864, \"reset\": 32768, \"irq\": 40960 },"));
+        assert!(json.ends_with("  }\n}"));
+    }
+}
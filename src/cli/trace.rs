@@ -0,0 +1,56 @@
+use std::io::{self, Write};
+
+use crate::nes::{cpu::registers::CpuRegisters, Nes};
+
+use super::disassembler::{disassemble, Instruction};
+
+/* Trace
+Log of the executed instructions printed by the `trace` command.
+The columns are similar to the nestest log, so that logs can be compared with other emulators.
+
+ex: `C000  4C F5 C5  JMP $C5F5                 A:00 X:00 Y:00 P:24 SP:FD`
+*/
+pub fn trace_line(instruction: &Instruction, registers: &CpuRegisters) -> String {
+    format!(
+        "{:<42}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
+        instruction.to_string(),
+        registers.a,
+        registers.x,
+        registers.y,
+        registers.p.bits(),
+        registers.s
+    )
+}
+
+/// Run `count` instructions and write a line before each of them.
+pub fn trace<W: Write>(nes: &mut Nes, count: usize, output: &mut W) -> io::Result<()> {
+    for _ in 0..count {
+        let registers = nes.cpu_registers();
+        let instruction = disassemble(|address| nes.peek(address), registers.pc);
+        writeln!(output, "{}", trace_line(&instruction, registers))?;
+
+        nes.step();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod trace_test {
+    use crate::nes::cpu::registers::CpuStatusFlag;
+
+    use super::*;
+
+    #[test]
+    fn trace_line_test() {
+        let program = [0x4C, 0xF5, 0xC5];
+        let instruction = disassemble(|address| program[address as usize], 0);
+
+        let mut registers = CpuRegisters::new();
+        registers.p = CpuStatusFlag::BREAK2 | CpuStatusFlag::INTERRUPT_DISABLE;
+
+        assert_eq!(
+            trace_line(&instruction, &registers),
+            "0000  4C F5 C5  JMP $C5F5                 A:00 X:00 Y:00 P:24 SP:FD"
+        );
+    }
+}
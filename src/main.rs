@@ -1,4 +1,4 @@
-use nes::ppu::frame::Frame;
+use nes::ppu::frame::{Frame, PixelFormat, PIXEL_FORMAT_NAMES};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 
@@ -11,18 +11,464 @@ extern crate anyhow;
 #[macro_use]
 extern crate arrayref;
 
-use nes::Nes;
+use clap::{Parser, Subcommand};
+use cli::{
+    config::Config,
+    disassembler::{disassemble_cartridge, parse_address},
+    extractor::{
+        chr_palette::{parse_palette_numbers, ChrPalette},
+        ChrImageFormat, SpriteExtractor,
+    },
+    importer::{self, Quantizer},
+    name_table_export,
+    rom_info::RomInfo,
+    trace::trace,
+};
+use nes::{
+    cartridge::Cartridge,
+    ppu::{
+        palette_ram::PaletteType,
+        system_palette::{SystemPalette, BUILTIN_PALETTE_NAMES},
+    },
+    Nes,
+};
 use sdl2::pixels::PixelFormatEnum;
-use clap::Parser;
+use sdl2::rect::Rect;
+use sdl2::video::FullscreenType;
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::PathBuf,
+    rc::Rc,
+    time::SystemTime,
+};
+use ui::filter::{
+    crt::CrtSettings,
+    ntsc::{NtscFilter, NtscSettings, NTSC_PRESET_NAMES},
+    FilteredImage, ScaleFilter, SCALE_FILTER_NAMES,
+};
+use ui::overscan::{Overscan, OVERSCAN_PRESET_NAMES};
+use ui::recorder::Recorder;
+use ui::screenshot::{timestamped_path, Screenshot};
+use ui::window::{WindowMode, WINDOW_MODE_NAMES};
 
 const SCALE: f32 = 3.0;
 const APPLICATION_NAME: &str = "BabyNES";
+const DEFAULT_SCREENSHOT_DIR: &str = ".";
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Built-in palette name (default, composite, monochrome) or path to a .pal file
+    #[arg(short, long, global = true)]
+    palette: Option<String>,
+
+    /// Config file with the defaults of the options (`key = value` lines of palette, screenshot_dir)
+    #[arg(short, long, global = true)]
+    config: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a ROM
+    Run(Box<RunArgs>),
+
+    /// Write the tiles of CHR-ROM into a PNG file
+    ExtractChr(ExtractChrArgs),
+
+    /// Write tiles edited in a PNG file back into CHR-ROM
+    ImportChr(ImportChrArgs),
+
+    /// Run a ROM and write the 4 name tables into a PNG file
+    DumpNametables {
+        rom_file_path: String,
+
+        /// Frames to run before the dump
+        #[arg(long, default_value_t = 60)]
+        frame: usize,
+
+        /// Path of the PNG file
+        #[arg(short, long, default_value = "nametables.png")]
+        output: String,
+
+        /// Also write the tile indices and palette IDs into a JSON file
+        #[arg(long)]
+        json: Option<String>,
+    },
+
+    /// Print the header, checksums and interrupt vectors of a ROM
+    RomInfo {
+        rom_file_path: String,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Disassemble PRG-ROM as mapped at power-on
+    Disasm {
+        rom_file_path: String,
+
+        /// Start address (ex: C000, $C000). Defaults to the reset vector
+        #[arg(short, long, value_parser = parse_address)]
+        start: Option<u16>,
+
+        /// Count of instructions
+        #[arg(short = 'n', long, default_value_t = 32)]
+        count: usize,
+    },
+
+    /// Run a ROM without a window and log every instruction with the CPU registers
+    Trace {
+        rom_file_path: String,
+
+        /// Count of instructions
+        #[arg(short = 'n', long, default_value_t = 1000)]
+        count: usize,
+
+        /// Write the log into a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct ExtractChrArgs {
+    rom_file_path: String,
+
+    /// Path of the PNG file
+    #[arg(short, long, default_value = "sprites.png")]
+    output: String,
+
+    /// Count of tiles per row of the image
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..))]
+    tiles_per_row: u32,
+
+    /// Palette numbers of the color numbers 0-3 in hex (ex: 0F,16,27,30). Grayscale if omitted
+    #[arg(long, value_parser = parse_palette_numbers, conflicts_with = "capture_frame")]
+    colors: Option<[u8; 4]>,
+
+    /// Run the game for the frames, and take the tiles and the colors at that time
+    #[arg(long)]
+    capture_frame: Option<usize>,
+
+    /// Palette in palette RAM used by --capture-frame (0-3: background, 4-7: sprite)
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..8))]
+    palette_index: u8,
+
+    /// Write an indexed PNG instead of RGBA
+    #[arg(long)]
+    indexed: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct ImportChrArgs {
+    rom_file_path: String,
+
+    /// PNG file laid out like the output of extract-chr
+    image_path: String,
+
+    /// Path of the patched ROM
     #[arg(short, long)]
+    output: String,
+
+    /// Palette numbers of the color numbers 0-3 in hex (ex: 0F,16,27,30). Gray levels if omitted
+    #[arg(long, value_parser = parse_palette_numbers)]
+    colors: Option<[u8; 4]>,
+}
+
+#[derive(clap::Args, Debug)]
+struct RunArgs {
     rom_file_path: String,
+
+    /// Apply the NTSC composite video filter with a preset
+    #[arg(long, value_parser = NTSC_PRESET_NAMES)]
+    ntsc: Option<String>,
+    /// Pixel format of the texture uploaded to SDL
+    #[arg(long, value_parser = PIXEL_FORMAT_NAMES, default_value = "rgb24")]
+    pixel_format: String,
+
+    /// Software filter applied after the NTSC filter
+    #[arg(long, value_parser = SCALE_FILTER_NAMES, default_value = "none")]
+    filter: String,
+
+    /// Darkness of the scanline gaps of the CRT filter (0.0 - 1.0)
+    #[arg(long, default_value_t = 0.5)]
+    scanline_intensity: f32,
+
+    /// Emulate the aperture grille in the CRT filter
+    #[arg(long)]
+    aperture_mask: bool,
+
+    /// How the image is scaled to the window
+    #[arg(long, value_parser = WINDOW_MODE_NAMES, default_value = "stretch")]
+    window_mode: String,
+
+    /// Start in fullscreen (toggle with F11)
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// Overscan preset which decides the lines hidden like a TV
+    #[arg(long, value_parser = OVERSCAN_PRESET_NAMES, default_value = "none")]
+    overscan: String,
+
+    /// Pixels cropped from the top edge (overrides the overscan preset)
+    #[arg(long)]
+    overscan_top: Option<usize>,
+
+    /// Pixels cropped from the bottom edge (overrides the overscan preset)
+    #[arg(long)]
+    overscan_bottom: Option<usize>,
+
+    /// Pixels cropped from the left edge (overrides the overscan preset)
+    #[arg(long)]
+    overscan_left: Option<usize>,
+
+    /// Pixels cropped from the right edge (overrides the overscan preset)
+    #[arg(long)]
+    overscan_right: Option<usize>,
+
+    /// Render the first frame into a PNG file without opening a window
+    #[arg(long, conflicts_with_all = ["screenshot_at", "record"])]
+    png_output: Option<String>,
+
+    /// Directory of the screenshots taken with F12 and the recordings of F9 [default: current directory]
+    #[arg(long)]
+    screenshot_dir: Option<String>,
+
+    /// Take a screenshot at the frame number (1: the first frame)
+    #[arg(long)]
+    screenshot_at: Option<usize>,
+
+    /// Record from the start into the file (y4m, rgb/raw: video + WAV, gif/png: clip). Toggle with F9
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Report accesses to unmapped addresses on the CPU bus
+    #[arg(long)]
+    debug_bus: bool,
+}
+
+impl RunArgs {
+    fn overscan(&self) -> anyhow::Result<Overscan> {
+        let preset = Overscan::preset(&self.overscan).unwrap();
+        Overscan::new(
+            self.overscan_top.unwrap_or(preset.top),
+            self.overscan_bottom.unwrap_or(preset.bottom),
+            self.overscan_left.unwrap_or(preset.left),
+            self.overscan_right.unwrap_or(preset.right),
+        )
+    }
+}
+
+fn to_sdl_pixel_format(format: PixelFormat) -> PixelFormatEnum {
+    match format {
+        PixelFormat::Rgb24 => PixelFormatEnum::RGB24,
+        PixelFormat::Rgba8888 => PixelFormatEnum::RGBA8888,
+        PixelFormat::Argb8888 => PixelFormatEnum::ARGB8888,
+    }
+}
+
+/// Errors on loading a ROM end the process.
+fn exit_with_load_error(rom_file_path: &str, error: impl Display) -> ! {
+    eprintln!("Failed to load {}: {}", rom_file_path, error);
+    std::process::exit(1);
+}
+
+fn print_rom_info(rom_file_path: &str, json: bool) {
+    let info = match RomInfo::load(rom_file_path) {
+        Ok(info) => info,
+        Err(error) => exit_with_load_error(rom_file_path, error),
+    };
+
+    match json {
+        true => println!("{}", info.to_json()),
+        false => println!("{}", info.to_text()),
+    }
+}
+
+fn load_system_palette(palette: Option<&str>) -> SystemPalette {
+    match palette {
+        Some(palette) => SystemPalette::from_name_or_path(palette).unwrap_or_else(|error| {
+            eprintln!("Failed to load the palette {}: {}", palette, error);
+            std::process::exit(1);
+        }),
+        None => SystemPalette::builtin(BUILTIN_PALETTE_NAMES[0]).unwrap(),
+    }
+}
+
+fn chr_image_format(palette: Option<ChrPalette>, indexed: bool) -> ChrImageFormat {
+    match (palette, indexed) {
+        (palette, true) => ChrImageFormat::Indexed(palette.unwrap_or_else(ChrPalette::grayscale)),
+        (Some(palette), false) => ChrImageFormat::Rgba(palette),
+        (None, false) => ChrImageFormat::Grayscale,
+    }
+}
+
+fn extract_chr(args: ExtractChrArgs, palette: Option<String>) {
+    let rom_file_path = &args.rom_file_path;
+    let system_palette = load_system_palette(palette.as_deref());
+
+    let result = match args.capture_frame {
+        // CHR-RAM and palette RAM are filled by the program.
+        Some(frame) => {
+            let mut nes = match Nes::new(rom_file_path) {
+                Ok(nes) => nes,
+                Err(error) => exit_with_load_error(rom_file_path, error),
+            };
+            nes.run_frames(frame);
+
+            let palette_numbers = match args.palette_index {
+                index @ 0..=3 => nes.palette_numbers(index, PaletteType::Background),
+                index => nes.palette_numbers(index - 4, PaletteType::Sprite),
+            };
+            let chr_palette = ChrPalette::from_palette_numbers(&palette_numbers, &system_palette);
+            let format = chr_image_format(Some(chr_palette), args.indexed);
+
+            let mapper = nes.mapper();
+            SpriteExtractor::from_mapper(&*mapper).extract_sprite(
+                &args.output,
+                args.tiles_per_row,
+                &format,
+            )
+        }
+        None => {
+            let cartridge = match Cartridge::load(rom_file_path) {
+                Ok(cartridge) => cartridge,
+                Err(error) => exit_with_load_error(rom_file_path, error),
+            };
+
+            if cartridge.character_rom.is_empty() {
+                eprintln!(
+                    "{} has no CHR-ROM. (CHR-RAM is filled by the program. Try --capture-frame)",
+                    rom_file_path
+                );
+                std::process::exit(1);
+            }
+
+            let chr_palette = args
+                .colors
+                .map(|numbers| ChrPalette::from_palette_numbers(&numbers, &system_palette));
+            let format = chr_image_format(chr_palette, args.indexed);
+
+            SpriteExtractor::new(&cartridge).extract_sprite(
+                &args.output,
+                args.tiles_per_row,
+                &format,
+            )
+        }
+    };
+
+    if let Err(error) = result {
+        eprintln!("Failed to write {}: {}", args.output, error);
+        std::process::exit(1);
+    }
+}
+
+fn import_chr(args: ImportChrArgs, palette: Option<String>) {
+    let rom_file_path = &args.rom_file_path;
+    let binary = match fs::read(rom_file_path) {
+        Ok(binary) => binary,
+        Err(error) => exit_with_load_error(rom_file_path, error),
+    };
+
+    let image = match image::open(&args.image_path) {
+        Ok(image) => image.to_rgba8(),
+        Err(error) => exit_with_load_error(&args.image_path, error),
+    };
+
+    let quantizer = match args.colors {
+        Some(numbers) => {
+            let system_palette = load_system_palette(palette.as_deref());
+            Quantizer::Palette(ChrPalette::from_palette_numbers(&numbers, &system_palette))
+        }
+        None => Quantizer::Grayscale,
+    };
+
+    let patched = match importer::import_chr(&binary, &image, &quantizer) {
+        Ok(patched) => patched,
+        Err(error) => {
+            eprintln!("Failed to import {}: {}", args.image_path, error);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(error) = fs::write(&args.output, patched) {
+        eprintln!("Failed to write {}: {}", args.output, error);
+        std::process::exit(1);
+    }
+}
+
+fn dump_name_tables(
+    rom_file_path: &str,
+    frame: usize,
+    output: &str,
+    json: Option<&str>,
+    palette: Option<String>,
+) {
+    let mut nes = match Nes::new(rom_file_path) {
+        Ok(nes) => nes,
+        Err(error) => exit_with_load_error(rom_file_path, error),
+    };
+    nes.set_system_palette(load_system_palette(palette.as_deref()));
+    nes.run_frames(frame);
+
+    let dump = match nes.dump_name_tables() {
+        Ok(dump) => dump,
+        Err(error) => {
+            eprintln!("Failed to dump the name tables: {}", error);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(error) = name_table_export::save_png(&dump, output) {
+        eprintln!("Failed to write {}: {}", output, error);
+        std::process::exit(1);
+    }
+
+    if let Some(json_path) = json {
+        if let Err(error) = fs::write(json_path, name_table_export::to_json(&dump)) {
+            eprintln!("Failed to write {}: {}", json_path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_disassembly(rom_file_path: &str, start: Option<u16>, count: usize) {
+    let instructions = match Cartridge::load(rom_file_path)
+        .and_then(|cartridge| disassemble_cartridge(&cartridge, start, count))
+    {
+        Ok(instructions) => instructions,
+        Err(error) => exit_with_load_error(rom_file_path, error),
+    };
+
+    for instruction in instructions {
+        println!("{}", instruction);
+    }
+}
+
+fn print_trace(rom_file_path: &str, count: usize, output: Option<&str>) {
+    let mut nes = match Nes::new(rom_file_path) {
+        Ok(nes) => nes,
+        Err(error) => exit_with_load_error(rom_file_path, error),
+    };
+
+    let result = match output {
+        Some(path) => {
+            File::create(path).and_then(|file| trace(&mut nes, count, &mut BufWriter::new(file)))
+        }
+        None => trace(&mut nes, count, &mut io::stdout().lock()),
+    };
+
+    if let Err(error) = result {
+        eprintln!("Failed to write the trace: {}", error);
+        std::process::exit(1);
+    }
 }
 
 fn main() {
@@ -30,42 +476,224 @@ fn main() {
     // Args check
     // ------------------------------------------------------------
     let args = Args::parse();
+
+    let config = match &args.config {
+        Some(path) => Config::load(path).unwrap_or_else(|error| {
+            eprintln!("{:#}", error);
+            std::process::exit(1);
+        }),
+        None => Config::default(),
+    };
+    // Options on the command line take precedence over the config file.
+    let palette = args
+        .palette
+        .or_else(|| config.get("palette").map(str::to_string));
+
+    match args.command {
+        Command::Run(run_args) => run(*run_args, palette, &config),
+        Command::ExtractChr(extract_chr_args) => extract_chr(extract_chr_args, palette),
+        Command::ImportChr(import_chr_args) => import_chr(import_chr_args, palette),
+        Command::DumpNametables {
+            rom_file_path,
+            frame,
+            output,
+            json,
+        } => dump_name_tables(&rom_file_path, frame, &output, json.as_deref(), palette),
+        Command::RomInfo {
+            rom_file_path,
+            json,
+        } => print_rom_info(&rom_file_path, json),
+        Command::Disasm {
+            rom_file_path,
+            start,
+            count,
+        } => print_disassembly(&rom_file_path, start, count),
+        Command::Trace {
+            rom_file_path,
+            count,
+            output,
+        } => print_trace(&rom_file_path, count, output.as_deref()),
+    }
+}
+
+fn run(args: RunArgs, palette: Option<String>, config: &Config) {
     let rom_file_path = &args.rom_file_path;
 
+    // ------------------------------------------------------------
+    // Initialize video filter
+    // ------------------------------------------------------------
+    let mut ntsc_filter = args
+        .ntsc
+        .as_ref()
+        .map(|preset| NtscFilter::new(NtscSettings::preset(preset).unwrap()));
+
+    let crt_settings = CrtSettings {
+        scanline_intensity: args.scanline_intensity,
+        aperture_mask: args.aperture_mask,
+    };
+    let scale_filter = ScaleFilter::from_name(&args.filter, crt_settings).unwrap();
+
+    let overscan = args.overscan().unwrap_or_else(|error| {
+        eprintln!("Invalid overscan: {}", error);
+        std::process::exit(1);
+    });
+
+    let (texture_width, texture_height) = match ntsc_filter {
+        Some(_) => (NtscFilter::OUTPUT_WIDTH, NtscFilter::OUTPUT_HEIGHT),
+        None => (Frame::WIDTH, Frame::HIGHT),
+    };
+    let (texture_width, texture_height) = overscan.cropped_size(texture_width, texture_height);
+    let (texture_width, texture_height) = scale_filter.output_size(texture_width, texture_height);
+
+    let pixel_format = PixelFormat::from_name(&args.pixel_format).unwrap();
+
+    // Crop the overscan before scaling, so that the hidden edges do not leak into the image.
+    let mut apply_filters = move |frame: &Frame| {
+        let image = match &mut ntsc_filter {
+            Some(filter) => filter.apply(frame),
+            None => FilteredImage::from_frame(frame),
+        };
+        scale_filter.apply(overscan.crop(&image))
+    };
+
+    // ------------------------------------------------------------
+    // Initialize NES
+    // ------------------------------------------------------------
+    let mut nes = match Nes::new(rom_file_path) {
+        Ok(nes) => nes,
+        Err(error) => exit_with_load_error(rom_file_path, error),
+    };
+
+    nes.set_bus_diagnostics(args.debug_bus);
+
+    nes.set_system_palette(load_system_palette(palette.as_deref()));
+
+    // ------------------------------------------------------------
+    // Headless
+    // ------------------------------------------------------------
+    if let Some(png_output) = args.png_output {
+        // Set by the first frame. The emulation stops after it, so that the battery is saved.
+        let saved = Rc::new(RefCell::new(None));
+
+        let render_saved = Rc::clone(&saved);
+        let render_png_output = png_output.clone();
+        let render_callback = move |frame: &Frame| {
+            let mut saved = render_saved.borrow_mut();
+            if saved.is_none() {
+                *saved = Some(apply_filters(frame).save_png(&render_png_output));
+            }
+        };
+        let input_callback = || saved.borrow().is_none();
+
+        nes.run(render_callback, input_callback);
+
+        if let Some(Err(error)) = saved.take() {
+            eprintln!("Failed to save {}: {}", png_output, error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // ------------------------------------------------------------
     // Initialize UI
     // ------------------------------------------------------------
-    let window_width = (nes::ppu::frame::Frame::WIDTH as f32 * SCALE) as u32;
-    let window_height = (nes::ppu::frame::Frame::HIGHT as f32 * SCALE) as u32;
+    let window_mode = WindowMode::from_name(&args.window_mode).unwrap();
+    let (window_width, window_height) = window_mode.window_size(overscan.visible_size(), SCALE);
 
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
-    let window = video_subsystem
+    let mut window = video_subsystem
         .window(APPLICATION_NAME, window_width, window_height)
         .position_centered()
+        .resizable()
         .build()
         .unwrap();
 
-    let mut canvas = window.into_canvas().present_vsync().build().unwrap();
+    if args.fullscreen {
+        window.set_fullscreen(FullscreenType::Desktop).unwrap();
+    }
+
+    // Shared with the input callback to toggle fullscreen.
+    let canvas = Rc::new(RefCell::new(
+        window.into_canvas().present_vsync().build().unwrap(),
+    ));
     let mut event_pump = sdl_context.event_pump().unwrap();
-    canvas.set_scale(SCALE, SCALE).unwrap();
 
-    let creator = canvas.texture_creator();
+    let creator = canvas.borrow().texture_creator();
     let mut texture = creator
-        .create_texture_target(PixelFormatEnum::RGB24, 256, 240)
+        .create_texture_target(
+            to_sdl_pixel_format(pixel_format),
+            texture_width as u32,
+            texture_height as u32,
+        )
         .unwrap();
 
-    // ------------------------------------------------------------
-    // Initialize NES
-    // ------------------------------------------------------------
-    let mut nes = Nes::new(rom_file_path).unwrap();
+    // The screenshot is the frame before the filters.
+    let screenshot_dir = args
+        .screenshot_dir
+        .as_deref()
+        .or_else(|| config.get("screenshot_dir"))
+        .unwrap_or(DEFAULT_SCREENSHOT_DIR);
+    let screenshot = Screenshot::new(screenshot_dir);
+    let record_dir = PathBuf::from(screenshot_dir);
+    // Set by F12, and the next frame is saved.
+    let screenshot_requested = Rc::new(Cell::new(false));
+    let screenshot_at = args.screenshot_at;
+    let mut frame_number = 0;
 
+    // Every emulated frame is recorded in the render callback, so frames are not dropped.
+    let recorder = Rc::new(RefCell::new(None));
+    if let Some(path) = &args.record {
+        match Recorder::start(path) {
+            Ok(started) => *recorder.borrow_mut() = Some(started),
+            Err(error) => {
+                eprintln!("Failed to start the recording: {}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let render_canvas = Rc::clone(&canvas);
+    let render_screenshot_requested = Rc::clone(&screenshot_requested);
+    let render_recorder = Rc::clone(&recorder);
     let render_callback = move |frame: &Frame| {
-        texture.update(None, &frame.data, 256 * 3).unwrap();
-        canvas.copy(&texture, None, None).unwrap();
+        frame_number += 1;
+        if render_screenshot_requested.replace(false) || screenshot_at == Some(frame_number) {
+            match screenshot.save(frame) {
+                Ok(path) => println!("Saved the screenshot to {}", path.display()),
+                Err(error) => eprintln!("Failed to save the screenshot: {}", error),
+            }
+        }
+
+        let mut recorder = render_recorder.borrow_mut();
+        if let Some(active) = recorder.as_mut() {
+            // The APU is not emulated yet, so the audio track is silent.
+            if let Err(error) = active.record_frame(frame, &[]) {
+                eprintln!("Failed to record the frame: {}", error);
+                *recorder = None;
+            } else if active.is_full() {
+                stop_recording(&mut recorder);
+            }
+        }
+
+        let image = apply_filters(frame);
+
+        let pixels = image.to_pixel_format(pixel_format);
+        texture
+            .update(None, &pixels, image.pitch(pixel_format))
+            .unwrap();
+
+        let mut canvas = render_canvas.borrow_mut();
+        let (output_width, output_height) = canvas.output_size().unwrap();
+        let viewport = window_mode.viewport(overscan.visible_size(), output_width, output_height);
+        let destination = Rect::new(viewport.x, viewport.y, viewport.width, viewport.height);
+
+        canvas.clear();
+        canvas.copy(&texture, None, destination).unwrap();
         canvas.present();
     };
 
+    // Returns false to quit. The battery-backed save is written when the emulation stops.
     let input_callback = || {
         for event in event_pump.poll_iter() {
             match event {
@@ -73,11 +701,67 @@ fn main() {
                 | Event::KeyDown {
                     keycode: Some(Keycode::Escape),
                     ..
-                } => std::process::exit(0),
+                } => return false,
+                Event::KeyDown {
+                    keycode: Some(Keycode::F11),
+                    repeat: false,
+                    ..
+                } => {
+                    let mut canvas = canvas.borrow_mut();
+                    let window = canvas.window_mut();
+                    let fullscreen = match window.fullscreen_state() {
+                        FullscreenType::Off => FullscreenType::Desktop,
+                        _ => FullscreenType::Off,
+                    };
+                    window.set_fullscreen(fullscreen).unwrap();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F12),
+                    repeat: false,
+                    ..
+                } => screenshot_requested.set(true),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F9),
+                    repeat: false,
+                    ..
+                } => {
+                    let mut recorder = recorder.borrow_mut();
+                    match recorder.is_some() {
+                        true => stop_recording(&mut recorder),
+                        false => {
+                            let path = timestamped_path(&record_dir, SystemTime::now(), "y4m");
+                            match Recorder::start(&path) {
+                                Ok(started) => {
+                                    println!("Recording to {}", path.display());
+                                    *recorder = Some(started);
+                                }
+                                Err(error) => eprintln!("Failed to start the recording: {}", error),
+                            }
+                        }
+                    }
+                }
                 _ => { /* do nothing */ }
             }
         }
+        true
     };
 
-    nes.run(render_callback, input_callback)
+    nes.run(render_callback, input_callback);
+    stop_recording(&mut recorder.borrow_mut());
+}
+
+fn stop_recording(recorder: &mut Option<Recorder>) {
+    if let Some(finished) = recorder.take() {
+        let frame_count = finished.frame_count();
+        match finished.finish() {
+            Ok(paths) => {
+                let paths: Vec<String> = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                println!("Saved {} frames to {}", frame_count, paths.join(", "));
+            }
+            Err(error) => eprintln!("Failed to save the recording: {}", error),
+        }
+    }
 }
@@ -0,0 +1,105 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+
+/* Battery-backed save
+https://www.nesdev.org/wiki/Battery
+
+Cartridges with a battery keep the contents of PRG-RAM ($6000-$7FFF) after the power is off.
+The contents are saved to a .sav file next to the ROM file. (ex: game.nes -> game.sav)
+*/
+pub struct BatterySave {
+    path: PathBuf,
+    /// Contents of the last load or save, to skip writing the same data.
+    last_saved: Vec<u8>,
+}
+
+impl BatterySave {
+    pub fn new<P: AsRef<Path>>(rom_path: P) -> Self {
+        BatterySave {
+            path: rom_path.as_ref().with_extension("sav"),
+            last_saved: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// None if nothing has been saved yet.
+    pub fn load(&mut self) -> Result<Option<Vec<u8>>> {
+        match fs::read(&self.path) {
+            Ok(data) => {
+                self.last_saved = data.clone();
+                Ok(Some(data))
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn save(&mut self, data: &[u8]) -> Result<()> {
+        if data == self.last_saved.as_slice() {
+            return Ok(());
+        }
+
+        fs::write(&self.path, data)?;
+        self.last_saved = data.to_vec();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod battery_save_test {
+    use std::env;
+
+    use super::*;
+
+    fn temp_rom_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("baby_nes_{}_{}.nes", name, std::process::id()))
+    }
+
+    #[test]
+    fn path_test() {
+        let save = BatterySave::new("roms/game.nes");
+        assert_eq!(save.path(), Path::new("roms/game.sav"));
+    }
+
+    #[test]
+    fn load_not_exist_test() {
+        let mut save = BatterySave::new(temp_rom_path("not_exist"));
+        assert_eq!(save.load().unwrap(), None);
+    }
+
+    #[test]
+    fn save_and_load_test() {
+        let rom_path = temp_rom_path("save_and_load");
+        let mut save = BatterySave::new(&rom_path);
+        save.save(&[0x01, 0x02, 0x03]).unwrap();
+
+        let mut reloaded = BatterySave::new(&rom_path);
+        assert_eq!(reloaded.load().unwrap(), Some(vec![0x01, 0x02, 0x03]));
+
+        fs::remove_file(save.path()).unwrap();
+    }
+
+    #[test]
+    fn skip_same_data_test() {
+        let rom_path = temp_rom_path("skip_same_data");
+        let mut save = BatterySave::new(&rom_path);
+        save.save(&[0x01]).unwrap();
+        fs::remove_file(save.path()).unwrap();
+
+        // Nothing changed since the last save.
+        save.save(&[0x01]).unwrap();
+        assert!(!save.path().exists());
+
+        save.save(&[0x02]).unwrap();
+        assert!(save.path().exists());
+
+        fs::remove_file(save.path()).unwrap();
+    }
+}
@@ -0,0 +1,142 @@
+use std::{error::Error, fmt, io, path::Path};
+
+/// Errors on loading a ROM file. The messages are shown to the user as they are.
+#[derive(Debug, PartialEq)]
+pub enum RomError {
+    NotFound(String),
+    /// The file exists, but can not be read. (ex: permission denied)
+    Unreadable {
+        path: String,
+        reason: String,
+    },
+    TooSmall {
+        minimum: usize,
+        actual: usize,
+    },
+    BadMagic,
+    /// Sizes of PRG-ROM, CHR-ROM and the trainer in the header do not match the file.
+    SizeMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// The header declares no PRG-ROM, so the CPU has nothing to run.
+    NoProgramRom,
+    /// PRG-ROM is smaller than a bank of the mapper.
+    ProgramRomTooSmall {
+        mapper: u16,
+        minimum: usize,
+        actual: usize,
+    },
+    UnsupportedMapper(u16),
+    UnsupportedNes2Feature(String),
+    /// The board of the mapper has no PRG-RAM to load the trainer into.
+    UnsupportedTrainer(u16),
+}
+
+impl RomError {
+    pub fn from_io<P: AsRef<Path>>(path: P, error: io::Error) -> Self {
+        let path = path.as_ref().display().to_string();
+        match error.kind() {
+            io::ErrorKind::NotFound => RomError::NotFound(path),
+            _ => RomError::Unreadable {
+                path,
+                reason: error.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomError::NotFound(path) => write!(f, "ROM file not found: {}", path),
+            RomError::Unreadable { path, reason } => {
+                write!(f, "can not read ROM file {}: {}", path, reason)
+            }
+            RomError::TooSmall { minimum, actual } => write!(
+                f,
+                "ROM is too small: {} bytes (must be at least {} bytes)",
+                actual, minimum
+            ),
+            RomError::BadMagic => write!(f, "not an iNES ROM: invalid magic bytes"),
+            RomError::SizeMismatch { expected, actual } => write!(
+                f,
+                "ROM size mismatch: the header expects {} bytes, but the file has {} bytes",
+                expected, actual
+            ),
+            RomError::NoProgramRom => write!(f, "the header declares no PRG-ROM"),
+            RomError::ProgramRomTooSmall {
+                mapper,
+                minimum,
+                actual,
+            } => write!(
+                f,
+                "PRG-ROM is too small for mapper {}: {} bytes (must be at least {} bytes)",
+                mapper, actual, minimum
+            ),
+            RomError::UnsupportedMapper(number) => write!(f, "unsupported mapper: {}", number),
+            RomError::UnsupportedNes2Feature(feature) => {
+                write!(f, "unsupported NES 2.0 feature: {}", feature)
+            }
+            RomError::UnsupportedTrainer(number) => write!(
+                f,
+                "unsupported trainer: mapper {} has no PRG-RAM at $7000",
+                number
+            ),
+        }
+    }
+}
+
+impl Error for RomError {}
+
+#[cfg(test)]
+mod rom_error_test {
+    use super::*;
+
+    #[test]
+    fn from_io_test() {
+        let error = io::Error::new(io::ErrorKind::NotFound, "not found");
+        assert_eq!(
+            RomError::from_io("game.nes", error),
+            RomError::NotFound("game.nes".to_string())
+        );
+
+        let error = io::Error::new(io::ErrorKind::PermissionDenied, "permission denied");
+        assert_eq!(
+            RomError::from_io("game.nes", error),
+            RomError::Unreadable {
+                path: "game.nes".to_string(),
+                reason: "permission denied".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn display_test() {
+        assert_eq!(
+            RomError::SizeMismatch {
+                expected: 40976,
+                actual: 40975
+            }
+            .to_string(),
+            "ROM size mismatch: the header expects 40976 bytes, but the file has 40975 bytes"
+        );
+        assert_eq!(
+            RomError::UnsupportedMapper(15).to_string(),
+            "unsupported mapper: 15"
+        );
+        assert_eq!(
+            RomError::ProgramRomTooSmall {
+                mapper: 4,
+                minimum: 16384,
+                actual: 8192
+            }
+            .to_string(),
+            "PRG-ROM is too small for mapper 4: 8192 bytes (must be at least 16384 bytes)"
+        );
+        assert_eq!(
+            RomError::UnsupportedTrainer(2).to_string(),
+            "unsupported trainer: mapper 2 has no PRG-RAM at $7000"
+        );
+    }
+}
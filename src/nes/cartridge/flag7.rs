@@ -0,0 +1,70 @@
+use super::header::ConsoleType;
+
+/* Flag7 format
+https://www.nesdev.org/wiki/INES#Flags_7
+https://www.nesdev.org/wiki/NES_2.0#Header
+
+76543210
+||||||||
+||||||++- Console type: 0: NES/Famicom, 1: VS System, 2: PlayChoice-10, 3: Extended (NES 2.0 only)
+||||++--- If equal to 2, flags 8-15 are in NES 2.0 format
+++++----- Upper nybble of mapper number
+ */
+pub struct Flag7 {
+    pub upper_mapper_number: u8,
+    pub is_nes2: bool,
+    pub console_type: ConsoleType,
+}
+
+impl Flag7 {
+    pub fn parse(byte: &u8) -> Self {
+        let is_nes2 = byte & 0b00001100 == 0b00001000;
+
+        let console_type = match byte & 0b00000011 {
+            0 => ConsoleType::Nes,
+            1 => ConsoleType::VsSystem,
+            2 => ConsoleType::Playchoice10,
+            // The type is in byte 13 of NES 2.0 headers.
+            _ => ConsoleType::Extended(0),
+        };
+
+        let upper_mapper_number = byte >> 4;
+
+        Self {
+            upper_mapper_number,
+            is_nes2,
+            console_type,
+        }
+    }
+}
+
+#[cfg(test)]
+mod flag7 {
+    use crate::nes::cartridge::{flag7::Flag7, header::ConsoleType};
+
+    #[test]
+    fn bit0_1_console_type() {
+        assert_eq!(Flag7::parse(&0b00000000).console_type, ConsoleType::Nes);
+        assert_eq!(
+            Flag7::parse(&0b00000001).console_type,
+            ConsoleType::VsSystem
+        );
+        assert_eq!(
+            Flag7::parse(&0b00000010).console_type,
+            ConsoleType::Playchoice10
+        );
+    }
+
+    #[test]
+    fn bit2_3_nes2() {
+        assert!(Flag7::parse(&0b00001000).is_nes2);
+        assert!(!Flag7::parse(&0b00000100).is_nes2);
+        assert!(!Flag7::parse(&0b00001100).is_nes2);
+    }
+
+    #[test]
+    fn bit4_7_upper_mapper_number() {
+        let flag7 = Flag7::parse(&0b10100000);
+        assert_eq!(flag7.upper_mapper_number, 0b1010);
+    }
+}
@@ -1,6 +1,9 @@
-use anyhow::{ensure, Result};
-
-use crate::nes::cartridge::flag6::Flag6;
+use crate::nes::{
+    cartridge::{
+        error::RomError, flag6::Flag6, flag7::Flag7, CHARACTER_UNIT_SIZE, PROGRAM_UNIT_SIZE,
+    },
+    mapper::character_memory::DEFAULT_CHARACTER_RAM_SIZE,
+};
 
 /* Header format
 https://www.nesdev.org/wiki/INES
@@ -15,52 +18,270 @@ https://archive.nesdev.org/NESHDRJ.TXT
 9: Flags 9 - TV system (rarely used extension)
 10: Flags 10 - TV system, PRG-RAM presence (unofficial, rarely used extension)
 11-15: Unused padding (should be filled with zero, but some rippers put their name across bytes 7-15)
+
+Headers which have something in bytes 12-15 are treated as archaic iNES, and bytes 7-15 are ignored.
+*/
+
+/* NES 2.0 header format
+https://www.nesdev.org/wiki/NES_2.0
+
+The header is NES 2.0 if bits 2-3 of Flags 7 are %10.
+
+8: Mapper MSB (bits 0-3), Submapper (bits 4-7)
+9: PRG-ROM size MSB (bits 0-3), CHR-ROM size MSB (bits 4-7)
+10: PRG-RAM shift count (bits 0-3), PRG-NVRAM shift count (bits 4-7)
+11: CHR-RAM shift count (bits 0-3), CHR-NVRAM shift count (bits 4-7)
+12: CPU/PPU timing (bits 0-1)
+13: VS System type, or Extended console type (bits 0-3)
+14: Number of miscellaneous ROMs (bits 0-1)
+15: Default expansion device (bits 0-5)
+
+ROM size: if the MSB is $F, the size is 2^E * (MM * 2 + 1) bytes. (LSB = EEEEEEMM)
+RAM size: 64 << shift count bytes. (0 = none)
 */
 
 /// 4 byte strings, N E S (EOF)
 pub const MAGIC_BYTES: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
 
+const PROGRAM_RAM_UNIT_SIZE: usize = 8192; // 8192 byte
+
 #[derive(Debug, PartialEq)]
 pub struct INesHeader {
     pub magic_bytes: [u8; 4],
-    pub program_rom_size: u8,
-    pub character_rom_size: u8,
-    pub mapper_number: u8,
+    pub format: HeaderFormat,
+    /// Size of PRG ROM in 16 KB units. (12 bits in NES 2.0)
+    pub program_rom_size: u16,
+    /// Size of CHR ROM in 8 KB units. (12 bits in NES 2.0)
+    pub character_rom_size: u16,
+    /// Size of PRG RAM in 8 KB units. (Value 0 infers 8 KB for compatibility, iNES only)
+    pub program_ram_size: u8,
+    pub mapper_number: u16,
+    /// 0 unless NES 2.0.
+    pub submapper_number: u8,
     pub mirroring: MirroringType,
     pub has_battery_backup: bool,
     pub has_trainer: bool,
     pub four_screen_mode: bool,
+    pub console_type: ConsoleType,
+    pub timing: TimingMode,
+    /// Shift counts of RAM sizes in NES 2.0 headers. (64 << shift count bytes)
+    pub program_ram_shift: u8,
+    pub program_nvram_shift: u8,
+    pub character_ram_shift: u8,
+    pub character_nvram_shift: u8,
+    pub miscellaneous_rom_count: u8,
+    pub default_expansion_device: u8,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HeaderFormat {
+    /// iNES with bytes 7-15 filled by rippers.
+    ArchaicINes,
+    INes,
+    Nes2,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ConsoleType {
+    Nes,
+    VsSystem,
+    Playchoice10,
+    /// Extended console type in byte 13 of NES 2.0 headers.
+    Extended(u8),
+}
+
+/* CPU/PPU timing
+https://www.nesdev.org/wiki/NES_2.0#CPU/PPU_Timing
+*/
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TimingMode {
+    /// RP2C02 (North America, Japan)
+    Ntsc,
+    /// RP2C07 (Europe, Australia)
+    Pal,
+    /// Works on both NTSC and PAL.
+    MultipleRegion,
+    /// UMC 6527P (Russia)
+    Dendy,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum MirroringType {
     Horizontal,
     Vertical,
+    SingleScreenA,
+    SingleScreenB,
+    FourScreen,
 }
 
 impl INesHeader {
-    pub fn new(binary: &[u8]) -> Result<Self> {
-        ensure!(binary.len() >= 7, "binary must be over than 7 bytes");
-        ensure!(binary[0..=3] == MAGIC_BYTES.to_vec(), "invalid magic bytes");
+    pub fn new(binary: &[u8]) -> Result<Self, RomError> {
+        if binary.len() < 7 {
+            return Err(RomError::TooSmall {
+                minimum: 7,
+                actual: binary.len(),
+            });
+        }
+        if binary[0..=3] != MAGIC_BYTES {
+            return Err(RomError::BadMagic);
+        }
+
+        let byte = |index: usize| binary.get(index).copied().unwrap_or(0);
 
         let flag6 = Flag6::parse(&binary[6]);
+        let flag7 = Flag7::parse(&byte(7));
 
-        Ok(Self {
+        let format = match flag7.is_nes2 {
+            true => HeaderFormat::Nes2,
+            false if (12..=15).any(|index| byte(index) != 0) => HeaderFormat::ArchaicINes,
+            false => HeaderFormat::INes,
+        };
+
+        let mut header = Self {
             magic_bytes: MAGIC_BYTES,
-            program_rom_size: binary[4],
-            character_rom_size: binary[5],
-            mapper_number: flag6.lower_mapper_number,
+            format,
+            program_rom_size: binary[4] as u16,
+            character_rom_size: binary[5] as u16,
+            program_ram_size: 0,
+            mapper_number: flag6.lower_mapper_number as u16,
+            submapper_number: 0,
             mirroring: flag6.mirroring,
             has_battery_backup: flag6.has_battery_backup,
             has_trainer: flag6.has_trainer,
             four_screen_mode: flag6.four_screen_mode,
-        })
+            console_type: ConsoleType::Nes,
+            timing: TimingMode::Ntsc,
+            program_ram_shift: 0,
+            program_nvram_shift: 0,
+            character_ram_shift: 0,
+            character_nvram_shift: 0,
+            miscellaneous_rom_count: 0,
+            default_expansion_device: 0,
+        };
+
+        match format {
+            HeaderFormat::ArchaicINes => {}
+            HeaderFormat::INes => {
+                header.mapper_number |= (flag7.upper_mapper_number as u16) << 4;
+                header.console_type = flag7.console_type;
+                header.program_ram_size = byte(8);
+                header.timing = match byte(9) & 0b00000001 {
+                    0 => TimingMode::Ntsc,
+                    _ => TimingMode::Pal,
+                };
+            }
+            HeaderFormat::Nes2 => {
+                header.mapper_number |=
+                    (flag7.upper_mapper_number as u16) << 4 | ((byte(8) & 0b00001111) as u16) << 8;
+                header.submapper_number = byte(8) >> 4;
+                header.program_rom_size |= ((byte(9) & 0b00001111) as u16) << 8;
+                header.character_rom_size |= ((byte(9) >> 4) as u16) << 8;
+                header.program_ram_shift = byte(10) & 0b00001111;
+                header.program_nvram_shift = byte(10) >> 4;
+                header.character_ram_shift = byte(11) & 0b00001111;
+                header.character_nvram_shift = byte(11) >> 4;
+                header.timing = match byte(12) & 0b00000011 {
+                    0 => TimingMode::Ntsc,
+                    1 => TimingMode::Pal,
+                    2 => TimingMode::MultipleRegion,
+                    _ => TimingMode::Dendy,
+                };
+                header.console_type = match flag7.console_type {
+                    ConsoleType::Extended(_) => ConsoleType::Extended(byte(13) & 0b00001111),
+                    console_type => console_type,
+                };
+                header.miscellaneous_rom_count = byte(14) & 0b00000011;
+                header.default_expansion_device = byte(15) & 0b00111111;
+            }
+        }
+
+        Ok(header)
+    }
+
+    pub fn is_nes2(&self) -> bool {
+        self.format == HeaderFormat::Nes2
+    }
+
+    pub fn program_rom_byte_size(&self) -> usize {
+        rom_byte_size(self.program_rom_size, PROGRAM_UNIT_SIZE)
+    }
+
+    pub fn character_rom_byte_size(&self) -> usize {
+        rom_byte_size(self.character_rom_size, CHARACTER_UNIT_SIZE)
+    }
+
+    /// PRG-RAM including the battery-backed one.
+    pub fn program_ram_byte_size(&self) -> usize {
+        match self.is_nes2() {
+            true => {
+                shift_byte_size(self.program_ram_shift) + shift_byte_size(self.program_nvram_shift)
+            }
+            false => PROGRAM_RAM_UNIT_SIZE * self.program_ram_size.max(1) as usize,
+        }
+    }
+
+    /// Battery-backed PRG-RAM. iNES headers can not tell it apart, so all PRG-RAM is assumed.
+    pub fn program_nvram_byte_size(&self) -> usize {
+        match (self.is_nes2(), self.has_battery_backup) {
+            (true, _) => shift_byte_size(self.program_nvram_shift),
+            (false, true) => self.program_ram_byte_size(),
+            (false, false) => 0,
+        }
+    }
+
+    /// iNES: 8KB CHR-RAM only if the cartridge has no CHR-ROM.
+    /// NES 2.0: the size in the header, including the battery-backed one.
+    pub fn character_ram_byte_size(&self) -> usize {
+        match self.is_nes2() {
+            true => {
+                shift_byte_size(self.character_ram_shift)
+                    + shift_byte_size(self.character_nvram_shift)
+            }
+            false if self.character_rom_size == 0 => DEFAULT_CHARACTER_RAM_SIZE,
+            false => 0,
+        }
+    }
+
+    /// Mirroring applied to the nametables at power-on.
+    /// Four-screen boards ignore the mirroring bit and provide extra VRAM instead.
+    pub fn nametable_mirroring(&self) -> MirroringType {
+        if self.four_screen_mode {
+            MirroringType::FourScreen
+        } else {
+            self.mirroring
+        }
+    }
+}
+
+/// `size` is in `unit` bytes, or in the exponent-multiplier form if the MSB is $F.
+/// Too large sizes are saturated, and fail the size check of the cartridge.
+fn rom_byte_size(size: u16, unit: usize) -> usize {
+    match size >> 8 {
+        0x0F => {
+            let exponent = ((size >> 2) & 0b00111111) as u32;
+            let multiplier = (size & 0b00000011) as usize * 2 + 1;
+            1usize
+                .checked_shl(exponent)
+                .and_then(|size| size.checked_mul(multiplier))
+                .unwrap_or(usize::MAX)
+        }
+        _ => unit * size as usize,
+    }
+}
+
+/// 64 << shift count bytes. (0 = none)
+fn shift_byte_size(shift: u8) -> usize {
+    match shift {
+        0 => 0,
+        shift => 64 << shift,
     }
 }
 
 #[cfg(test)]
 mod ines_header_tests {
-    use crate::nes::cartridge::header::{MirroringType, MAGIC_BYTES};
+    use crate::nes::cartridge::header::{
+        ConsoleType, HeaderFormat, MirroringType, TimingMode, MAGIC_BYTES,
+    };
     use std::vec;
 
     use super::INesHeader;
@@ -82,17 +303,247 @@ mod ines_header_tests {
             header,
             INesHeader {
                 magic_bytes: MAGIC_BYTES,
+                format: HeaderFormat::INes,
                 program_rom_size: 3,
                 character_rom_size: 2,
+                program_ram_size: 0,
                 mapper_number: 1,
+                submapper_number: 0,
                 mirroring: MirroringType::Horizontal,
                 has_battery_backup: true,
                 has_trainer: false,
                 four_screen_mode: true,
+                console_type: ConsoleType::Nes,
+                timing: TimingMode::Ntsc,
+                program_ram_shift: 0,
+                program_nvram_shift: 0,
+                character_ram_shift: 0,
+                character_nvram_shift: 0,
+                miscellaneous_rom_count: 0,
+                default_expansion_device: 0,
             }
         );
     }
 
+    #[test]
+    fn nametable_mirroring_test() {
+        let mut binary = vec![
+            MAGIC_BYTES[0],
+            MAGIC_BYTES[1],
+            MAGIC_BYTES[2],
+            MAGIC_BYTES[3],
+            3,
+            2,
+            0b00000001,
+        ];
+
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.nametable_mirroring(), MirroringType::Vertical);
+
+        // four screen mode overrides the mirroring bit.
+        binary[6] = 0b00001001;
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.nametable_mirroring(), MirroringType::FourScreen);
+    }
+
+    #[test]
+    fn program_ram_size_test() {
+        let mut binary = vec![
+            MAGIC_BYTES[0],
+            MAGIC_BYTES[1],
+            MAGIC_BYTES[2],
+            MAGIC_BYTES[3],
+            3,
+            2,
+            0b00000000,
+            0b00000000,
+            0,
+        ];
+
+        // 0 infers 8KB.
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.program_ram_byte_size(), 0x2000);
+
+        binary[8] = 4;
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.program_ram_size, 4);
+        assert_eq!(header.program_ram_byte_size(), 0x8000);
+    }
+
+    #[test]
+    fn character_ram_size_test() {
+        let mut binary = vec![
+            MAGIC_BYTES[0],
+            MAGIC_BYTES[1],
+            MAGIC_BYTES[2],
+            MAGIC_BYTES[3],
+            3,
+            0,
+            0b00000000,
+            0b00000000,
+            0,
+            0,
+            0,
+            0b00000111,
+        ];
+
+        // iNES without CHR-ROM has 8KB CHR-RAM. Byte 11 is ignored.
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.character_ram_byte_size(), 0x2000);
+
+        binary[5] = 2;
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.character_ram_byte_size(), 0);
+
+        // NES 2.0: 64 << 7 = 8KB.
+        binary[7] = 0b00001000;
+        let header = INesHeader::new(&binary).unwrap();
+        assert!(header.is_nes2());
+        assert_eq!(header.character_ram_byte_size(), 0x2000);
+
+        binary[11] = 0b00001001;
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.character_ram_byte_size(), 0x8000);
+
+        binary[11] = 0;
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.character_ram_byte_size(), 0);
+    }
+
+    #[test]
+    fn ines_header_test() {
+        let binary = vec![
+            MAGIC_BYTES[0],
+            MAGIC_BYTES[1],
+            MAGIC_BYTES[2],
+            MAGIC_BYTES[3],
+            2,
+            1,
+            0b01000000,
+            0b00010001,
+            2,
+            0b00000001,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        ];
+
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.format, HeaderFormat::INes);
+        assert_eq!(header.mapper_number, 0x14);
+        assert_eq!(header.console_type, ConsoleType::VsSystem);
+        assert_eq!(header.timing, TimingMode::Pal);
+        assert_eq!(header.program_rom_byte_size(), 0x8000);
+        assert_eq!(header.character_rom_byte_size(), 0x2000);
+        assert_eq!(header.program_ram_byte_size(), 0x4000);
+    }
+
+    #[test]
+    fn archaic_ines_header_test() {
+        // "DiskDude!" is put across bytes 7-15.
+        let binary = [
+            vec![
+                MAGIC_BYTES[0],
+                MAGIC_BYTES[1],
+                MAGIC_BYTES[2],
+                MAGIC_BYTES[3],
+                2,
+                1,
+                0b01000000,
+            ],
+            b"DiskDude!".to_vec(),
+        ]
+        .concat();
+
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.format, HeaderFormat::ArchaicINes);
+        assert_eq!(header.mapper_number, 4);
+        assert_eq!(header.console_type, ConsoleType::Nes);
+        assert_eq!(header.program_ram_size, 0);
+    }
+
+    #[test]
+    fn nes2_header_test() {
+        let binary = vec![
+            MAGIC_BYTES[0],
+            MAGIC_BYTES[1],
+            MAGIC_BYTES[2],
+            MAGIC_BYTES[3],
+            0x00,
+            0x20,
+            0b01000010,
+            0b00101011,
+            0b00110001,
+            0b00000001,
+            0b01110000,
+            0b00000111,
+            0b00000011,
+            0b00000101,
+            0b00000001,
+            0b00000010,
+        ];
+
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.format, HeaderFormat::Nes2);
+        assert_eq!(header.mapper_number, 0x124);
+        assert_eq!(header.submapper_number, 3);
+        assert_eq!(header.program_rom_byte_size(), 0x100 * 0x4000);
+        assert_eq!(header.character_rom_byte_size(), 0x20 * 0x2000);
+        assert_eq!(header.program_ram_byte_size(), 0x2000);
+        assert_eq!(header.program_nvram_byte_size(), 0x2000);
+        assert_eq!(header.character_ram_byte_size(), 0x2000);
+        assert_eq!(header.timing, TimingMode::Dendy);
+        assert_eq!(header.console_type, ConsoleType::Extended(5));
+        assert_eq!(header.miscellaneous_rom_count, 1);
+        assert_eq!(header.default_expansion_device, 2);
+    }
+
+    #[test]
+    fn nes2_exponent_multiplier_rom_size_test() {
+        let binary = vec![
+            MAGIC_BYTES[0],
+            MAGIC_BYTES[1],
+            MAGIC_BYTES[2],
+            MAGIC_BYTES[3],
+            // 2^4 * (1 * 2 + 1) = 48 bytes
+            0b00010001,
+            // 2^10 * 1 = 1KB
+            0b00101000,
+            0b00000000,
+            0b00001000,
+            0,
+            0xFF,
+        ];
+
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.program_rom_byte_size(), 48);
+        assert_eq!(header.character_rom_byte_size(), 0x400);
+    }
+
+    #[test]
+    fn program_nvram_size_test() {
+        let mut binary = vec![
+            MAGIC_BYTES[0],
+            MAGIC_BYTES[1],
+            MAGIC_BYTES[2],
+            MAGIC_BYTES[3],
+            2,
+            1,
+            0b00000000,
+        ];
+
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.program_nvram_byte_size(), 0);
+
+        // iNES assumes all PRG-RAM is battery-backed.
+        binary[6] = 0b00000010;
+        let header = INesHeader::new(&binary).unwrap();
+        assert_eq!(header.program_nvram_byte_size(), 0x2000);
+    }
+
     #[test]
     fn fails_create_if_less_than_7_bytes() {
         let binary = vec![
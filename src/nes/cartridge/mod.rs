@@ -1,53 +1,99 @@
+pub mod battery;
+pub mod error;
 mod flag6;
+mod flag7;
 pub mod header;
 
-use anyhow::Result;
+use std::{fs, path::Path};
+
+use error::RomError;
 use header::INesHeader;
 
 const PROGRAM_UNIT_SIZE: usize = 16384; // 16384 byte
 const CHARACTER_UNIT_SIZE: usize = 8192; // 8192 byte
+const HEADER_SIZE: usize = 16; // 16 byte
+const TRAINER_SIZE: usize = 512; // 512 byte
 
 #[derive(Debug, PartialEq)]
 pub struct Cartridge {
     pub header: INesHeader,
+    /// Copied to $7000-$71FF at power-on. It is placed between the header and PRG-ROM.
+    pub trainer: Option<Vec<u8>>,
     pub program_rom: Vec<u8>,
     pub character_rom: Vec<u8>,
 }
 
 impl Cartridge {
-    pub fn new(binary: &[u8]) -> Result<Self> {
-        ensure!(binary.len() >= 16, "binary must be over than 16 bytes");
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RomError> {
+        let binary = fs::read(path.as_ref()).map_err(|error| RomError::from_io(path, error))?;
+        Self::new(&binary)
+    }
 
-        let header_binary = binary[0..16].to_vec();
-        let header = match INesHeader::new(&header_binary) {
-            Ok(header) => header,
-            _ => bail!("invalid header binary"),
-        };
+    pub fn new(binary: &[u8]) -> Result<Self, RomError> {
+        if binary.len() < HEADER_SIZE {
+            return Err(RomError::TooSmall {
+                minimum: HEADER_SIZE,
+                actual: binary.len(),
+            });
+        }
 
-        let program_rom_byte_size = PROGRAM_UNIT_SIZE * header.program_rom_size as usize;
-        let character_rom_byte_size = CHARACTER_UNIT_SIZE * header.character_rom_size as usize;
+        let header = INesHeader::new(&binary[0..HEADER_SIZE])?;
 
-        let rom_binary = binary[16..].to_vec();
-        if rom_binary.len() != program_rom_byte_size + character_rom_byte_size {
-            bail!("invalid PRG-ROM or CHR-ROM binary size.");
+        let program_rom_byte_size = header.program_rom_byte_size();
+        if program_rom_byte_size == 0 {
+            return Err(RomError::NoProgramRom);
+        }
+        let character_rom_byte_size = header.character_rom_byte_size();
+        let trainer_byte_size = match header.has_trainer {
+            true => TRAINER_SIZE,
+            false => 0,
+        };
+        let expected = HEADER_SIZE
+            .saturating_add(trainer_byte_size)
+            .saturating_add(program_rom_byte_size)
+            .saturating_add(character_rom_byte_size);
+
+        // Miscellaneous ROMs of NES 2.0 follow CHR-ROM.
+        let is_valid_size = match header.miscellaneous_rom_count {
+            0 => binary.len() == expected,
+            _ => binary.len() >= expected,
+        };
+        if !is_valid_size {
+            return Err(RomError::SizeMismatch {
+                expected,
+                actual: binary.len(),
+            });
         }
 
-        let (program_rom, character_rom) = rom_binary.split_at(program_rom_byte_size);
+        let (trainer, rom_binary) = binary[HEADER_SIZE..].split_at(trainer_byte_size);
+        let (program_rom, rom_binary) = rom_binary.split_at(program_rom_byte_size);
+        let character_rom = &rom_binary[..character_rom_byte_size];
+        let trainer = match header.has_trainer {
+            true => Some(trainer.to_vec()),
+            false => None,
+        };
 
         Ok(Cartridge {
             header,
+            trainer,
             program_rom: program_rom.to_vec(),
             character_rom: character_rom.to_vec(),
         })
     }
+
+    /// Offset of CHR-ROM in the file.
+    pub fn character_rom_offset(&self) -> usize {
+        let trainer_byte_size = self.trainer.as_ref().map_or(0, Vec::len);
+        HEADER_SIZE + trainer_byte_size + self.program_rom.len()
+    }
 }
 
 #[cfg(test)]
 mod cartridge_tests {
-    use crate::nes::cartridge::{CHARACTER_UNIT_SIZE, PROGRAM_UNIT_SIZE};
+    use crate::nes::cartridge::{CHARACTER_UNIT_SIZE, PROGRAM_UNIT_SIZE, TRAINER_SIZE};
     use std::vec;
 
-    use super::{header::MAGIC_BYTES, Cartridge};
+    use super::{error::RomError, header::MAGIC_BYTES, Cartridge};
 
     fn build_correct_binary() -> (Vec<u8>, Vec<u8>, Vec<u8>) {
         let program_rom_size: u8 = 2;
@@ -82,8 +128,60 @@ mod cartridge_tests {
         let binary = [header_binary, program_rom.clone(), character_rom.clone()].concat();
         let cartridge = Cartridge::new(&binary).unwrap();
 
+        assert_eq!(cartridge.trainer, None);
+        assert_eq!(cartridge.program_rom, program_rom);
+        assert_eq!(cartridge.character_rom, character_rom);
+        assert_eq!(cartridge.character_rom_offset(), 16 + PROGRAM_UNIT_SIZE * 2);
+    }
+
+    #[test]
+    fn create_cartridge_with_trainer() {
+        let (mut header_binary, program_rom, character_rom) = build_correct_binary();
+        header_binary[6] |= 0b00000100;
+        let trainer = vec![0x30u8; TRAINER_SIZE];
+
+        let binary = [
+            header_binary,
+            trainer.clone(),
+            program_rom.clone(),
+            character_rom.clone(),
+        ]
+        .concat();
+        let cartridge = Cartridge::new(&binary).unwrap();
+
+        assert_eq!(cartridge.trainer, Some(trainer));
         assert_eq!(cartridge.program_rom, program_rom);
         assert_eq!(cartridge.character_rom, character_rom);
+        assert_eq!(
+            cartridge.character_rom_offset(),
+            16 + TRAINER_SIZE + PROGRAM_UNIT_SIZE * 2
+        );
+    }
+
+    #[test]
+    fn fails_create_if_trainer_insufficient() {
+        let (mut header_binary, _, _) = build_correct_binary();
+        header_binary[6] |= 0b00000100;
+
+        let binary = [header_binary, vec![0x30u8; TRAINER_SIZE - 1]].concat();
+
+        assert_eq!(
+            Cartridge::new(&binary),
+            Err(RomError::SizeMismatch {
+                expected: 16 + TRAINER_SIZE + PROGRAM_UNIT_SIZE * 2 + CHARACTER_UNIT_SIZE,
+                actual: 16 + TRAINER_SIZE - 1,
+            })
+        );
+    }
+
+    #[test]
+    fn fails_create_if_no_program_rom() {
+        let (mut header_binary, _, character_rom) = build_correct_binary();
+        header_binary[4] = 0;
+
+        let binary = [header_binary, character_rom].concat();
+
+        assert_eq!(Cartridge::new(&binary), Err(RomError::NoProgramRom));
     }
 
     #[test]
@@ -95,6 +193,42 @@ mod cartridge_tests {
 
         let cartridge = Cartridge::new(&binary);
 
-        assert!(cartridge.is_err());
+        assert_eq!(
+            cartridge,
+            Err(RomError::SizeMismatch {
+                expected: binary.len() + 1,
+                actual: binary.len(),
+            })
+        );
+    }
+
+    #[test]
+    fn fails_create_if_less_than_header_size() {
+        let (header_binary, _, _) = build_correct_binary();
+
+        assert_eq!(
+            Cartridge::new(&header_binary[..15]),
+            Err(RomError::TooSmall {
+                minimum: 16,
+                actual: 15
+            })
+        );
+    }
+
+    #[test]
+    fn fails_create_if_magic_bytes_not_match() {
+        let (mut header_binary, program_rom, character_rom) = build_correct_binary();
+        header_binary[3] = 0xFF;
+
+        let binary = [header_binary, program_rom, character_rom].concat();
+        assert_eq!(Cartridge::new(&binary), Err(RomError::BadMagic));
+    }
+
+    #[test]
+    fn fails_load_if_not_found() {
+        assert_eq!(
+            Cartridge::load("not/exist.nes"),
+            Err(RomError::NotFound("not/exist.nes".to_string()))
+        );
     }
 }
@@ -1,20 +1,37 @@
-use crate::nes::{bus::Bus, ppu::registers::PpuRegistration, ram::Ram};
+use crate::nes::{
+    bus::Bus, mapper::SharedMapper, open_bus::OpenBus, ppu::registers::PpuRegistration, ram::Ram,
+};
 
 pub struct CpuBus<'a, T: PpuRegistration> {
-    program_rom: &'a Vec<u8>,
+    mapper: &'a SharedMapper,
     wram: &'a mut Ram,
     ppu: &'a mut T,
+    open_bus: &'a mut OpenBus,
 }
 
 impl<'a, T> CpuBus<'a, T>
 where
     T: PpuRegistration,
 {
-    pub fn new(program_rom: &'a Vec<u8>, wram: &'a mut Ram, ppu: &'a mut T) -> Self {
+    pub fn new(
+        mapper: &'a SharedMapper,
+        wram: &'a mut Ram,
+        ppu: &'a mut T,
+        open_bus: &'a mut OpenBus,
+    ) -> Self {
         Self {
-            program_rom,
+            mapper,
             wram,
             ppu,
+            open_bus,
+        }
+    }
+
+    fn read_cartridge(&mut self, address: u16) -> u8 {
+        let mut mapper = self.mapper.borrow_mut();
+        match mapper.is_cpu_mapped(address) {
+            true => mapper.cpu_read(address),
+            false => self.open_bus.unmapped_read(address),
         }
     }
 }
@@ -24,7 +41,7 @@ where
     T: PpuRegistration,
 {
     fn read(&mut self, address: u16) -> u8 {
-        match address {
+        let data = match address {
             0x0000..=0x1FFF => {
                 // 0x0000..=0x07FF => access to RAM.
                 // 0x0800..=0x0FFF => Mirrors of 0x0000..=0x07FF
@@ -39,24 +56,13 @@ where
                 let calibrated_address = (address - 0x2000) % 8;
                 self.ppu.read(calibrated_address)
             }
-            // 0x4000..0x401F => unimplemented!(), // APU I/O Keypad
-            // 0x4020..0x5FFF => unimplemented!(), // Expansion Rom
-            // 0x6000..0x7FFF => unimplemented!(), // Expansion Ram
-            0x8000..=0xBFFF => *self.program_rom.get((address - 0x8000) as usize).unwrap(),
-            0xC000..=0xFFFF => {
-                // if program rom sie is 16kb, mirror 0x8000..=0xBFFF
-                let calibrated_address = if self.program_rom.len() <= 0x4000 {
-                    // Mirror
-                    address - 0xC000
-                } else {
-                    // No mirror
-                    address - 0x8000
-                };
-
-                *self.program_rom.get(calibrated_address as usize).unwrap()
-            }
-            _ => panic!("unexpected memory area access! Addr: {:x}", address),
-        }
+            // 0x4000..0x401F => APU I/O Keypad (not implemented yet, read as open bus)
+            0x4000..=0x401F => self.open_bus.unmapped_read(address),
+            // 0x4020..=0xFFFF => Cartridge space. (Expansion ROM, PRG-RAM, PRG-ROM)
+            0x4020..=0xFFFF => self.read_cartridge(address),
+        };
+
+        self.open_bus.latch(data)
     }
 
     fn read_u16(&mut self, address: u16) -> u16 {
@@ -66,7 +72,8 @@ where
     }
 
     fn write(&mut self, address: u16, data: u8) {
-        println!("CpuBus write Address {:x} / Data: {:x}", &address, &data);
+        self.open_bus.latch(data);
+
         match address {
             0x0000..=0x1FFF => {
                 // 0x0000..=0x07FF => access to RAM.
@@ -82,20 +89,30 @@ where
                 let calibrated_address = (address - 0x2000) % 8;
                 self.ppu.write(calibrated_address, data);
             }
-            // 0x4000..0x401F => unimplemented!(), // APU I/O Keypad
-            // 0x4020..0x5FFF => unimplemented!(), // Expansion Rom
-            // 0x6000..0x7FFF => unimplemented!(), // Expansion Ram
-            _ => panic!(
-                "unexpected memory area access! Addr: {:x} / Data: {:x}",
-                address, data
-            ),
+            // 0x4000..0x401F => APU I/O Keypad (not implemented yet, ignored)
+            0x4000..=0x401F => self.open_bus.unmapped_write(address, data),
+            // 0x4020..=0xFFFF => Cartridge space. (Expansion ROM, PRG-RAM, mapper registers)
+            0x4020..=0xFFFF => self.mapper.borrow_mut().cpu_write(address, data),
         };
     }
 }
 
 #[cfg(test)]
 mod cpu_bus_test {
-    use crate::nes::ppu::registers::PpuRegistration;
+    use crate::nes::{
+        cartridge::header::MirroringType,
+        mapper::{character_memory::CharacterMemory, nrom::Nrom, share, SharedMapper},
+        ppu::registers::PpuRegistration,
+    };
+
+    fn build_mapper(program_rom: Vec<u8>) -> SharedMapper {
+        share(Nrom::new(
+            program_rom,
+            CharacterMemory::ram(0x2000),
+            0x2000,
+            MirroringType::Horizontal,
+        ))
+    }
 
     struct MockPpu {
         pub data: Vec<u8>,
@@ -120,18 +137,22 @@ mod cpu_bus_test {
     }
 
     mod read_test {
-        use crate::nes::{bus::Bus, cpu::bus::CpuBus, ppu::registers::PpuRegistration, ram::Ram};
+        use crate::nes::{
+            bus::Bus, cpu::bus::CpuBus, open_bus::OpenBus, ppu::registers::PpuRegistration,
+            ram::Ram,
+        };
 
         #[test]
         fn vram_range_read_test() {
-            let program_rom = Vec::new();
+            let mapper = super::build_mapper(vec![0; 0x4000]);
             let mut ppu = super::MockPpu::new();
 
             let mut wram = Ram::new(0x0800);
             wram.write(0x0000, 0x01);
             wram.write(0x07FF, 0x02);
 
-            let mut bus = CpuBus::new(&program_rom, &mut wram, &mut ppu);
+            let mut open_bus = OpenBus::new();
+            let mut bus = CpuBus::new(&mapper, &mut wram, &mut ppu, &mut open_bus);
 
             // Read RAM
             assert_eq!(bus.read(0x0000), 0x01);
@@ -149,14 +170,15 @@ mod cpu_bus_test {
 
         #[test]
         fn ppu_range_read_test() {
-            let program_rom = Vec::new();
+            let mapper = super::build_mapper(vec![0; 0x4000]);
             let mut wram = Ram::new(0x0800);
 
             let mut ppu = super::MockPpu::new();
             ppu.write(0x0000, 0x01);
             ppu.write(0x0007, 0x02);
 
-            let mut bus = CpuBus::new(&program_rom, &mut wram, &mut ppu);
+            let mut open_bus = OpenBus::new();
+            let mut bus = CpuBus::new(&mapper, &mut wram, &mut ppu, &mut open_bus);
 
             // Read PPU
             assert_eq!(bus.read(0x2000), 0x01);
@@ -173,73 +195,72 @@ mod cpu_bus_test {
         }
 
         #[test]
-        fn program_rom_range_read_test() {
+        fn cartridge_range_read_test() {
             let mut ppu = super::MockPpu::new();
             let mut wram = Ram::new(0x0800);
 
             let mut program_rom = vec![0x00; 0x8000];
             program_rom[0x0000] = 0x01;
-            program_rom[0x3FFF] = 0x02;
-            program_rom[0x4000] = 0x03;
-            program_rom[0x7FFF] = 0x04;
+            program_rom[0x7FFF] = 0x02;
+            let mapper = super::build_mapper(program_rom);
 
-            let mut bus = CpuBus::new(&program_rom, &mut wram, &mut ppu);
+            let mut open_bus = OpenBus::new();
+            let mut bus = CpuBus::new(&mapper, &mut wram, &mut ppu, &mut open_bus);
 
-            // Read Program ROM
+            // Read Program ROM through the mapper
             assert_eq!(bus.read(0x8000), 0x01);
-            assert_eq!(bus.read(0xBFFF), 0x02);
-            assert_eq!(bus.read(0xC000), 0x03);
-            assert_eq!(bus.read(0xFFFF), 0x04);
+            assert_eq!(bus.read(0xFFFF), 0x02);
         }
 
         #[test]
-        fn program_rom_range_16kb_rom_read_test() {
+        fn open_bus_read_test() {
+            let mapper = super::build_mapper(vec![0x12; 0x4000]);
             let mut ppu = super::MockPpu::new();
             let mut wram = Ram::new(0x0800);
 
-            let mut program_rom = vec![0x00; 0x4000];
-            program_rom[0x0000] = 0x01;
-            program_rom[0x3FFF] = 0x02;
+            let mut open_bus = OpenBus::new();
+            let mut bus = CpuBus::new(&mapper, &mut wram, &mut ppu, &mut open_bus);
 
-            let mut bus = CpuBus::new(&program_rom, &mut wram, &mut ppu);
+            // Nothing is connected to 0x4020..=0x5FFF of NROM.
+            assert_eq!(bus.read(0x8000), 0x12);
+            assert_eq!(bus.read(0x5000), 0x12);
+            assert_eq!(bus.read(0x4018), 0x12);
 
-            // Read Program ROM
-            assert_eq!(bus.read(0x8000), 0x01);
-            assert_eq!(bus.read(0xBFFF), 0x02);
-            // Mirror
-            assert_eq!(bus.read(0xC000), 0x01);
-            assert_eq!(bus.read(0xFFFF), 0x02);
+            bus.write(0x0000, 0x34);
+            assert_eq!(bus.read(0x4020), 0x34);
         }
     }
 
     mod read_u16_test {
-        use crate::nes::{bus::Bus, cpu::bus::CpuBus, ram::Ram};
+        use crate::nes::{bus::Bus, cpu::bus::CpuBus, open_bus::OpenBus, ram::Ram};
 
         #[test]
         fn read_u16_test() {
-            let program_rom = Vec::new();
+            let mapper = super::build_mapper(vec![0; 0x4000]);
             let mut ppu = super::MockPpu::new();
 
             let mut wram = Ram::new(0x0800);
             wram.write(0x0000, 0x01); // lower
             wram.write(0x0001, 0x02); // upper
 
-            let mut bus = CpuBus::new(&program_rom, &mut wram, &mut ppu);
+            let mut open_bus = OpenBus::new();
+            let mut bus = CpuBus::new(&mapper, &mut wram, &mut ppu, &mut open_bus);
 
             assert_eq!(bus.read_u16(0x0000), 0x0201);
         }
     }
 
     mod write_test {
-        use crate::nes::{bus::Bus, cpu::bus::CpuBus, ram::Ram};
+        use crate::nes::{bus::Bus, cpu::bus::CpuBus, open_bus::OpenBus, ram::Ram};
 
         #[test]
         fn vram_range_write_test() {
-            let program_rom = Vec::new();
+            let mapper = super::build_mapper(vec![0; 0x4000]);
             let mut ppu = super::MockPpu::new();
             let mut wram = Ram::new(0x0800);
 
-            let mut bus = CpuBus::new(&program_rom, &mut wram, &mut ppu);
+            let mut open_bus = OpenBus::new();
+            let mut bus = CpuBus::new(&mapper, &mut wram, &mut ppu, &mut open_bus);
 
             // Write RAM
             bus.write(0x0000, 0x01);
@@ -265,11 +286,12 @@ mod cpu_bus_test {
 
         #[test]
         fn ppu_range_write_test() {
-            let program_rom = Vec::new();
+            let mapper = super::build_mapper(vec![0; 0x4000]);
             let mut wram = Ram::new(0x0800);
             let mut ppu = super::MockPpu::new();
 
-            let mut bus = CpuBus::new(&program_rom, &mut wram, &mut ppu);
+            let mut open_bus = OpenBus::new();
+            let mut bus = CpuBus::new(&mapper, &mut wram, &mut ppu, &mut open_bus);
 
             // Write PPU
             bus.write(0x2000, 0x01);
@@ -293,5 +315,34 @@ mod cpu_bus_test {
                 assert_eq!(bus.ppu.data[0x0007], data2);
             }
         }
+
+        #[test]
+        fn cartridge_range_write_test() {
+            let mapper = super::build_mapper(vec![0; 0x4000]);
+            let mut wram = Ram::new(0x0800);
+            let mut ppu = super::MockPpu::new();
+
+            let mut open_bus = OpenBus::new();
+            let mut bus = CpuBus::new(&mapper, &mut wram, &mut ppu, &mut open_bus);
+
+            // Write PRG-RAM through the mapper
+            bus.write(0x6000, 0x01);
+            assert_eq!(mapper.borrow_mut().cpu_read(0x6000), 0x01);
+        }
+
+        #[test]
+        fn unmapped_write_test() {
+            let mapper = super::build_mapper(vec![0; 0x4000]);
+            let mut wram = Ram::new(0x0800);
+            let mut ppu = super::MockPpu::new();
+
+            let mut open_bus = OpenBus::new();
+            let mut bus = CpuBus::new(&mapper, &mut wram, &mut ppu, &mut open_bus);
+
+            // Ignored without panic.
+            bus.write(0x4014, 0x01);
+            bus.write(0x5000, 0x02);
+            assert_eq!(bus.open_bus.value(), 0x02);
+        }
     }
 }
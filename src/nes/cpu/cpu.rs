@@ -3,9 +3,12 @@ use crate::nes::{bus::Bus, cpu::instructions};
 use super::{
     fetch,
     opecode::{self, Code},
-    registers::CpuRegisters,
+    registers::{CpuRegisters, CpuStatusFlag},
 };
 
+/// Cycles of the interrupt sequence.
+const INTERRUPT_CYCLE: u16 = 7;
+
 pub struct Cpu<'a, T: Bus> {
     registers: &'a mut CpuRegisters,
     bus: &'a mut T,
@@ -25,11 +28,6 @@ impl<'a, T: Bus> Cpu<'a, T> {
         let instruction_code = fetch::fetch(cpu.bus, cpu.registers);
         let opecode = opecode::OPECODE_MAP.get(&instruction_code).unwrap();
 
-        println!(
-            "{:?} : {:?} : {:?}",
-            &opecode.code, &opecode.mode, &opecode.cycle
-        );
-
         match opecode.code {
             // ref: https://www.nesdev.org/obelisk-6502-guide/instructions.html
             // -- Load --
@@ -101,18 +99,33 @@ impl<'a, T: Bus> Cpu<'a, T> {
             Code::BRK => instructions::system::brk(cpu.bus, cpu.registers),
             Code::NOP => { /* NOP do nothing */ }
             Code::RTI => instructions::system::rti(cpu.bus, cpu.registers),
-            _ => println!("Unimplemented instruction: {:?}", opecode.code),
+            _ => eprintln!("Unimplemented instruction: {:?}", opecode.code),
         };
 
         opecode.cycle
     }
+
+    /// Take an IRQ before the next instruction. The IRQ line is level-triggered,
+    /// so the caller checks it before every instruction.
+    /// Returns None if the IRQ is masked by the I flag.
+    pub fn irq(cpu_register: &'a mut CpuRegisters, cpu_bus: &mut T) -> Option<u16> {
+        if cpu_register.p.contains(CpuStatusFlag::INTERRUPT_DISABLE) {
+            return None;
+        }
+
+        instructions::system::irq(cpu_bus, cpu_register);
+        Some(INTERRUPT_CYCLE)
+    }
 }
 
 #[cfg(test)]
 mod cpu_tests {
     use crate::nes::{
         bus::Bus,
-        cpu::{fetch, registers::CpuRegisters},
+        cpu::{
+            fetch,
+            registers::{CpuRegisters, CpuStatusFlag},
+        },
     };
 
     use super::Cpu;
@@ -150,4 +163,17 @@ mod cpu_tests {
         assert_eq!(operand, 10);
         assert_eq!(cpu.registers.pc, 3);
     }
+
+    #[test]
+    fn masked_irq_test() {
+        let mut registers = CpuRegisters::new();
+        registers.p.insert(CpuStatusFlag::INTERRUPT_DISABLE);
+        registers.pc = 0x8000;
+
+        let mut bus = MockBus { data: vec![0; 1] };
+
+        assert_eq!(Cpu::irq(&mut registers, &mut bus), None);
+        assert_eq!(registers.pc, 0x8000);
+        assert_eq!(registers.s, 0xFD);
+    }
 }
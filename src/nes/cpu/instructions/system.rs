@@ -16,6 +16,24 @@ where
     registers.p.insert(CpuStatusFlag::INTERRUPT_DISABLE);
 }
 
+/* IRQ
+https://www.nesdev.org/wiki/CPU_interrupts
+
+Same sequence as BRK, but the B flag of the pushed status is cleared.
+*/
+pub fn irq<T>(bus: &mut T, registers: &mut CpuRegisters)
+where
+    T: Bus,
+{
+    registers.push_u16(bus, registers.pc);
+    let status = (registers.p - CpuStatusFlag::BREAK) | CpuStatusFlag::BREAK2;
+    registers.push(bus, status.bits());
+
+    registers.pc = bus.read_u16(0xFFFE);
+
+    registers.p.insert(CpuStatusFlag::INTERRUPT_DISABLE);
+}
+
 pub fn rti<T>(bus: &mut T, registers: &mut CpuRegisters)
 where
     T: Bus,
@@ -55,6 +73,26 @@ mod system_tests {
         assert!(registers.p.contains(CpuStatusFlag::INTERRUPT_DISABLE));
     }
 
+    #[test]
+    fn irq_test() {
+        let mut bus = MockBus::new();
+        let mut registers = CpuRegisters::new();
+
+        registers.s = 0x09;
+        registers.pc = 0xFF20;
+        registers.p = CpuStatusFlag::from_bits_truncate(0b10010001);
+        bus.write(0xFFFE, 0x34);
+
+        irq(&mut bus, &mut registers);
+
+        assert_eq!(bus.read(0x0107), 0b10100001);
+        assert_eq!(bus.read(0x0108), 0x20);
+        assert_eq!(bus.read(0x0109), 0xFF);
+        assert_eq!(registers.s, 0x06);
+        assert_eq!(registers.pc, 0x34);
+        assert!(registers.p.contains(CpuStatusFlag::INTERRUPT_DISABLE));
+    }
+
     #[test]
     fn rti_test() {
         let mut bus = MockBus::new();
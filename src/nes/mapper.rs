@@ -0,0 +1,447 @@
+pub mod axrom;
+pub mod character_memory;
+pub mod cnrom;
+pub mod mmc1;
+pub mod mmc3;
+pub mod nrom;
+pub mod uxrom;
+
+use std::{cell::RefCell, rc::Rc};
+
+use self::{
+    axrom::Axrom,
+    character_memory::CharacterMemory,
+    cnrom::Cnrom,
+    mmc1::Mmc1,
+    mmc3::{Mmc3, Mmc3Revision},
+    nrom::Nrom,
+    uxrom::Uxrom,
+};
+use super::cartridge::{
+    error::RomError,
+    header::{ConsoleType, INesHeader, MirroringType},
+    Cartridge,
+};
+
+/* Mapper
+https://www.nesdev.org/wiki/Mapper
+
+The cartridge board decides what the CPU and the PPU see in the cartridge space.
+
+CPU $4020-$FFFF: PRG-ROM, PRG-RAM and the registers of the mapper.
+PPU $0000-$1FFF: pattern tables (CHR-ROM or CHR-RAM).
+
+The mapper also controls the name table mirroring, and some mappers raise IRQs.
+*/
+pub trait Mapper {
+    /// Whether the cartridge drives the data bus on reading `address`.
+    /// Open bus is read from the other addresses. (ex: $4020-$7FFF without PRG-RAM)
+    fn is_cpu_mapped(&self, address: u16) -> bool {
+        address >= 0x8000
+    }
+
+    /// `address` is a CPU address. (0x4020..=0xFFFF)
+    fn cpu_read(&mut self, address: u16) -> u8;
+    /// `address` is a CPU address. (0x4020..=0xFFFF)
+    fn cpu_write(&mut self, address: u16, data: u8);
+    /// `address` is a PPU address. (0x0000..=0x1FFF)
+    fn ppu_read(&mut self, address: u16) -> u8;
+    /// `address` is a PPU address. (0x0000..=0x1FFF)
+    fn ppu_write(&mut self, address: u16, data: u8);
+
+    fn mirroring(&self) -> MirroringType;
+
+    /// CHR-ROM or CHR-RAM of the cartridge.
+    fn character_memory(&self) -> &CharacterMemory;
+
+    /// PRG-RAM at $6000-$7FFF. Empty if the board has no PRG-RAM.
+    fn program_ram(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Restore PRG-RAM from a battery-backed save.
+    fn load_program_ram(&mut self, _data: &[u8]) {}
+
+    /// Level of the IRQ line. true = asserted. The CPU checks it before every instruction.
+    fn irq(&self) -> bool {
+        false
+    }
+
+    /// Called with the CPU cycles of every instruction.
+    fn clock(&mut self, _cycle: u16) {}
+}
+
+/* Bus conflicts
+https://www.nesdev.org/wiki/Bus_conflict
+
+On boards without a chip that disables the ROM on writes, a value written to the ROM area
+conflicts with the value output by the ROM. The mapper sees the AND of both.
+iNES headers can not tell the boards apart, so the common board of each mapper is assumed.
+NES 2.0 headers tell it by the submapper of mapper 2, 3 and 7. (1: no bus conflicts, 2: AND)
+*/
+/// UNROM has bus conflicts. (UOROM does not.)
+const UXROM_BUS_CONFLICTS: bool = true;
+const CNROM_BUS_CONFLICTS: bool = true;
+/// ANROM and AOROM have no bus conflicts. (AMROM does.)
+const AXROM_BUS_CONFLICTS: bool = false;
+
+/// The trainer is copied to PRG-RAM at $7000-$71FF.
+const TRAINER_ADDRESS: u16 = 0x7000;
+/// PRG-RAM given to the boards with a trainer, even if the header declares less.
+const TRAINER_PROGRAM_RAM_SIZE: usize = 0x2000;
+
+/// Most MMC3 boards have the Sharp MMC3B or later. NES 2.0 submapper 4 is the MMC3A.
+const MMC3_REVISION: Mmc3Revision = Mmc3Revision::RevB;
+
+/// The mapper is shared by the CPU bus and the PPU.
+pub type SharedMapper = Rc<RefCell<dyn Mapper>>;
+
+pub fn share<M: Mapper + 'static>(mapper: M) -> SharedMapper {
+    Rc::new(RefCell::new(mapper))
+}
+
+/// Board name of the mapper. Names are known only for common mappers.
+pub fn mapper_name(number: u16) -> Option<&'static str> {
+    let name = match number {
+        0 => "NROM",
+        1 => "MMC1",
+        2 => "UxROM",
+        3 => "CNROM",
+        4 => "MMC3",
+        5 => "MMC5",
+        7 => "AxROM",
+        9 => "MMC2",
+        10 => "MMC4",
+        11 => "Color Dreams",
+        66 => "GxROM",
+        69 => "Sunsoft FME-7",
+        71 => "Camerica",
+        _ => return None,
+    };
+    Some(name)
+}
+
+pub fn build_mapper(cartridge: &Cartridge) -> Result<SharedMapper, RomError> {
+    let header = &cartridge.header;
+    if let ConsoleType::Extended(console_type) = header.console_type {
+        return Err(RomError::UnsupportedNes2Feature(format!(
+            "extended console type {}",
+            console_type
+        )));
+    }
+
+    let minimum = minimum_program_rom_size(header.mapper_number);
+    if cartridge.program_rom.len() < minimum {
+        return Err(RomError::ProgramRomTooSmall {
+            mapper: header.mapper_number,
+            minimum,
+            actual: cartridge.program_rom.len(),
+        });
+    }
+
+    let program_rom = cartridge.program_rom.clone();
+    let character = CharacterMemory::new(
+        cartridge.character_rom.clone(),
+        header.character_ram_byte_size(),
+    );
+    let mirroring = header.nametable_mirroring();
+    let program_ram_size = match cartridge.trainer {
+        Some(_) => header.program_ram_byte_size().max(TRAINER_PROGRAM_RAM_SIZE),
+        None => header.program_ram_byte_size(),
+    };
+
+    let mapper = match header.mapper_number {
+        0 => share(Nrom::new(
+            program_rom,
+            character,
+            program_ram_size,
+            mirroring,
+        )),
+        1 => share(Mmc1::new(program_rom, character, program_ram_size)),
+        2 => share(Uxrom::new(
+            program_rom,
+            character,
+            mirroring,
+            bus_conflicts(header, UXROM_BUS_CONFLICTS),
+        )),
+        3 => share(Cnrom::new(
+            program_rom,
+            character,
+            mirroring,
+            bus_conflicts(header, CNROM_BUS_CONFLICTS),
+        )),
+        4 => share(Mmc3::new(
+            program_rom,
+            character,
+            program_ram_size,
+            mirroring,
+            mmc3_revision(header),
+        )),
+        7 => share(Axrom::new(
+            program_rom,
+            character,
+            bus_conflicts(header, AXROM_BUS_CONFLICTS),
+        )),
+        number => return Err(RomError::UnsupportedMapper(number)),
+    };
+
+    if let Some(trainer) = &cartridge.trainer {
+        let mut mapper = mapper.borrow_mut();
+        // UxROM, CNROM and AxROM have no PRG-RAM.
+        if mapper.program_ram().is_empty() {
+            return Err(RomError::UnsupportedTrainer(header.mapper_number));
+        }
+        for (offset, data) in trainer.iter().enumerate() {
+            mapper.cpu_write(TRAINER_ADDRESS + offset as u16, *data);
+        }
+    }
+
+    Ok(mapper)
+}
+
+/// NES 2.0 headers can declare PRG-ROM smaller than 16KB.
+fn minimum_program_rom_size(mapper_number: u16) -> usize {
+    match mapper_number {
+        // The last 16KB bank is fixed, or a 16KB bank is switched.
+        1 | 2 => 0x4000,
+        // The last two 8KB banks are fixed.
+        4 => 0x4000,
+        // NROM, CNROM and AxROM mirror the smaller PRG-ROM.
+        _ => 1,
+    }
+}
+
+fn bus_conflicts(header: &INesHeader, default: bool) -> bool {
+    match (header.is_nes2(), header.submapper_number) {
+        (true, 1) => false,
+        (true, 2) => true,
+        _ => default,
+    }
+}
+
+fn mmc3_revision(header: &INesHeader) -> Mmc3Revision {
+    match (header.is_nes2(), header.submapper_number) {
+        (true, 4) => Mmc3Revision::RevA,
+        _ => MMC3_REVISION,
+    }
+}
+
+/// ROM whose every bank is filled with its bank number. Used by the tests of the mappers.
+#[cfg(test)]
+pub fn build_rom(bank_size: usize, bank_count: usize) -> Vec<u8> {
+    (0..bank_count)
+        .flat_map(|bank| vec![bank as u8; bank_size])
+        .collect()
+}
+
+#[cfg(test)]
+mod mapper_test {
+    use crate::nes::cartridge::header::{HeaderFormat, MAGIC_BYTES};
+
+    use super::*;
+
+    fn build_cartridge(mapper_number: u8) -> Cartridge {
+        let header = [
+            vec![
+                MAGIC_BYTES[0],
+                MAGIC_BYTES[1],
+                MAGIC_BYTES[2],
+                MAGIC_BYTES[3],
+                1,
+                1,
+                mapper_number << 4,
+            ],
+            vec![0; 9],
+        ]
+        .concat();
+        let binary = [header, vec![0; 0x4000], vec![0; 0x2000]].concat();
+
+        Cartridge::new(&binary).unwrap()
+    }
+
+    #[test]
+    fn build_nrom_test() {
+        assert!(build_mapper(&build_cartridge(0)).is_ok());
+    }
+
+    #[test]
+    fn build_mmc1_test() {
+        assert!(build_mapper(&build_cartridge(1)).is_ok());
+    }
+
+    #[test]
+    fn build_bank_switching_mappers_test() {
+        for mapper_number in [2, 3, 4, 7] {
+            assert!(build_mapper(&build_cartridge(mapper_number)).is_ok());
+        }
+    }
+
+    #[test]
+    fn character_ram_test() {
+        let mut cartridge = build_cartridge(0);
+        cartridge.header.character_rom_size = 0;
+        cartridge.character_rom = Vec::new();
+
+        let mapper = build_mapper(&cartridge).unwrap();
+        assert!(mapper.borrow().character_memory().is_ram());
+        assert_eq!(mapper.borrow().character_memory().len(), 0x2000);
+
+        mapper.borrow_mut().ppu_write(0x0010, 0xAB);
+        assert_eq!(mapper.borrow_mut().ppu_read(0x0010), 0xAB);
+    }
+
+    #[test]
+    fn nes2_submapper_test() {
+        let mut cartridge = build_cartridge(2);
+        cartridge.header.format = HeaderFormat::Nes2;
+        assert!(bus_conflicts(&cartridge.header, true));
+
+        cartridge.header.submapper_number = 1;
+        assert!(!bus_conflicts(&cartridge.header, true));
+
+        cartridge.header.submapper_number = 2;
+        assert!(bus_conflicts(&cartridge.header, false));
+
+        cartridge.header.submapper_number = 4;
+        assert_eq!(mmc3_revision(&cartridge.header), Mmc3Revision::RevA);
+
+        // iNES headers have no submapper.
+        cartridge.header.format = HeaderFormat::INes;
+        assert_eq!(mmc3_revision(&cartridge.header), MMC3_REVISION);
+    }
+
+    #[test]
+    fn program_ram_size_test() {
+        // iNES headers always have 8KB.
+        for mapper_number in [0, 1, 4] {
+            let mapper = build_mapper(&build_cartridge(mapper_number)).unwrap();
+            assert_eq!(mapper.borrow().program_ram().len(), 0x2000);
+            assert!(mapper.borrow().is_cpu_mapped(0x6000));
+        }
+
+        // NES 2.0 headers can declare no PRG-RAM.
+        for mapper_number in [0, 1, 4] {
+            let mut cartridge = build_cartridge(mapper_number);
+            cartridge.header.format = HeaderFormat::Nes2;
+
+            let mapper = build_mapper(&cartridge).unwrap();
+            assert!(mapper.borrow().program_ram().is_empty());
+            assert!(!mapper.borrow().is_cpu_mapped(0x6000));
+        }
+
+        let mut cartridge = build_cartridge(4);
+        cartridge.header.format = HeaderFormat::Nes2;
+        cartridge.header.program_ram_shift = 4; // 64 << 4 = 1KB
+        let mapper = build_mapper(&cartridge).unwrap();
+        assert_eq!(mapper.borrow().program_ram().len(), 0x0400);
+    }
+
+    #[test]
+    fn trainer_test() {
+        for mapper_number in [0, 1, 4] {
+            let mut cartridge = build_cartridge(mapper_number);
+            cartridge.trainer = Some(vec![0xEA; 512]);
+
+            let mapper = build_mapper(&cartridge).unwrap();
+            assert_eq!(mapper.borrow_mut().cpu_read(0x6FFF), 0x00);
+            assert_eq!(mapper.borrow_mut().cpu_read(0x7000), 0xEA);
+            assert_eq!(mapper.borrow_mut().cpu_read(0x71FF), 0xEA);
+            assert_eq!(mapper.borrow_mut().cpu_read(0x7200), 0x00);
+        }
+    }
+
+    #[test]
+    fn trainer_without_program_ram_in_header_test() {
+        // NES 2.0 headers can declare no PRG-RAM, but the trainer needs it.
+        for mapper_number in [0, 1, 4] {
+            let mut cartridge = build_cartridge(mapper_number);
+            cartridge.header.format = HeaderFormat::Nes2;
+            cartridge.trainer = Some(vec![0xEA; 512]);
+
+            let mapper = build_mapper(&cartridge).unwrap();
+            assert_eq!(mapper.borrow().program_ram().len(), 0x2000);
+            assert_eq!(mapper.borrow_mut().cpu_read(0x7000), 0xEA);
+        }
+    }
+
+    #[test]
+    fn trainer_without_program_ram_board_test() {
+        for mapper_number in [2, 3, 7] {
+            let mut cartridge = build_cartridge(mapper_number);
+            cartridge.trainer = Some(vec![0xEA; 512]);
+
+            let result = build_mapper(&cartridge);
+            assert_eq!(
+                result.err(),
+                Some(RomError::UnsupportedTrainer(mapper_number as u16))
+            );
+        }
+    }
+
+    #[test]
+    fn program_rom_too_small_test() {
+        for mapper_number in [1, 2, 4] {
+            let mut cartridge = build_cartridge(mapper_number);
+            cartridge.program_rom = vec![0; 0x2000];
+
+            let result = build_mapper(&cartridge);
+            assert_eq!(
+                result.err(),
+                Some(RomError::ProgramRomTooSmall {
+                    mapper: mapper_number as u16,
+                    minimum: 0x4000,
+                    actual: 0x2000
+                })
+            );
+        }
+
+        // Smaller PRG-ROM is mirrored.
+        for mapper_number in [0, 3, 7] {
+            let mut cartridge = build_cartridge(mapper_number);
+            cartridge.program_rom = vec![0xEA; 0x2000];
+
+            let mapper = build_mapper(&cartridge).unwrap();
+            assert_eq!(mapper.borrow_mut().cpu_read(0xFFFC), 0xEA);
+        }
+    }
+
+    #[test]
+    fn empty_program_rom_test() {
+        for mapper_number in [0, 1, 2, 3, 4, 7] {
+            let mut cartridge = build_cartridge(mapper_number);
+            cartridge.program_rom = Vec::new();
+
+            let result = build_mapper(&cartridge);
+            assert!(matches!(
+                result.err(),
+                Some(RomError::ProgramRomTooSmall { actual: 0, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn mapper_name_test() {
+        assert_eq!(mapper_name(4), Some("MMC3"));
+        assert_eq!(mapper_name(4095), None);
+    }
+
+    #[test]
+    fn unsupported_mapper_test() {
+        let result = build_mapper(&build_cartridge(15));
+        assert_eq!(result.err(), Some(RomError::UnsupportedMapper(15)));
+    }
+
+    #[test]
+    fn unsupported_console_type_test() {
+        let mut cartridge = build_cartridge(0);
+        cartridge.header.console_type = ConsoleType::Extended(3);
+
+        let result = build_mapper(&cartridge);
+        assert_eq!(
+            result.err(),
+            Some(RomError::UnsupportedNes2Feature(
+                "extended console type 3".to_string()
+            ))
+        );
+    }
+}
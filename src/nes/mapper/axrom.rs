@@ -0,0 +1,145 @@
+use crate::nes::cartridge::header::MirroringType;
+
+use super::{character_memory::CharacterMemory, Mapper};
+
+const PROGRAM_BANK_SIZE: usize = 0x8000; // 32KB
+
+/* AxROM (mapper 7)
+https://www.nesdev.org/wiki/AxROM
+
+CPU $8000-$FFFF: 32KB switchable PRG-ROM bank
+PPU $0000-$1FFF: 8KB CHR-RAM
+
+Writing to $8000-$FFFF selects the PRG-ROM bank and the single-screen name table.
+
+7  bit  0
+---- ----
+xxxM xPPP
+   |  |||
+   |  +++- Select 32KB PRG-ROM bank
+   +------ Select 1KB VRAM page for all 4 nametables (single-screen mirroring)
+*/
+pub struct Axrom {
+    program_rom: Vec<u8>,
+    character: CharacterMemory,
+    bank_select: u8,
+    /// The written value is ANDed with the ROM value at the address. (AMROM)
+    bus_conflicts: bool,
+}
+
+impl Axrom {
+    pub fn new(program_rom: Vec<u8>, character: CharacterMemory, bus_conflicts: bool) -> Self {
+        Axrom {
+            program_rom,
+            character,
+            bank_select: 0,
+            bus_conflicts,
+        }
+    }
+
+    fn program_rom_offset(&self, address: u16) -> usize {
+        let bank_count = (self.program_rom.len() / PROGRAM_BANK_SIZE).max(1);
+        let bank = (self.bank_select & 0b0111) as usize % bank_count;
+
+        (bank * PROGRAM_BANK_SIZE + (address - 0x8000) as usize) % self.program_rom.len()
+    }
+}
+
+impl Mapper for Axrom {
+    fn cpu_read(&mut self, address: u16) -> u8 {
+        match address {
+            0x8000..=0xFFFF => self.program_rom[self.program_rom_offset(address)],
+            // Nothing is connected.
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        if let 0x8000..=0xFFFF = address {
+            self.bank_select = match self.bus_conflicts {
+                true => data & self.cpu_read(address),
+                false => data,
+            };
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.character.read(address as usize)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        self.character.write(address as usize, data);
+    }
+
+    fn mirroring(&self) -> MirroringType {
+        match self.bank_select & 0b10000 {
+            0 => MirroringType::SingleScreenA,
+            _ => MirroringType::SingleScreenB,
+        }
+    }
+
+    fn character_memory(&self) -> &CharacterMemory {
+        &self.character
+    }
+}
+
+#[cfg(test)]
+mod axrom_test {
+    use crate::nes::mapper::build_rom;
+
+    use super::*;
+
+    #[test]
+    fn switch_program_bank_test() {
+        let mut axrom = Axrom::new(
+            build_rom(PROGRAM_BANK_SIZE, 8),
+            CharacterMemory::ram(0x2000),
+            false,
+        );
+
+        assert_eq!(axrom.cpu_read(0x8000), 0);
+
+        axrom.cpu_write(0x8000, 5);
+        assert_eq!(axrom.cpu_read(0x8000), 5);
+        assert_eq!(axrom.cpu_read(0xFFFF), 5);
+    }
+
+    #[test]
+    fn single_screen_mirroring_test() {
+        let mut axrom = Axrom::new(
+            build_rom(PROGRAM_BANK_SIZE, 8),
+            CharacterMemory::ram(0x2000),
+            false,
+        );
+        assert_eq!(axrom.mirroring(), MirroringType::SingleScreenA);
+
+        axrom.cpu_write(0x8000, 0b10010);
+        assert_eq!(axrom.mirroring(), MirroringType::SingleScreenB);
+        assert_eq!(axrom.cpu_read(0x8000), 2);
+    }
+
+    #[test]
+    fn bus_conflicts_test() {
+        // Bank 0 reads 0x00, so every write selects bank 0 with the conflicts.
+        let mut axrom = Axrom::new(
+            build_rom(PROGRAM_BANK_SIZE, 8),
+            CharacterMemory::ram(0x2000),
+            true,
+        );
+        axrom.cpu_write(0x8000, 0b10011);
+        assert_eq!(axrom.cpu_read(0x8000), 0);
+        assert_eq!(axrom.mirroring(), MirroringType::SingleScreenA);
+    }
+
+    #[test]
+    fn character_ram_test() {
+        let mut axrom = Axrom::new(
+            build_rom(PROGRAM_BANK_SIZE, 1),
+            CharacterMemory::ram(0x2000),
+            false,
+        );
+
+        axrom.ppu_write(0x0123, 0x45);
+        assert_eq!(axrom.ppu_read(0x0123), 0x45);
+    }
+}
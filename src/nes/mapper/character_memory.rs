@@ -0,0 +1,118 @@
+/// CHR-RAM size of the boards whose header does not tell the size. (iNES)
+pub const DEFAULT_CHARACTER_RAM_SIZE: usize = 0x2000; // 8KB
+
+/* CHR memory
+https://www.nesdev.org/wiki/CHR_ROM_vs._CHR_RAM
+
+PPU $0000-$1FFF of the cartridge. Boards have CHR-ROM, or CHR-RAM which the CPU fills through $2007.
+Offsets wrap around the size, so boards smaller than the bank window are mirrored.
+*/
+pub struct CharacterMemory {
+    /// Up to 512KB, which is larger than `Ram` can address.
+    data: Vec<u8>,
+    is_ram: bool,
+}
+
+impl CharacterMemory {
+    pub fn rom(data: Vec<u8>) -> Self {
+        CharacterMemory {
+            data,
+            is_ram: false,
+        }
+    }
+
+    pub fn ram(size: usize) -> Self {
+        CharacterMemory {
+            data: vec![0; size],
+            is_ram: true,
+        }
+    }
+
+    /// CHR-ROM if the cartridge has it, otherwise CHR-RAM of `ram_size` bytes.
+    pub fn new(character_rom: Vec<u8>, ram_size: usize) -> Self {
+        match character_rom.is_empty() {
+            true => Self::ram(ram_size),
+            false => Self::rom(character_rom),
+        }
+    }
+
+    pub fn is_ram(&self) -> bool {
+        self.is_ram
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Open bus is read as 0 if nothing is connected.
+    pub fn read(&self, offset: usize) -> u8 {
+        match self.is_empty() {
+            true => 0,
+            false => self.data[offset % self.data.len()],
+        }
+    }
+
+    /// CHR-ROM can not be written.
+    pub fn write(&mut self, offset: usize, data: u8) {
+        if self.is_ram && !self.is_empty() {
+            let offset = offset % self.data.len();
+            self.data[offset] = data;
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod character_memory_test {
+    use super::*;
+
+    #[test]
+    fn new_test() {
+        let memory = CharacterMemory::new(vec![0x01; 0x2000], 0x2000);
+        assert!(!memory.is_ram());
+
+        let memory = CharacterMemory::new(Vec::new(), 0x8000);
+        assert!(memory.is_ram());
+        assert_eq!(memory.len(), 0x8000);
+    }
+
+    #[test]
+    fn rom_can_not_be_written_test() {
+        let mut memory = CharacterMemory::rom(vec![0x01; 0x2000]);
+        memory.write(0x0000, 0xFF);
+        assert_eq!(memory.read(0x0000), 0x01);
+    }
+
+    #[test]
+    fn large_rom_test() {
+        // 256KB (MMC3)
+        let mut data = vec![0x00; 0x40000];
+        data[0x3FFFF] = 0x01;
+
+        let memory = CharacterMemory::rom(data);
+        assert_eq!(memory.read(0x3FFFF), 0x01);
+    }
+
+    #[test]
+    fn ram_test() {
+        let mut memory = CharacterMemory::ram(0x2000);
+        memory.write(0x1FFF, 0x12);
+        assert_eq!(memory.read(0x1FFF), 0x12);
+        assert_eq!(memory.as_slice()[0x1FFF], 0x12);
+    }
+
+    #[test]
+    fn mirror_test() {
+        let memory = CharacterMemory::rom([vec![0x00; 0x10], vec![0x01; 0x10]].concat());
+        assert_eq!(memory.read(0x0010), 0x01);
+        assert_eq!(memory.read(0x0030), 0x01);
+        assert_eq!(memory.read(0x0020), 0x00);
+    }
+}
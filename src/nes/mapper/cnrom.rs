@@ -0,0 +1,148 @@
+use crate::nes::cartridge::header::MirroringType;
+
+use super::{character_memory::CharacterMemory, Mapper};
+
+const CHARACTER_BANK_SIZE: usize = 0x2000; // 8KB
+
+/* CNROM (mapper 3)
+https://www.nesdev.org/wiki/CNROM
+
+CPU $8000-$FFFF: 16KB or 32KB PRG-ROM (16KB is mirrored like NROM)
+PPU $0000-$1FFF: 8KB switchable CHR-ROM bank
+
+Writing to $8000-$FFFF selects the CHR-ROM bank.
+*/
+pub struct Cnrom {
+    program_rom: Vec<u8>,
+    character: CharacterMemory,
+    mirroring: MirroringType,
+    character_bank: u8,
+    /// The written value is ANDed with the ROM value at the address.
+    bus_conflicts: bool,
+}
+
+impl Cnrom {
+    pub fn new(
+        program_rom: Vec<u8>,
+        character: CharacterMemory,
+        mirroring: MirroringType,
+        bus_conflicts: bool,
+    ) -> Self {
+        Cnrom {
+            program_rom,
+            character,
+            mirroring,
+            character_bank: 0,
+            bus_conflicts,
+        }
+    }
+
+    fn character_offset(&self, address: u16) -> usize {
+        let bank_count = (self.character.len() / CHARACTER_BANK_SIZE).max(1);
+        let bank = self.character_bank as usize % bank_count;
+
+        bank * CHARACTER_BANK_SIZE + (address as usize & (CHARACTER_BANK_SIZE - 1))
+    }
+}
+
+impl Mapper for Cnrom {
+    fn cpu_read(&mut self, address: u16) -> u8 {
+        match address {
+            // if program rom size is 16kb, 0xC000..=0xFFFF mirrors 0x8000..=0xBFFF
+            0x8000..=0xFFFF => {
+                self.program_rom[(address - 0x8000) as usize % self.program_rom.len()]
+            }
+            // Nothing is connected.
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        if let 0x8000..=0xFFFF = address {
+            self.character_bank = match self.bus_conflicts {
+                true => data & self.cpu_read(address),
+                false => data,
+            };
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.character.read(self.character_offset(address))
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        let offset = self.character_offset(address);
+        self.character.write(offset, data);
+    }
+
+    fn mirroring(&self) -> MirroringType {
+        self.mirroring
+    }
+
+    fn character_memory(&self) -> &CharacterMemory {
+        &self.character
+    }
+}
+
+#[cfg(test)]
+mod cnrom_test {
+    use crate::nes::mapper::build_rom;
+
+    use super::*;
+
+    #[test]
+    fn switch_character_bank_test() {
+        let program_rom = vec![0xFF; 0x8000];
+        let mut cnrom = Cnrom::new(
+            program_rom,
+            CharacterMemory::rom(build_rom(CHARACTER_BANK_SIZE, 4)),
+            MirroringType::Horizontal,
+            false,
+        );
+
+        assert_eq!(cnrom.ppu_read(0x0000), 0);
+
+        cnrom.cpu_write(0x8000, 2);
+        assert_eq!(cnrom.ppu_read(0x0000), 2);
+        assert_eq!(cnrom.ppu_read(0x1FFF), 2);
+
+        // CHR-ROM can not be written.
+        cnrom.ppu_write(0x0000, 0xFF);
+        assert_eq!(cnrom.ppu_read(0x0000), 2);
+    }
+
+    #[test]
+    fn program_rom_16kb_mirror_test() {
+        let mut program_rom = vec![0x00; 0x4000];
+        program_rom[0x0000] = 0x01;
+
+        let mut cnrom = Cnrom::new(
+            program_rom,
+            CharacterMemory::rom(build_rom(CHARACTER_BANK_SIZE, 1)),
+            MirroringType::Horizontal,
+            false,
+        );
+        assert_eq!(cnrom.cpu_read(0xC000), 0x01);
+    }
+
+    #[test]
+    fn bus_conflicts_test() {
+        let mut program_rom = vec![0xFF; 0x8000];
+        program_rom[0x0000] = 0x01;
+
+        let mut cnrom = Cnrom::new(
+            program_rom,
+            CharacterMemory::rom(build_rom(CHARACTER_BANK_SIZE, 4)),
+            MirroringType::Horizontal,
+            true,
+        );
+
+        // 0x03 & 0x01
+        cnrom.cpu_write(0x8000, 0x03);
+        assert_eq!(cnrom.ppu_read(0x0000), 1);
+
+        // 0x03 & 0xFF
+        cnrom.cpu_write(0x8001, 0x03);
+        assert_eq!(cnrom.ppu_read(0x0000), 3);
+    }
+}
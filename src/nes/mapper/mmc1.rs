@@ -0,0 +1,497 @@
+use crate::nes::{cartridge::header::MirroringType, ram::Ram};
+
+use super::{character_memory::CharacterMemory, Mapper};
+
+const PROGRAM_BANK_SIZE: usize = 0x4000; // 16KB
+const CHARACTER_BANK_SIZE: usize = 0x1000; // 4KB
+const PROGRAM_RAM_BANK_SIZE: usize = 0x2000; // 8KB
+/// PRG-ROM is selected in 256KB windows. (16 banks of 16KB)
+const PROGRAM_WINDOW_BANKS: usize = 16;
+
+/// The control register after reset. (PRG mode 3: fix last bank at $C000)
+const CONTROL_RESET: u8 = 0b01100;
+
+/* Boards which reuse the unused bits of the CHR bank registers.
+https://www.nesdev.org/wiki/MMC1#SxROM_connection_variants
+
+These boards have 8KB of CHR-RAM, so the upper bits of the CHR bank 0 register are free.
+
+Standard: -
+SOROM   : bit 3 selects an 8KB PRG-RAM bank. (16KB PRG-RAM)
+SUROM   : bit 4 selects a 256KB PRG-ROM window. (512KB PRG-ROM)
+SXROM   : bits 2-3 select an 8KB PRG-RAM bank, bit 4 selects a 256KB PRG-ROM window. (32KB PRG-RAM)
+*/
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Mmc1Board {
+    Standard,
+    Sorom,
+    Surom,
+    Sxrom,
+}
+
+impl Mmc1Board {
+    pub fn detect(program_rom_size: usize, program_ram_size: usize) -> Self {
+        match (program_rom_size > 0x40000, program_ram_size) {
+            (_, 0x8000) => Mmc1Board::Sxrom,
+            (true, _) => Mmc1Board::Surom,
+            (false, 0x4000) => Mmc1Board::Sorom,
+            _ => Mmc1Board::Standard,
+        }
+    }
+}
+
+/* MMC1 (mapper 1)
+https://www.nesdev.org/wiki/MMC1
+
+The registers are written serially through a 5 bits shift register.
+Write bit 0 of the data 5 times to $8000-$FFFF, and the 5th write decides the register by the address.
+Writing a value with bit 7 set resets the shift register.
+
+$8000-$9FFF: Control    (CPPMM: C = CHR mode, PP = PRG mode, MM = mirroring)
+$A000-$BFFF: CHR bank 0
+$C000-$DFFF: CHR bank 1
+$E000-$FFFF: PRG bank   (RPPPP: R = PRG-RAM disable, PPPP = PRG bank)
+
+PRG mode 0, 1: switch 32KB at $8000, ignoring the low bit of the bank number
+PRG mode 2   : fix the first bank at $8000, switch 16KB at $C000
+PRG mode 3   : fix the last bank at $C000, switch 16KB at $8000
+CHR mode 0   : switch 8KB at a time, ignoring the low bit of the bank number
+CHR mode 1   : switch two separate 4KB banks
+*/
+pub struct Mmc1 {
+    board: Mmc1Board,
+    program_rom: Vec<u8>,
+    program_ram: Ram,
+    character: CharacterMemory,
+    shift_register: u8,
+    write_count: u8,
+    control: u8,
+    character_bank_0: u8,
+    character_bank_1: u8,
+    program_bank: u8,
+    /// CPU cycles counted by `clock`.
+    cycle: u64,
+    last_write_cycle: Option<u64>,
+}
+
+impl Mmc1 {
+    pub fn new(program_rom: Vec<u8>, character: CharacterMemory, program_ram_size: usize) -> Self {
+        let board = Mmc1Board::detect(program_rom.len(), program_ram_size);
+        Mmc1 {
+            board,
+            program_rom,
+            program_ram: Ram::from_vec(match program_ram_size {
+                0 => Vec::new(),
+                size => vec![0; size.max(PROGRAM_RAM_BANK_SIZE)],
+            }),
+            character,
+            shift_register: 0,
+            write_count: 0,
+            control: CONTROL_RESET,
+            character_bank_0: 0,
+            character_bank_1: 0,
+            program_bank: 0,
+            cycle: 0,
+            last_write_cycle: None,
+        }
+    }
+
+    pub fn board(&self) -> Mmc1Board {
+        self.board
+    }
+
+    fn write_register(&mut self, address: u16, data: u8) {
+        // The CPU writes twice on consecutive cycles in read-modify-write instructions (ex: INC).
+        // MMC1 ignores the second write.
+        if self.last_write_cycle == Some(self.cycle) {
+            return;
+        }
+        self.last_write_cycle = Some(self.cycle);
+
+        if data & 0b10000000 != 0 {
+            self.shift_register = 0;
+            self.write_count = 0;
+            self.control |= CONTROL_RESET;
+            return;
+        }
+
+        self.shift_register = (self.shift_register >> 1) | ((data & 0b00000001) << 4);
+        self.write_count += 1;
+        if self.write_count < 5 {
+            return;
+        }
+
+        let value = self.shift_register;
+        match address {
+            0x8000..=0x9FFF => self.control = value,
+            0xA000..=0xBFFF => self.character_bank_0 = value,
+            0xC000..=0xDFFF => self.character_bank_1 = value,
+            _ => self.program_bank = value,
+        }
+
+        self.shift_register = 0;
+        self.write_count = 0;
+    }
+
+    fn program_ram_enabled(&self) -> bool {
+        self.program_bank & 0b10000 == 0
+    }
+
+    /// Offset of the 8KB PRG-RAM bank in the PRG-RAM.
+    fn program_ram_offset(&self) -> usize {
+        let bank = match self.board {
+            Mmc1Board::Sorom => (self.character_bank_0 >> 3) & 0b01,
+            Mmc1Board::Sxrom => (self.character_bank_0 >> 2) & 0b11,
+            _ => 0,
+        } as usize;
+
+        let bank_count = (self.program_ram.len() / PROGRAM_RAM_BANK_SIZE).max(1);
+        (bank % bank_count) * PROGRAM_RAM_BANK_SIZE
+    }
+
+    fn program_rom_offset(&self, address: u16) -> usize {
+        let window = match self.board {
+            Mmc1Board::Surom | Mmc1Board::Sxrom => {
+                ((self.character_bank_0 >> 4) & 0b1) as usize * PROGRAM_WINDOW_BANKS
+            }
+            _ => 0,
+        };
+
+        let bank = (self.program_bank & 0b01111) as usize;
+        let is_upper = address >= 0xC000;
+        let bank = match (self.control >> 2) & 0b11 {
+            0 | 1 => (bank & !1) + is_upper as usize,
+            2 => match is_upper {
+                false => 0,
+                true => bank,
+            },
+            _ => match is_upper {
+                false => bank,
+                true => PROGRAM_WINDOW_BANKS - 1,
+            },
+        };
+
+        let bank_count = self.program_rom.len() / PROGRAM_BANK_SIZE;
+        let bank = (window + bank) % bank_count;
+        bank * PROGRAM_BANK_SIZE + (address as usize & (PROGRAM_BANK_SIZE - 1))
+    }
+
+    fn character_offset(&self, address: u16) -> usize {
+        let is_upper = address >= 0x1000;
+        let bank = match self.control & 0b10000 {
+            0 => (self.character_bank_0 & !1) as usize + is_upper as usize,
+            _ => match is_upper {
+                false => self.character_bank_0 as usize,
+                true => self.character_bank_1 as usize,
+            },
+        };
+
+        let bank_count = (self.character.len() / CHARACTER_BANK_SIZE).max(1);
+        let offset = (bank % bank_count) * CHARACTER_BANK_SIZE;
+        offset + (address as usize & (CHARACTER_BANK_SIZE - 1))
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn is_cpu_mapped(&self, address: u16) -> bool {
+        match address {
+            0x6000..=0x7FFF => self.program_ram_enabled() && !self.program_ram.is_empty(),
+            _ => address >= 0x8000,
+        }
+    }
+
+    fn cpu_read(&mut self, address: u16) -> u8 {
+        match address {
+            0x6000..=0x7FFF if self.program_ram_enabled() => {
+                let offset = self.program_ram_offset() + (address - 0x6000) as usize;
+                *self.program_ram.read(offset as u16)
+            }
+            0x8000..=0xFFFF => self.program_rom[self.program_rom_offset(address)],
+            // Nothing is connected, or PRG-RAM is disabled.
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address {
+            0x6000..=0x7FFF if self.program_ram_enabled() => {
+                let offset = self.program_ram_offset() + (address - 0x6000) as usize;
+                self.program_ram.write(offset as u16, data);
+            }
+            0x8000..=0xFFFF => self.write_register(address, data),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.character.read(self.character_offset(address))
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        let offset = self.character_offset(address);
+        self.character.write(offset, data);
+    }
+
+    fn mirroring(&self) -> MirroringType {
+        match self.control & 0b11 {
+            0 => MirroringType::SingleScreenA,
+            1 => MirroringType::SingleScreenB,
+            2 => MirroringType::Vertical,
+            _ => MirroringType::Horizontal,
+        }
+    }
+
+    fn character_memory(&self) -> &CharacterMemory {
+        &self.character
+    }
+
+    fn program_ram(&self) -> &[u8] {
+        self.program_ram.as_slice()
+    }
+
+    fn load_program_ram(&mut self, data: &[u8]) {
+        self.program_ram.load(data);
+    }
+
+    fn clock(&mut self, cycle: u16) {
+        self.cycle += cycle as u64;
+    }
+}
+
+#[cfg(test)]
+mod mmc1_test {
+    use crate::nes::mapper::build_rom;
+
+    use super::*;
+
+    /// Write a register through the shift register. A CPU instruction runs between each write.
+    fn write_serial(mmc1: &mut Mmc1, address: u16, value: u8) {
+        for i in 0..5 {
+            mmc1.cpu_write(address, (value >> i) & 0b1);
+            mmc1.clock(2);
+        }
+    }
+
+    #[test]
+    fn detect_board_test() {
+        assert_eq!(Mmc1Board::detect(0x40000, 0x2000), Mmc1Board::Standard);
+        assert_eq!(Mmc1Board::detect(0x40000, 0x4000), Mmc1Board::Sorom);
+        assert_eq!(Mmc1Board::detect(0x80000, 0x2000), Mmc1Board::Surom);
+        assert_eq!(Mmc1Board::detect(0x80000, 0x8000), Mmc1Board::Sxrom);
+    }
+
+    #[test]
+    fn shift_register_test() {
+        let mut mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 8),
+            CharacterMemory::ram(0x2000),
+            0x2000,
+        );
+
+        write_serial(&mut mmc1, 0x8000, 0b00010);
+        assert_eq!(mmc1.control, 0b00010);
+        assert_eq!(mmc1.mirroring(), MirroringType::Vertical);
+
+        // Reset in the middle of the serial writes.
+        mmc1.cpu_write(0xE000, 0b1);
+        mmc1.clock(2);
+        mmc1.cpu_write(0xE000, 0b10000000);
+        mmc1.clock(2);
+        assert_eq!(mmc1.write_count, 0);
+        assert_eq!(mmc1.control, 0b01110);
+
+        write_serial(&mut mmc1, 0xE000, 0b00011);
+        assert_eq!(mmc1.program_bank, 0b00011);
+    }
+
+    #[test]
+    fn ignore_consecutive_write_test() {
+        let mut mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 8),
+            CharacterMemory::ram(0x2000),
+            0x2000,
+        );
+
+        // Read-modify-write instructions write twice in a row.
+        mmc1.cpu_write(0x8000, 0b1);
+        mmc1.cpu_write(0x8000, 0b0);
+        assert_eq!(mmc1.write_count, 1);
+        assert_eq!(mmc1.shift_register, 0b10000);
+
+        mmc1.clock(6);
+        mmc1.cpu_write(0x8000, 0b0);
+        assert_eq!(mmc1.write_count, 2);
+    }
+
+    #[test]
+    fn program_bank_mode_test() {
+        let mut mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 8),
+            CharacterMemory::ram(0x2000),
+            0x2000,
+        );
+
+        // Mode 3 (power-on): switch $8000, fix the last bank at $C000
+        write_serial(&mut mmc1, 0xE000, 2);
+        assert_eq!(mmc1.cpu_read(0x8000), 2);
+        assert_eq!(mmc1.cpu_read(0xFFFF), 7);
+
+        // Mode 2: fix the first bank at $8000, switch $C000
+        write_serial(&mut mmc1, 0x8000, 0b01000);
+        assert_eq!(mmc1.cpu_read(0x8000), 0);
+        assert_eq!(mmc1.cpu_read(0xC000), 2);
+
+        // Mode 0: switch 32KB, ignoring the low bit
+        write_serial(&mut mmc1, 0x8000, 0b00000);
+        write_serial(&mut mmc1, 0xE000, 5);
+        assert_eq!(mmc1.cpu_read(0x8000), 4);
+        assert_eq!(mmc1.cpu_read(0xC000), 5);
+    }
+
+    #[test]
+    fn character_bank_mode_test() {
+        let mut mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 2),
+            CharacterMemory::rom(build_rom(CHARACTER_BANK_SIZE, 8)),
+            0x2000,
+        );
+
+        // 8KB mode, ignoring the low bit
+        write_serial(&mut mmc1, 0xA000, 3);
+        assert_eq!(mmc1.ppu_read(0x0000), 2);
+        assert_eq!(mmc1.ppu_read(0x1000), 3);
+
+        // 4KB mode
+        write_serial(&mut mmc1, 0x8000, 0b10000);
+        write_serial(&mut mmc1, 0xA000, 5);
+        write_serial(&mut mmc1, 0xC000, 6);
+        assert_eq!(mmc1.ppu_read(0x0000), 5);
+        assert_eq!(mmc1.ppu_read(0x1FFF), 6);
+
+        // CHR-ROM can not be written.
+        mmc1.ppu_write(0x0000, 0xFF);
+        assert_eq!(mmc1.ppu_read(0x0000), 5);
+    }
+
+    #[test]
+    fn program_ram_enable_test() {
+        let mut mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 2),
+            CharacterMemory::ram(0x2000),
+            0x2000,
+        );
+
+        mmc1.cpu_write(0x6000, 0x12);
+        mmc1.clock(4);
+        assert_eq!(mmc1.cpu_read(0x6000), 0x12);
+
+        // Disable PRG-RAM
+        write_serial(&mut mmc1, 0xE000, 0b10000);
+        assert!(!mmc1.is_cpu_mapped(0x6000));
+        assert_eq!(mmc1.cpu_read(0x6000), 0x00);
+        mmc1.cpu_write(0x6000, 0x34);
+        mmc1.clock(4);
+
+        write_serial(&mut mmc1, 0xE000, 0b00000);
+        assert!(mmc1.is_cpu_mapped(0x6000));
+        assert_eq!(mmc1.cpu_read(0x6000), 0x12);
+    }
+
+    #[test]
+    fn no_program_ram_test() {
+        let mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 2),
+            CharacterMemory::ram(0x2000),
+            0,
+        );
+
+        assert!(!mmc1.is_cpu_mapped(0x6000));
+        assert!(mmc1.program_ram().is_empty());
+    }
+
+    #[test]
+    fn mirroring_test() {
+        let mut mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 2),
+            CharacterMemory::ram(0x2000),
+            0x2000,
+        );
+
+        let expects = [
+            MirroringType::SingleScreenA,
+            MirroringType::SingleScreenB,
+            MirroringType::Vertical,
+            MirroringType::Horizontal,
+        ];
+        for (value, expect) in expects.iter().enumerate() {
+            write_serial(&mut mmc1, 0x8000, value as u8);
+            assert_eq!(mmc1.mirroring(), *expect);
+        }
+    }
+
+    #[test]
+    fn surom_test() {
+        let mut mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 32),
+            CharacterMemory::ram(0x2000),
+            0x2000,
+        );
+        assert_eq!(mmc1.board(), Mmc1Board::Surom);
+
+        // First 256KB
+        write_serial(&mut mmc1, 0xE000, 1);
+        assert_eq!(mmc1.cpu_read(0x8000), 1);
+        assert_eq!(mmc1.cpu_read(0xC000), 15);
+
+        // Second 256KB is selected by bit 4 of CHR bank 0.
+        write_serial(&mut mmc1, 0xA000, 0b10000);
+        assert_eq!(mmc1.cpu_read(0x8000), 17);
+        assert_eq!(mmc1.cpu_read(0xC000), 31);
+    }
+
+    #[test]
+    fn sorom_test() {
+        let mut mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 16),
+            CharacterMemory::ram(0x2000),
+            0x4000,
+        );
+        assert_eq!(mmc1.board(), Mmc1Board::Sorom);
+
+        mmc1.cpu_write(0x6000, 0x11);
+        mmc1.clock(4);
+
+        // The second 8KB is selected by bit 3 of CHR bank 0.
+        write_serial(&mut mmc1, 0xA000, 0b01000);
+        assert_eq!(mmc1.cpu_read(0x6000), 0x00);
+        mmc1.cpu_write(0x6000, 0x22);
+        mmc1.clock(4);
+
+        write_serial(&mut mmc1, 0xA000, 0b00000);
+        assert_eq!(mmc1.cpu_read(0x6000), 0x11);
+    }
+
+    #[test]
+    fn sxrom_test() {
+        let mut mmc1 = Mmc1::new(
+            build_rom(PROGRAM_BANK_SIZE, 32),
+            CharacterMemory::ram(0x2000),
+            0x8000,
+        );
+        assert_eq!(mmc1.board(), Mmc1Board::Sxrom);
+
+        for bank in 0..4u8 {
+            write_serial(&mut mmc1, 0xA000, bank << 2);
+            mmc1.cpu_write(0x6000, bank + 1);
+            mmc1.clock(4);
+        }
+
+        write_serial(&mut mmc1, 0xA000, 0b01000);
+        assert_eq!(mmc1.cpu_read(0x6000), 3);
+
+        // Bit 4 still selects the 256KB PRG-ROM window.
+        write_serial(&mut mmc1, 0xA000, 0b10000);
+        assert_eq!(mmc1.cpu_read(0xC000), 31);
+        assert_eq!(mmc1.cpu_read(0x6000), 1);
+    }
+}
@@ -0,0 +1,461 @@
+use crate::nes::{cartridge::header::MirroringType, ram::Ram};
+
+use super::{character_memory::CharacterMemory, Mapper};
+
+const PROGRAM_BANK_SIZE: usize = 0x2000; // 8KB
+const CHARACTER_BANK_SIZE: usize = 0x0400; // 1KB
+
+/// A12 must stay low for some CPU cycles before a rising edge clocks the IRQ counter.
+/// This filters the edges between the fetches of the 8 sprites of a scanline.
+const A12_FILTER_CYCLES: u64 = 3;
+
+/* Revisions of the IRQ counter
+https://www.nesdev.org/wiki/MMC3#IRQ_Specifics
+
+RevA (NEC) : IRQ is raised when the counter becomes 0 by decrement, or by a reload requested by $C001.
+             A latch of 0 raises IRQ only once.
+RevB (Sharp): IRQ is raised whenever the counter is 0 after it is clocked.
+              A latch of 0 raises IRQ on every scanline.
+*/
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Mmc3Revision {
+    RevA,
+    RevB,
+}
+
+/* MMC3 (mapper 4)
+https://www.nesdev.org/wiki/MMC3
+
+$8000-$9FFE (even): Bank select   (CPxx xRRR: C = CHR inversion, P = PRG mode, RRR = register)
+$8001-$9FFF (odd) : Bank data     (value of the selected register R0-R7)
+$A000-$BFFE (even): Mirroring     (0: vertical, 1: horizontal)
+$A001-$BFFF (odd) : PRG-RAM protect (EWxx xxxx: E = enable, W = deny writes)
+$C000-$DFFE (even): IRQ latch
+$C001-$DFFF (odd) : IRQ reload
+$E000-$FFFE (even): IRQ disable (and acknowledge)
+$E001-$FFFF (odd) : IRQ enable
+
+PRG mode 0: $8000 = R6, $A000 = R7, $C000 = second last, $E000 = last
+PRG mode 1: $8000 = second last, $A000 = R7, $C000 = R6, $E000 = last
+
+CHR inversion 0: $0000 = R0 (2KB), $0800 = R1 (2KB), $1000-$1C00 = R2-R5 (1KB)
+CHR inversion 1: $0000-$0C00 = R2-R5 (1KB), $1000 = R0 (2KB), $1800 = R1 (2KB)
+*/
+pub struct Mmc3 {
+    revision: Mmc3Revision,
+    program_rom: Vec<u8>,
+    program_ram: Ram,
+    character: CharacterMemory,
+    bank_select: u8,
+    bank_registers: [u8; 8],
+    mirroring: MirroringType,
+    program_ram_enabled: bool,
+    program_ram_write_protected: bool,
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+    /// CPU cycles counted by `clock`.
+    cycle: u64,
+    /// Cycle when A12 went low. None while A12 is high.
+    a12_low_since: Option<u64>,
+}
+
+impl Mmc3 {
+    /// PRG-RAM smaller than 8KB is mirrored. (ex: 1KB of MMC6)
+    pub fn new(
+        program_rom: Vec<u8>,
+        character: CharacterMemory,
+        program_ram_size: usize,
+        mirroring: MirroringType,
+        revision: Mmc3Revision,
+    ) -> Self {
+        Mmc3 {
+            revision,
+            program_rom,
+            program_ram: Ram::from_vec(vec![0; program_ram_size]),
+            character,
+            bank_select: 0,
+            bank_registers: [0; 8],
+            mirroring,
+            program_ram_enabled: true,
+            program_ram_write_protected: false,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+            cycle: 0,
+            a12_low_since: Some(0),
+        }
+    }
+
+    fn write_register(&mut self, address: u16, data: u8) {
+        let is_odd = address & 0b1 == 1;
+
+        match (address, is_odd) {
+            (0x8000..=0x9FFF, false) => self.bank_select = data,
+            (0x8000..=0x9FFF, true) => {
+                self.bank_registers[(self.bank_select & 0b111) as usize] = data
+            }
+            (0xA000..=0xBFFF, false) => {
+                // Four-screen boards have their own VRAM, and ignore this register.
+                if self.mirroring != MirroringType::FourScreen {
+                    self.mirroring = match data & 0b1 {
+                        0 => MirroringType::Vertical,
+                        _ => MirroringType::Horizontal,
+                    };
+                }
+            }
+            (0xA000..=0xBFFF, true) => {
+                self.program_ram_enabled = data & 0b10000000 != 0;
+                self.program_ram_write_protected = data & 0b01000000 != 0;
+            }
+            (0xC000..=0xDFFF, false) => self.irq_latch = data,
+            (0xC000..=0xDFFF, true) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            (0xE000..=0xFFFF, false) => {
+                self.irq_enabled = false;
+                self.irq_pending = false;
+            }
+            _ => self.irq_enabled = true,
+        }
+    }
+
+    fn program_rom_offset(&self, address: u16) -> usize {
+        let bank_count = self.program_rom.len() / PROGRAM_BANK_SIZE;
+        let second_last = bank_count - 2;
+        let is_inverted = self.bank_select & 0b01000000 != 0;
+
+        let bank = match (address, is_inverted) {
+            (0x8000..=0x9FFF, false) | (0xC000..=0xDFFF, true) => {
+                (self.bank_registers[6] & 0b00111111) as usize
+            }
+            (0x8000..=0x9FFF, true) | (0xC000..=0xDFFF, false) => second_last,
+            (0xA000..=0xBFFF, _) => (self.bank_registers[7] & 0b00111111) as usize,
+            _ => bank_count - 1,
+        };
+
+        (bank % bank_count) * PROGRAM_BANK_SIZE + (address as usize & (PROGRAM_BANK_SIZE - 1))
+    }
+
+    fn character_offset(&self, address: u16) -> usize {
+        // CHR inversion swaps $0000-$0FFF and $1000-$1FFF.
+        let address = match self.bank_select & 0b10000000 {
+            0 => address,
+            _ => address ^ 0x1000,
+        } as usize;
+
+        let bank = match address / CHARACTER_BANK_SIZE {
+            // 2KB banks ignore the low bit.
+            0 => self.bank_registers[0] & !1,
+            1 => self.bank_registers[0] | 1,
+            2 => self.bank_registers[1] & !1,
+            3 => self.bank_registers[1] | 1,
+            slot => self.bank_registers[slot - 2],
+        } as usize;
+
+        let bank_count = (self.character.len() / CHARACTER_BANK_SIZE).max(1);
+        (bank % bank_count) * CHARACTER_BANK_SIZE + (address & (CHARACTER_BANK_SIZE - 1))
+    }
+
+    /// The PPU drives the address bus on every pattern access. A rising edge of A12 clocks the IRQ counter.
+    fn watch_a12(&mut self, address: u16) {
+        let a12 = address & 0x1000 != 0;
+
+        match (a12, self.a12_low_since) {
+            (true, Some(low_since)) => {
+                if self.cycle - low_since >= A12_FILTER_CYCLES {
+                    self.clock_irq_counter();
+                }
+                self.a12_low_since = None;
+            }
+            (false, None) => self.a12_low_since = Some(self.cycle),
+            _ => {}
+        }
+    }
+
+    fn clock_irq_counter(&mut self) {
+        let previous = self.irq_counter;
+
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+        } else {
+            self.irq_counter -= 1;
+        }
+
+        let raise = match self.revision {
+            Mmc3Revision::RevA => (previous > 0 || self.irq_reload) && self.irq_counter == 0,
+            Mmc3Revision::RevB => self.irq_counter == 0,
+        };
+        if raise && self.irq_enabled {
+            self.irq_pending = true;
+        }
+
+        self.irq_reload = false;
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn is_cpu_mapped(&self, address: u16) -> bool {
+        match address {
+            0x6000..=0x7FFF => self.program_ram_enabled && !self.program_ram.is_empty(),
+            _ => address >= 0x8000,
+        }
+    }
+
+    fn cpu_read(&mut self, address: u16) -> u8 {
+        match address {
+            0x6000..=0x7FFF if self.program_ram_enabled => *self.program_ram.read(address - 0x6000),
+            0x8000..=0xFFFF => self.program_rom[self.program_rom_offset(address)],
+            // Nothing is connected, or PRG-RAM is disabled.
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address {
+            0x6000..=0x7FFF if self.program_ram_enabled && !self.program_ram_write_protected => {
+                self.program_ram.write(address - 0x6000, data);
+            }
+            0x8000..=0xFFFF => self.write_register(address, data),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.watch_a12(address);
+        self.character.read(self.character_offset(address))
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        self.watch_a12(address);
+
+        let offset = self.character_offset(address);
+        self.character.write(offset, data);
+    }
+
+    fn mirroring(&self) -> MirroringType {
+        self.mirroring
+    }
+
+    fn character_memory(&self) -> &CharacterMemory {
+        &self.character
+    }
+
+    fn program_ram(&self) -> &[u8] {
+        self.program_ram.as_slice()
+    }
+
+    fn load_program_ram(&mut self, data: &[u8]) {
+        self.program_ram.load(data);
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn clock(&mut self, cycle: u16) {
+        self.cycle += cycle as u64;
+    }
+}
+
+#[cfg(test)]
+mod mmc3_test {
+    use crate::nes::mapper::build_rom;
+
+    use super::*;
+
+    fn build_mmc3(revision: Mmc3Revision) -> Mmc3 {
+        Mmc3::new(
+            build_rom(PROGRAM_BANK_SIZE, 16),
+            CharacterMemory::rom(build_rom(CHARACTER_BANK_SIZE, 64)),
+            0x2000,
+            MirroringType::Vertical,
+            revision,
+        )
+    }
+
+    fn write_bank(mmc3: &mut Mmc3, bank_select: u8, data: u8) {
+        mmc3.cpu_write(0x8000, bank_select);
+        mmc3.cpu_write(0x8001, data);
+    }
+
+    /// A scanline of rendering: background from $0000, then sprites from $1000.
+    fn render_scanline(mmc3: &mut Mmc3) {
+        mmc3.ppu_read(0x0000);
+        mmc3.clock(100);
+        mmc3.ppu_read(0x1000);
+        mmc3.clock(14);
+    }
+
+    #[test]
+    fn program_bank_mode_test() {
+        let mut mmc3 = build_mmc3(Mmc3Revision::RevB);
+        write_bank(&mut mmc3, 6, 3);
+        write_bank(&mut mmc3, 7, 5);
+
+        // PRG mode 0
+        assert_eq!(mmc3.cpu_read(0x8000), 3);
+        assert_eq!(mmc3.cpu_read(0xA000), 5);
+        assert_eq!(mmc3.cpu_read(0xC000), 14);
+        assert_eq!(mmc3.cpu_read(0xE000), 15);
+
+        // PRG mode 1
+        mmc3.cpu_write(0x8000, 0b01000000);
+        assert_eq!(mmc3.cpu_read(0x8000), 14);
+        assert_eq!(mmc3.cpu_read(0xA000), 5);
+        assert_eq!(mmc3.cpu_read(0xC000), 3);
+        assert_eq!(mmc3.cpu_read(0xFFFF), 15);
+    }
+
+    #[test]
+    fn character_bank_mode_test() {
+        let mut mmc3 = build_mmc3(Mmc3Revision::RevB);
+        write_bank(&mut mmc3, 0, 9); // 2KB, the low bit is ignored.
+        write_bank(&mut mmc3, 1, 20);
+        for (register, bank) in (2..=5).zip([30, 31, 32, 33]) {
+            write_bank(&mut mmc3, register, bank);
+        }
+
+        // CHR inversion 0
+        assert_eq!(mmc3.ppu_read(0x0000), 8);
+        assert_eq!(mmc3.ppu_read(0x0400), 9);
+        assert_eq!(mmc3.ppu_read(0x0800), 20);
+        assert_eq!(mmc3.ppu_read(0x0C00), 21);
+        assert_eq!(mmc3.ppu_read(0x1000), 30);
+        assert_eq!(mmc3.ppu_read(0x1C00), 33);
+
+        // CHR inversion 1
+        mmc3.cpu_write(0x8000, 0b10000000);
+        assert_eq!(mmc3.ppu_read(0x0000), 30);
+        assert_eq!(mmc3.ppu_read(0x0C00), 33);
+        assert_eq!(mmc3.ppu_read(0x1000), 8);
+        assert_eq!(mmc3.ppu_read(0x1800), 20);
+    }
+
+    #[test]
+    fn mirroring_test() {
+        let mut mmc3 = build_mmc3(Mmc3Revision::RevB);
+
+        mmc3.cpu_write(0xA000, 1);
+        assert_eq!(mmc3.mirroring(), MirroringType::Horizontal);
+        mmc3.cpu_write(0xA000, 0);
+        assert_eq!(mmc3.mirroring(), MirroringType::Vertical);
+
+        // Four-screen boards ignore the register.
+        let mut mmc3 = Mmc3::new(
+            build_rom(PROGRAM_BANK_SIZE, 4),
+            CharacterMemory::ram(0x2000),
+            0x2000,
+            MirroringType::FourScreen,
+            Mmc3Revision::RevB,
+        );
+        mmc3.cpu_write(0xA000, 1);
+        assert_eq!(mmc3.mirroring(), MirroringType::FourScreen);
+    }
+
+    #[test]
+    fn program_ram_protect_test() {
+        let mut mmc3 = build_mmc3(Mmc3Revision::RevB);
+
+        mmc3.cpu_write(0x6000, 0x12);
+        assert_eq!(mmc3.cpu_read(0x6000), 0x12);
+
+        // Deny writes
+        mmc3.cpu_write(0xA001, 0b11000000);
+        mmc3.cpu_write(0x6000, 0x34);
+        assert_eq!(mmc3.cpu_read(0x6000), 0x12);
+
+        // Disable the chip
+        mmc3.cpu_write(0xA001, 0b00000000);
+        assert_eq!(mmc3.cpu_read(0x6000), 0x00);
+    }
+
+    #[test]
+    fn no_program_ram_test() {
+        let mut mmc3 = Mmc3::new(
+            build_rom(PROGRAM_BANK_SIZE, 4),
+            CharacterMemory::ram(0x2000),
+            0,
+            MirroringType::Vertical,
+            Mmc3Revision::RevB,
+        );
+
+        mmc3.cpu_write(0xA001, 0b10000000);
+        assert!(!mmc3.is_cpu_mapped(0x6000));
+        assert!(mmc3.program_ram().is_empty());
+    }
+
+    #[test]
+    fn irq_counter_test() {
+        let mut mmc3 = build_mmc3(Mmc3Revision::RevB);
+        mmc3.cpu_write(0xC000, 2); // latch
+        mmc3.cpu_write(0xC001, 0); // reload
+        mmc3.cpu_write(0xE001, 0); // enable
+
+        render_scanline(&mut mmc3); // reload: 2
+        render_scanline(&mut mmc3); // 1
+        assert!(!mmc3.irq());
+        render_scanline(&mut mmc3); // 0
+        assert!(mmc3.irq());
+
+        // Disable acknowledges the IRQ.
+        mmc3.cpu_write(0xE000, 0);
+        assert!(!mmc3.irq());
+        render_scanline(&mut mmc3); // reload: 2
+        render_scanline(&mut mmc3); // 1
+        render_scanline(&mut mmc3); // 0
+        assert!(!mmc3.irq());
+    }
+
+    #[test]
+    fn a12_filter_test() {
+        let mut mmc3 = build_mmc3(Mmc3Revision::RevB);
+        mmc3.cpu_write(0xC000, 5);
+        mmc3.cpu_write(0xC001, 0);
+
+        render_scanline(&mut mmc3);
+        assert_eq!(mmc3.irq_counter, 5);
+
+        // Sprite fetches toggle A12 quickly, which does not clock the counter.
+        mmc3.ppu_read(0x0000);
+        mmc3.ppu_read(0x1000);
+        mmc3.ppu_read(0x0000);
+        mmc3.clock(1);
+        mmc3.ppu_read(0x1000);
+        assert_eq!(mmc3.irq_counter, 5);
+
+        render_scanline(&mut mmc3);
+        assert_eq!(mmc3.irq_counter, 4);
+    }
+
+    #[test]
+    fn zero_latch_revision_test() {
+        // RevB raises IRQ on every scanline with a latch of 0.
+        let mut mmc3 = build_mmc3(Mmc3Revision::RevB);
+        mmc3.cpu_write(0xE001, 0);
+        render_scanline(&mut mmc3);
+        assert!(mmc3.irq());
+        mmc3.cpu_write(0xE000, 0);
+        mmc3.cpu_write(0xE001, 0);
+        render_scanline(&mut mmc3);
+        assert!(mmc3.irq());
+
+        // RevA raises IRQ only by the reload of $C001.
+        let mut mmc3 = build_mmc3(Mmc3Revision::RevA);
+        mmc3.cpu_write(0xE001, 0);
+        render_scanline(&mut mmc3);
+        assert!(!mmc3.irq());
+        mmc3.cpu_write(0xC001, 0);
+        render_scanline(&mut mmc3);
+        assert!(mmc3.irq());
+        mmc3.cpu_write(0xE000, 0);
+        mmc3.cpu_write(0xE001, 0);
+        render_scanline(&mut mmc3);
+        assert!(!mmc3.irq());
+    }
+}
@@ -0,0 +1,215 @@
+use crate::nes::{cartridge::header::MirroringType, ram::Ram};
+
+use super::{character_memory::CharacterMemory, Mapper};
+
+/* NROM (mapper 0)
+https://www.nesdev.org/wiki/NROM
+
+No bank switching.
+
+CPU $6000-$7FFF: PRG-RAM (Family Basic only, open bus without PRG-RAM)
+CPU $8000-$BFFF: First 16KB of PRG-ROM
+CPU $C000-$FFFF: Last 16KB of PRG-ROM (NROM-128 mirrors $8000-$BFFF)
+PPU $0000-$1FFF: 8KB CHR-ROM (CHR-RAM if the cartridge has no CHR-ROM)
+*/
+pub struct Nrom {
+    program_rom: Vec<u8>,
+    program_ram: Ram,
+    character: CharacterMemory,
+    mirroring: MirroringType,
+}
+
+impl Nrom {
+    /// PRG-RAM smaller than 8KB is mirrored.
+    pub fn new(
+        program_rom: Vec<u8>,
+        character: CharacterMemory,
+        program_ram_size: usize,
+        mirroring: MirroringType,
+    ) -> Self {
+        Nrom {
+            program_rom,
+            program_ram: Ram::from_vec(vec![0; program_ram_size]),
+            character,
+            mirroring,
+        }
+    }
+}
+
+impl Mapper for Nrom {
+    fn is_cpu_mapped(&self, address: u16) -> bool {
+        match address {
+            0x6000..=0x7FFF => !self.program_ram.is_empty(),
+            _ => address >= 0x8000,
+        }
+    }
+
+    fn cpu_read(&mut self, address: u16) -> u8 {
+        match address {
+            0x6000..=0x7FFF => *self.program_ram.read(address - 0x6000),
+            // if program rom size is 16kb, 0xC000..=0xFFFF mirrors 0x8000..=0xBFFF
+            0x8000..=0xFFFF => {
+                self.program_rom[(address - 0x8000) as usize % self.program_rom.len()]
+            }
+            // Nothing is connected.
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        if let 0x6000..=0x7FFF = address {
+            self.program_ram.write(address - 0x6000, data);
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.character.read(address as usize)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        self.character.write(address as usize, data);
+    }
+
+    fn mirroring(&self) -> MirroringType {
+        self.mirroring
+    }
+
+    fn character_memory(&self) -> &CharacterMemory {
+        &self.character
+    }
+
+    fn program_ram(&self) -> &[u8] {
+        self.program_ram.as_slice()
+    }
+
+    fn load_program_ram(&mut self, data: &[u8]) {
+        self.program_ram.load(data);
+    }
+}
+
+#[cfg(test)]
+mod nrom_test {
+    use super::*;
+
+    #[test]
+    fn program_rom_32kb_read_test() {
+        let mut program_rom = vec![0x00; 0x8000];
+        program_rom[0x0000] = 0x01;
+        program_rom[0x3FFF] = 0x02;
+        program_rom[0x4000] = 0x03;
+        program_rom[0x7FFF] = 0x04;
+
+        let mut nrom = Nrom::new(
+            program_rom,
+            CharacterMemory::rom(vec![0; 0x2000]),
+            0x2000,
+            MirroringType::Horizontal,
+        );
+
+        assert_eq!(nrom.cpu_read(0x8000), 0x01);
+        assert_eq!(nrom.cpu_read(0xBFFF), 0x02);
+        assert_eq!(nrom.cpu_read(0xC000), 0x03);
+        assert_eq!(nrom.cpu_read(0xFFFF), 0x04);
+    }
+
+    #[test]
+    fn program_rom_16kb_read_test() {
+        let mut program_rom = vec![0x00; 0x4000];
+        program_rom[0x0000] = 0x01;
+        program_rom[0x3FFF] = 0x02;
+
+        let mut nrom = Nrom::new(
+            program_rom,
+            CharacterMemory::rom(vec![0; 0x2000]),
+            0x2000,
+            MirroringType::Horizontal,
+        );
+
+        assert_eq!(nrom.cpu_read(0x8000), 0x01);
+        assert_eq!(nrom.cpu_read(0xBFFF), 0x02);
+        // Mirror
+        assert_eq!(nrom.cpu_read(0xC000), 0x01);
+        assert_eq!(nrom.cpu_read(0xFFFF), 0x02);
+    }
+
+    #[test]
+    fn program_ram_test() {
+        let mut nrom = Nrom::new(
+            vec![0; 0x4000],
+            CharacterMemory::rom(vec![0; 0x2000]),
+            0x2000,
+            MirroringType::Horizontal,
+        );
+
+        nrom.cpu_write(0x6000, 0x11);
+        nrom.cpu_write(0x7FFF, 0x22);
+        assert_eq!(nrom.cpu_read(0x6000), 0x11);
+        assert_eq!(nrom.cpu_read(0x7FFF), 0x22);
+
+        // PRG-ROM is not written.
+        nrom.cpu_write(0x8000, 0x33);
+        assert_eq!(nrom.cpu_read(0x8000), 0x00);
+    }
+
+    #[test]
+    fn no_program_ram_test() {
+        let nrom = Nrom::new(
+            vec![0; 0x4000],
+            CharacterMemory::rom(vec![0; 0x2000]),
+            0,
+            MirroringType::Horizontal,
+        );
+
+        // $6000-$7FFF is open bus.
+        assert!(!nrom.is_cpu_mapped(0x6000));
+        assert!(!nrom.is_cpu_mapped(0x7FFF));
+        assert!(nrom.is_cpu_mapped(0x8000));
+        assert!(nrom.program_ram().is_empty());
+    }
+
+    #[test]
+    fn load_program_ram_test() {
+        let mut nrom = Nrom::new(
+            vec![0; 0x4000],
+            CharacterMemory::rom(vec![0; 0x2000]),
+            0x2000,
+            MirroringType::Horizontal,
+        );
+
+        nrom.load_program_ram(&[0x11, 0x22]);
+        assert_eq!(nrom.cpu_read(0x6000), 0x11);
+        assert_eq!(nrom.cpu_read(0x6001), 0x22);
+        assert_eq!(nrom.program_ram().len(), 0x2000);
+    }
+
+    #[test]
+    fn character_rom_test() {
+        let mut character_rom = vec![0x00; 0x2000];
+        character_rom[0x1FFF] = 0x01;
+
+        let mut nrom = Nrom::new(
+            vec![0; 0x4000],
+            CharacterMemory::rom(character_rom),
+            0x2000,
+            MirroringType::Horizontal,
+        );
+        assert_eq!(nrom.ppu_read(0x1FFF), 0x01);
+
+        nrom.ppu_write(0x1FFF, 0x02);
+        assert_eq!(nrom.ppu_read(0x1FFF), 0x01);
+    }
+
+    #[test]
+    fn character_ram_test() {
+        let mut nrom = Nrom::new(
+            vec![0; 0x4000],
+            CharacterMemory::ram(0x2000),
+            0x2000,
+            MirroringType::Vertical,
+        );
+
+        nrom.ppu_write(0x1FFF, 0x02);
+        assert_eq!(nrom.ppu_read(0x1FFF), 0x02);
+        assert_eq!(nrom.mirroring(), MirroringType::Vertical);
+    }
+}
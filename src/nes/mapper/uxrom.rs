@@ -0,0 +1,149 @@
+use crate::nes::cartridge::header::MirroringType;
+
+use super::{character_memory::CharacterMemory, Mapper};
+
+const PROGRAM_BANK_SIZE: usize = 0x4000; // 16KB
+
+/* UxROM (mapper 2)
+https://www.nesdev.org/wiki/UxROM
+
+CPU $8000-$BFFF: 16KB switchable PRG-ROM bank
+CPU $C000-$FFFF: 16KB PRG-ROM bank, fixed to the last bank
+PPU $0000-$1FFF: 8KB CHR (usually CHR-RAM)
+
+Writing to $8000-$FFFF selects the PRG-ROM bank.
+*/
+pub struct Uxrom {
+    program_rom: Vec<u8>,
+    character: CharacterMemory,
+    mirroring: MirroringType,
+    program_bank: u8,
+    /// The written value is ANDed with the ROM value at the address. (UNROM)
+    bus_conflicts: bool,
+}
+
+impl Uxrom {
+    pub fn new(
+        program_rom: Vec<u8>,
+        character: CharacterMemory,
+        mirroring: MirroringType,
+        bus_conflicts: bool,
+    ) -> Self {
+        Uxrom {
+            program_rom,
+            character,
+            mirroring,
+            program_bank: 0,
+            bus_conflicts,
+        }
+    }
+
+    fn program_rom_offset(&self, address: u16) -> usize {
+        let bank_count = self.program_rom.len() / PROGRAM_BANK_SIZE;
+        let bank = match address {
+            0x8000..=0xBFFF => self.program_bank as usize % bank_count,
+            _ => bank_count - 1,
+        };
+
+        bank * PROGRAM_BANK_SIZE + (address as usize & (PROGRAM_BANK_SIZE - 1))
+    }
+}
+
+impl Mapper for Uxrom {
+    fn cpu_read(&mut self, address: u16) -> u8 {
+        match address {
+            0x8000..=0xFFFF => self.program_rom[self.program_rom_offset(address)],
+            // Nothing is connected.
+            _ => 0,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        if let 0x8000..=0xFFFF = address {
+            self.program_bank = match self.bus_conflicts {
+                true => data & self.cpu_read(address),
+                false => data,
+            };
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.character.read(address as usize)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        self.character.write(address as usize, data);
+    }
+
+    fn mirroring(&self) -> MirroringType {
+        self.mirroring
+    }
+
+    fn character_memory(&self) -> &CharacterMemory {
+        &self.character
+    }
+}
+
+#[cfg(test)]
+mod uxrom_test {
+    use crate::nes::mapper::build_rom;
+
+    use super::*;
+
+    #[test]
+    fn switch_program_bank_test() {
+        let mut uxrom = Uxrom::new(
+            build_rom(PROGRAM_BANK_SIZE, 8),
+            CharacterMemory::ram(0x2000),
+            MirroringType::Vertical,
+            false,
+        );
+
+        assert_eq!(uxrom.cpu_read(0x8000), 0);
+        assert_eq!(uxrom.cpu_read(0xC000), 7);
+
+        uxrom.cpu_write(0x8000, 3);
+        assert_eq!(uxrom.cpu_read(0xBFFF), 3);
+        // The last bank is fixed.
+        assert_eq!(uxrom.cpu_read(0xFFFF), 7);
+    }
+
+    #[test]
+    fn bus_conflicts_test() {
+        let mut program_rom = build_rom(PROGRAM_BANK_SIZE, 8);
+        // $C000 reads 0x07 (the last bank).
+        let mut uxrom = Uxrom::new(
+            program_rom.clone(),
+            CharacterMemory::ram(0x2000),
+            MirroringType::Vertical,
+            true,
+        );
+        uxrom.cpu_write(0xC000, 0x0E);
+        assert_eq!(uxrom.cpu_read(0x8000), 0x06);
+
+        // Games write to a ROM byte which has the same value to avoid the conflict.
+        program_rom[0x7FFF] = 0xFF;
+        let mut uxrom = Uxrom::new(
+            program_rom,
+            CharacterMemory::ram(0x2000),
+            MirroringType::Vertical,
+            true,
+        );
+        uxrom.cpu_write(0xC000 + 0x3FFF, 0x05);
+        assert_eq!(uxrom.cpu_read(0x8000), 0x05);
+    }
+
+    #[test]
+    fn character_ram_test() {
+        let mut uxrom = Uxrom::new(
+            build_rom(PROGRAM_BANK_SIZE, 2),
+            CharacterMemory::ram(0x2000),
+            MirroringType::Vertical,
+            false,
+        );
+
+        uxrom.ppu_write(0x1FFF, 0x12);
+        assert_eq!(uxrom.ppu_read(0x1FFF), 0x12);
+        assert_eq!(uxrom.mirroring(), MirroringType::Vertical);
+    }
+}
@@ -1,15 +1,21 @@
 use core::time;
-use std::{fs::File, io::Read, thread};
+use std::{cell::Ref, time::Instant};
 
 use crate::nes::ppu::render::rendering_frame;
 
 use self::{
-    cartridge::Cartridge,
+    cartridge::{battery::BatterySave, header::TimingMode, Cartridge},
     cpu::{bus::CpuBus, cpu::Cpu, registers::CpuRegisters},
+    mapper::{build_mapper, Mapper, SharedMapper},
+    open_bus::OpenBus,
     ppu::{
         frame::Frame,
+        name_table::NameTable,
+        name_table_dump::NameTableDump,
+        palette_ram::PaletteType,
         pattern_table::PatternTable,
         ppu::{Ppu, PpuRunResult},
+        system_palette::SystemPalette,
     },
     ram::Ram,
 };
@@ -18,72 +24,199 @@ use anyhow::Result;
 pub mod bus;
 pub mod cartridge;
 pub mod cpu;
+pub mod mapper;
+pub mod open_bus;
 pub mod ppu;
 pub mod ram;
 
 const WRAM_SIZE: u16 = 2048;
-const VRAM_SIZE: u16 = 2048;
+/// Interval of saving battery-backed PRG-RAM while running.
+const BATTERY_SAVE_INTERVAL: time::Duration = time::Duration::from_secs(10);
 
 pub struct Nes {
-    cartridge: Cartridge,
+    mapper: SharedMapper,
     cpu_registers: CpuRegisters,
     ppu: Ppu,
     wram: Ram,
+    open_bus: OpenBus,
+    /// Region of the cartridge. Only NTSC timing is emulated for now.
+    timing: TimingMode,
+    /// Some if the cartridge has a battery.
+    battery_save: Option<BatterySave>,
+    last_battery_save: Instant,
 }
 
 impl Nes {
+    /// Errors on loading the ROM are `RomError`.
     pub fn new(path: &str) -> Result<Self> {
-        // ToDo
-        let mut f = File::open(path).unwrap();
-        let mut buffer: Vec<u8> = Vec::new();
-        f.read_to_end(&mut buffer).unwrap();
+        let cartridge = Cartridge::load(path)?;
+        let mapper = build_mapper(&cartridge)?;
+
+        let battery_save = match cartridge.header.has_battery_backup {
+            true => {
+                let mut battery_save = BatterySave::new(path);
+                if let Some(data) = battery_save.load()? {
+                    mapper.borrow_mut().load_program_ram(&data);
+                }
+                Some(battery_save)
+            }
+            false => None,
+        };
 
-        let cartridge = Cartridge::new(&buffer).unwrap();
         let cpu_registers = CpuRegisters::new();
         let wram = Ram::new(WRAM_SIZE);
 
-        let pattern_table =
-            PatternTable::new(Ram::from_vec(cartridge.character_rom.clone())).unwrap();
-        let vram = Ram::new(VRAM_SIZE);
-        let ppu = Ppu::new(pattern_table, vram);
+        let pattern_table = PatternTable::new(mapper.clone());
+        let name_table = NameTable::new(mapper.borrow().mirroring());
+        let ppu = Ppu::new(pattern_table, name_table);
 
         Ok(Nes {
-            cartridge,
+            mapper,
             cpu_registers,
             ppu,
             wram,
+            open_bus: OpenBus::new(),
+            timing: cartridge.header.timing,
+            battery_save,
+            last_battery_save: Instant::now(),
         })
     }
 
+    pub fn timing(&self) -> TimingMode {
+        self.timing
+    }
+
+    /// Report accesses to unmapped addresses on the CPU bus.
+    pub fn set_bus_diagnostics(&mut self, enabled: bool) {
+        self.open_bus.diagnostics = enabled;
+    }
+
+    pub fn set_system_palette(&mut self, system_palette: SystemPalette) {
+        self.ppu.system_palette = system_palette;
+    }
+
+    /// Run until `input_callback` returns false. Battery-backed PRG-RAM is saved on exit.
     pub fn run<'call, Fr, Fi>(&mut self, mut render_callback: Fr, mut input_callback: Fi)
     where
         Fr: FnMut(&Frame) + 'call,
-        Fi: FnMut() + 'call,
+        Fi: FnMut() -> bool + 'call,
     {
         loop {
-            let cycle = {
-                let mut cpu_bus =
-                    CpuBus::new(&self.cartridge.program_rom, &mut self.wram, &mut self.ppu);
-                Cpu::run(&mut self.cpu_registers, &mut cpu_bus)
+            if let Some(frame) = self.step() {
+                render_callback(&frame);
+            }
+
+            if !input_callback() {
+                break;
+            }
+        }
+
+        self.save_battery();
+    }
+
+    /// Run one CPU instruction and the PPU cycles of it.
+    /// Returns the frame when the PPU has finished it.
+    pub fn step(&mut self) -> Option<Frame> {
+        let irq = self.mapper.borrow().irq();
+        let cycle = {
+            let mut cpu_bus = CpuBus::new(
+                &self.mapper,
+                &mut self.wram,
+                &mut self.ppu,
+                &mut self.open_bus,
+            );
+            // The mapper holds the IRQ line until the game acknowledges it.
+            let interrupt = match irq {
+                true => Cpu::irq(&mut self.cpu_registers, &mut cpu_bus),
+                false => None,
             };
+            interrupt.unwrap_or_else(|| Cpu::run(&mut self.cpu_registers, &mut cpu_bus))
+        };
 
-            // println!("PPU context cycle: {:?} / line: {:?}", self.ppu.cycle, self.ppu.line);
+        self.mapper.borrow_mut().clock(cycle);
+        self.sync_mirroring();
 
-            match self.ppu.run(cycle * 3) {
-                PpuRunResult::FinishedBuildAllBackgroundLine => {
-                    println!("Finished build all background line.");
-                    let frame = rendering_frame(&self.ppu);
-                    render_callback(&frame);
-                    self.ppu.reset_background();
+        match self.ppu.run(cycle * 3) {
+            PpuRunResult::FinishedBuildAllBackgroundLine => {
+                let frame = rendering_frame(&self.ppu);
+                self.ppu.reset_background();
 
-                    // Debug sleep
-                    let ten_millis = time::Duration::from_millis(1000);
-                    thread::sleep(ten_millis);
+                if self.last_battery_save.elapsed() >= BATTERY_SAVE_INTERVAL {
+                    self.save_battery();
                 }
-                _ => {}
-            };
 
-            input_callback();
+                Some(frame)
+            }
+            _ => None,
+        }
+    }
+
+    /// Run until the PPU finishes `count` frames.
+    pub fn run_frames(&mut self, count: usize) {
+        let mut frames = 0;
+        while frames < count {
+            if self.step().is_some() {
+                frames += 1;
+            }
+        }
+    }
+
+    pub fn mapper(&self) -> Ref<'_, dyn Mapper> {
+        self.mapper.borrow()
+    }
+
+    /// Palette numbers of a palette in palette RAM.
+    pub fn palette_numbers(&self, palette_id: u8, palette_type: PaletteType) -> [u8; 4] {
+        let palettes = self.ppu.palette_ram.get_palettes(palette_id, palette_type);
+        [0, 1, 2, 3].map(|num| palettes.get(num).get_palette_number())
+    }
+
+    /// Render the 4 name tables in the current state of the PPU.
+    pub fn dump_name_tables(&self) -> Result<NameTableDump> {
+        NameTableDump::new(&self.ppu)
+    }
+
+    pub fn cpu_registers(&self) -> &CpuRegisters {
+        &self.cpu_registers
+    }
+
+    /// Read the CPU address space without side effects.
+    /// The PPU and APU registers are not read, and return the open bus value.
+    pub fn peek(&self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x1FFF => *self.wram.read(address),
+            0x4020..=0xFFFF if self.mapper.borrow().is_cpu_mapped(address) => {
+                self.mapper.borrow_mut().cpu_read(address)
+            }
+            _ => self.open_bus.value(),
+        }
+    }
+
+    /// Write PRG-RAM to the .sav file if the cartridge has a battery.
+    /// A failed save is reported, and the emulation keeps running.
+    pub fn save_battery(&mut self) {
+        self.last_battery_save = Instant::now();
+
+        let battery_save = match &mut self.battery_save {
+            Some(battery_save) => battery_save,
+            None => return,
+        };
+
+        let mapper = self.mapper.borrow();
+        if let Err(error) = battery_save.save(mapper.program_ram()) {
+            eprintln!(
+                "Failed to save {}: {}",
+                battery_save.path().display(),
+                error
+            );
+        }
+    }
+
+    /// Mappers can switch the name table mirroring by writing to their registers.
+    fn sync_mirroring(&mut self) {
+        let mirroring = self.mapper.borrow().mirroring();
+        if self.ppu.name_table.mirroring() != mirroring {
+            self.ppu.set_mirroring(mirroring);
         }
     }
 }
@@ -0,0 +1,75 @@
+/* Open bus
+https://www.nesdev.org/wiki/Open_bus_behavior
+
+Reading an address where nothing drives the data bus returns the last value on the bus.
+Writing to such an address does nothing.
+Games rarely depend on it, but they may touch unmapped addresses by accident.
+*/
+pub struct OpenBus {
+    value: u8,
+    /// Report accesses to unmapped addresses. (for debugging)
+    pub diagnostics: bool,
+}
+
+impl OpenBus {
+    pub fn new() -> Self {
+        OpenBus {
+            value: 0,
+            diagnostics: false,
+        }
+    }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    /// `data` is put on the bus by a read or a write.
+    pub fn latch(&mut self, data: u8) -> u8 {
+        self.value = data;
+        data
+    }
+
+    pub fn unmapped_read(&self, address: u16) -> u8 {
+        if self.diagnostics {
+            eprintln!(
+                "Unmapped read | Address: {:x} -> open bus {:x}",
+                address, self.value
+            );
+        }
+        self.value
+    }
+
+    pub fn unmapped_write(&mut self, address: u16, data: u8) {
+        if self.diagnostics {
+            eprintln!("Unmapped write | Address: {:x} Data: {:x}", address, data);
+        }
+        self.value = data;
+    }
+}
+
+impl Default for OpenBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod open_bus_test {
+    use super::*;
+
+    #[test]
+    fn latch_test() {
+        let mut open_bus = OpenBus::new();
+        assert_eq!(open_bus.unmapped_read(0x4020), 0x00);
+
+        assert_eq!(open_bus.latch(0x12), 0x12);
+        assert_eq!(open_bus.unmapped_read(0x4020), 0x12);
+    }
+
+    #[test]
+    fn unmapped_write_test() {
+        let mut open_bus = OpenBus::new();
+        open_bus.unmapped_write(0x4020, 0x34);
+        assert_eq!(open_bus.value(), 0x34);
+    }
+}
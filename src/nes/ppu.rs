@@ -1,11 +1,15 @@
 pub mod background;
 pub mod frame;
+pub mod name_table;
+pub mod name_table_dump;
 pub mod palette;
 pub mod palette_ram;
 pub mod pattern_table;
 pub mod ppu;
 pub mod registers;
 pub mod render;
+pub mod signal;
 pub mod sprite;
+pub mod system_palette;
 pub mod tile;
 pub mod tile_position;
@@ -1,3 +1,58 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::system_palette::SystemPalette;
+
+/* Pixel formats which the frame can be converted into.
+32 bits formats are packed into a native endian u32 (same as SDL).
+
+Rgb24   : [R, G, B]
+Rgba8888: 0xRRGGBBAA
+Argb8888: 0xAARRGGBB
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelFormat {
+    Rgb24,
+    Rgba8888,
+    Argb8888,
+}
+
+pub const PIXEL_FORMAT_NAMES: [&str; 3] = ["rgb24", "rgba8888", "argb8888"];
+
+impl PixelFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rgb24" => Some(PixelFormat::Rgb24),
+            "rgba8888" => Some(PixelFormat::Rgba8888),
+            "argb8888" => Some(PixelFormat::Argb8888),
+            _ => None,
+        }
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::Rgb24 => 3,
+            PixelFormat::Rgba8888 | PixelFormat::Argb8888 => 4,
+        }
+    }
+}
+
+/// Convert RGB24 bytes into `format`. Alpha is always opaque.
+pub fn convert_rgb24(rgb: &[u8], format: PixelFormat) -> Vec<u8> {
+    let pack = |f: fn(u32, u32, u32) -> u32| -> Vec<u8> {
+        rgb.chunks_exact(3)
+            .flat_map(|c| f(c[0] as u32, c[1] as u32, c[2] as u32).to_ne_bytes())
+            .collect()
+    };
+
+    match format {
+        PixelFormat::Rgb24 => rgb.to_vec(),
+        PixelFormat::Rgba8888 => pack(|r, g, b| r << 24 | g << 16 | b << 8 | 0xFF),
+        PixelFormat::Argb8888 => pack(|r, g, b| 0xFF << 24 | r << 16 | g << 8 | b),
+    }
+}
+
 pub struct Frame {
     /* Frame has WIDTH x HEIGHT pixels.
     pixel is represented by 3 bytes (RGB).
@@ -9,6 +64,12 @@ pub struct Frame {
     [0, 0, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0],
     */
     pub data: Vec<u8>,
+    /* Raw palette index of each pixel. (9 bits)
+    emphasis(3 bits: BGR) | palette number(6 bits)
+
+    Filters which emulate the video signal (ex: NTSC) need it instead of RGB.
+    */
+    pub indices: Vec<u16>,
 }
 
 impl Frame {
@@ -18,6 +79,7 @@ impl Frame {
     pub fn new() -> Self {
         Frame {
             data: vec![0; (Frame::WIDTH) * (Frame::HIGHT) * 3],
+            indices: vec![0; (Frame::WIDTH) * (Frame::HIGHT)],
         }
     }
 
@@ -34,12 +96,85 @@ impl Frame {
         let base = y * 3 * Frame::WIDTH + x * 3;
         (self.data[base], self.data[base + 1], self.data[base + 2])
     }
+
+    pub fn set_palette_index(&mut self, x: usize, y: usize, index: u16) {
+        let base = y * Frame::WIDTH + x;
+        if base < self.indices.len() {
+            self.indices[base] = index & 0x1FF;
+        }
+    }
+
+    pub fn get_palette_index(&self, x: usize, y: usize) -> u16 {
+        self.indices[y * Frame::WIDTH + x]
+    }
+
+    /// 6 bits color index (palette number) of the pixel.
+    pub fn get_color_index(&self, x: usize, y: usize) -> u8 {
+        (self.get_palette_index(x, y) & 0x3F) as u8
+    }
+
+    /// Emphasis bits (0bBGR) of the pixel.
+    pub fn get_emphasis(&self, x: usize, y: usize) -> u8 {
+        (self.get_palette_index(x, y) >> 6) as u8
+    }
+
+    /// Bytes per row in `format`.
+    pub fn pitch(format: PixelFormat) -> usize {
+        Frame::WIDTH * format.bytes_per_pixel()
+    }
+
+    pub fn to_pixel_format(&self, format: PixelFormat) -> Vec<u8> {
+        convert_rgb24(&self.data, format)
+    }
+
+    /// 256x240 RGB PNG.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        image::save_buffer_with_format(
+            path,
+            &self.data,
+            Frame::WIDTH as u32,
+            Frame::HIGHT as u32,
+            image::ColorType::Rgb8,
+            image::ImageFormat::Png,
+        )?;
+
+        Ok(())
+    }
+
+    /// 256x240 16 bits grayscale PNG of the raw palette indices.
+    /// The values are the indices of 512 colors .pal files. (emphasis << 6 | palette number)
+    pub fn save_index_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let image: image::ImageBuffer<image::Luma<u16>, Vec<u16>> = image::ImageBuffer::from_raw(
+            Frame::WIDTH as u32,
+            Frame::HIGHT as u32,
+            self.indices.clone(),
+        )
+        .unwrap();
+        image.save_with_format(path, image::ImageFormat::Png)?;
+
+        Ok(())
+    }
+
+    /// Rebuild RGB from the raw palette indices with another palette.
+    pub fn recolor(&mut self, system_palette: &SystemPalette) {
+        for (i, index) in self.indices.iter().enumerate() {
+            let (r, g, b) = system_palette.get_color_code_by_index(*index as usize);
+            self.data[i * 3..i * 3 + 3].copy_from_slice(&[r, g, b]);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{env, fs};
+
     use super::*;
 
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let file_name = format!("baby_nes_frame_{}_{}.png", name, std::process::id());
+        env::temp_dir().join(file_name)
+    }
+
     #[test]
     fn test_set_pixel() {
         let mut frame = Frame::new();
@@ -65,4 +200,106 @@ mod tests {
         assert_eq!(pixel.1, 200);
         assert_eq!(pixel.2, 100);
     }
+
+    #[test]
+    fn test_palette_index() {
+        let mut frame = Frame::new();
+        frame.set_palette_index(10, 20, 0x1C5);
+        assert_eq!(frame.get_palette_index(10, 20), 0x1C5);
+        assert_eq!(frame.indices[20 * Frame::WIDTH + 10], 0x1C5);
+
+        // Out of range is ignored like `set_pixel`.
+        frame.set_palette_index(0, Frame::HIGHT, 0x01);
+    }
+
+    #[test]
+    fn test_color_index_and_emphasis() {
+        let mut frame = Frame::new();
+        frame.set_palette_index(1, 2, 0b101_010110);
+
+        assert_eq!(frame.get_color_index(1, 2), 0b010110);
+        assert_eq!(frame.get_emphasis(1, 2), 0b101);
+    }
+
+    #[test]
+    fn test_pixel_format_from_name() {
+        for name in PIXEL_FORMAT_NAMES {
+            assert!(PixelFormat::from_name(name).is_some());
+        }
+        assert_eq!(PixelFormat::from_name("bgr24"), None);
+    }
+
+    #[test]
+    fn test_convert_rgb24() {
+        let rgb = [0x11, 0x22, 0x33];
+
+        assert_eq!(convert_rgb24(&rgb, PixelFormat::Rgb24), rgb.to_vec());
+        assert_eq!(
+            convert_rgb24(&rgb, PixelFormat::Rgba8888),
+            0x112233FFu32.to_ne_bytes().to_vec()
+        );
+        assert_eq!(
+            convert_rgb24(&rgb, PixelFormat::Argb8888),
+            0xFF112233u32.to_ne_bytes().to_vec()
+        );
+    }
+
+    #[test]
+    fn test_to_pixel_format() {
+        let mut frame = Frame::new();
+        frame.set_pixel(1, 0, (0x11, 0x22, 0x33));
+
+        for format in [
+            PixelFormat::Rgb24,
+            PixelFormat::Rgba8888,
+            PixelFormat::Argb8888,
+        ] {
+            let data = frame.to_pixel_format(format);
+            assert_eq!(data.len(), Frame::pitch(format) * Frame::HIGHT);
+        }
+
+        let data = frame.to_pixel_format(PixelFormat::Argb8888);
+        assert_eq!(data[4..8], 0xFF112233u32.to_ne_bytes());
+    }
+
+    #[test]
+    fn test_recolor() {
+        let mut frame = Frame::new();
+        frame.set_palette_index(0, 0, 0x20);
+
+        let system_palette = SystemPalette::default();
+        frame.recolor(&system_palette);
+
+        assert_eq!(frame.get_pixel(0, 0), system_palette.get_color_code(0x20, 0));
+        assert_eq!(frame.get_pixel(1, 0), system_palette.get_color_code(0x00, 0));
+    }
+
+    #[test]
+    fn test_save_png() {
+        let mut frame = Frame::new();
+        frame.set_pixel(3, 4, (0x11, 0x22, 0x33));
+
+        let path = temp_path("rgb");
+        frame.save_png(&path).unwrap();
+        let image = image::open(&path).unwrap().to_rgb8();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.width(), Frame::WIDTH as u32);
+        assert_eq!(image.height(), Frame::HIGHT as u32);
+        assert_eq!(image.get_pixel(3, 4), &image::Rgb([0x11, 0x22, 0x33]));
+    }
+
+    #[test]
+    fn test_save_index_png() {
+        let mut frame = Frame::new();
+        frame.set_palette_index(3, 4, 0x1C5);
+
+        let path = temp_path("index");
+        frame.save_index_png(&path).unwrap();
+        let image = image::open(&path).unwrap().to_luma16();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(image.get_pixel(3, 4), &image::Luma([0x1C5]));
+        assert_eq!(image.get_pixel(0, 0), &image::Luma([0]));
+    }
 }
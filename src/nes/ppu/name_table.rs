@@ -0,0 +1,146 @@
+use crate::nes::{cartridge::header::MirroringType, ram::Ram};
+
+const NAME_TABLE_SIZE: u16 = 0x0400; // 1KB
+const VRAM_SIZE: u16 = 0x0800; // 2KB (internal CIRAM)
+const FOUR_SCREEN_VRAM_SIZE: u16 = 0x1000; // 4KB (CIRAM + 2KB on the cartridge)
+
+/* Name table mirroring
+https://www.nesdev.org/wiki/Mirroring#Nametable_Mirroring
+
+The PPU addresses 4 name tables, but the NES only has 2KB of VRAM (2 physical tables).
+The cartridge decides which physical table each logical table is mapped to.
+
+logical table: 0(0x2000) 1(0x2400) 2(0x2800) 3(0x2C00)
+Horizontal   : A         A         B         B
+Vertical     : A         B         A         B
+Single A     : A         A         A         A
+Single B     : B         B         B         B
+Four screen  : A         B         C         D
+*/
+pub struct NameTable {
+    vram: Ram,
+    mirroring: MirroringType,
+}
+
+impl NameTable {
+    pub fn new(mirroring: MirroringType) -> Self {
+        let size = match mirroring {
+            MirroringType::FourScreen => FOUR_SCREEN_VRAM_SIZE,
+            _ => VRAM_SIZE,
+        };
+
+        NameTable {
+            vram: Ram::new(size),
+            mirroring,
+        }
+    }
+
+    pub fn mirroring(&self) -> MirroringType {
+        self.mirroring
+    }
+
+    /// Mappers can switch the mirroring at runtime.
+    pub fn set_mirroring(&mut self, mirroring: MirroringType) {
+        if mirroring == MirroringType::FourScreen
+            && self.vram.len() < FOUR_SCREEN_VRAM_SIZE as usize
+        {
+            // Keep the contents of the 2 internal tables, and add the extra 2KB.
+            let mut data = self.vram.to_vec();
+            data.resize(FOUR_SCREEN_VRAM_SIZE as usize, 0);
+            self.vram = Ram::from_vec(data);
+        }
+
+        self.mirroring = mirroring;
+    }
+
+    /// `addr` is relative to 0x2000. (0x0000..=0x0FFF, upper bits are ignored)
+    pub fn read(&self, addr: u16) -> &u8 {
+        self.vram.read(self.calibrate_address(addr))
+    }
+
+    /// `addr` is relative to 0x2000. (0x0000..=0x0FFF, upper bits are ignored)
+    pub fn write(&mut self, addr: u16, data: u8) {
+        let calibrated_addr = self.calibrate_address(addr);
+        self.vram.write(calibrated_addr, data);
+    }
+
+    fn calibrate_address(&self, addr: u16) -> u16 {
+        let addr = addr & 0x0FFF;
+        let table = addr / NAME_TABLE_SIZE;
+        let offset = addr % NAME_TABLE_SIZE;
+
+        let physical_table = match self.mirroring {
+            MirroringType::Horizontal => table / 2,
+            MirroringType::Vertical => table % 2,
+            MirroringType::SingleScreenA => 0,
+            MirroringType::SingleScreenB => 1,
+            MirroringType::FourScreen => table,
+        };
+
+        physical_table * NAME_TABLE_SIZE + offset
+    }
+}
+
+#[cfg(test)]
+mod name_table_test {
+    use super::*;
+
+    fn assert_physical_tables(mirroring: MirroringType, expect: [u16; 4]) {
+        let name_table = NameTable::new(mirroring);
+        for (table, physical_table) in expect.iter().enumerate() {
+            let addr = table as u16 * NAME_TABLE_SIZE + 0x0012;
+            assert_eq!(
+                name_table.calibrate_address(addr),
+                physical_table * NAME_TABLE_SIZE + 0x0012
+            );
+        }
+    }
+
+    #[test]
+    fn horizontal_mirroring_test() {
+        assert_physical_tables(MirroringType::Horizontal, [0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn vertical_mirroring_test() {
+        assert_physical_tables(MirroringType::Vertical, [0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn single_screen_mirroring_test() {
+        assert_physical_tables(MirroringType::SingleScreenA, [0, 0, 0, 0]);
+        assert_physical_tables(MirroringType::SingleScreenB, [1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn four_screen_mirroring_test() {
+        assert_physical_tables(MirroringType::FourScreen, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn read_and_write_through_mirror_test() {
+        let mut name_table = NameTable::new(MirroringType::Vertical);
+        name_table.write(0x0000, 0x11);
+        name_table.write(0x0C00, 0x22);
+
+        assert_eq!(name_table.read(0x0800), &0x11);
+        assert_eq!(name_table.read(0x0400), &0x22);
+        // 0x3000..=0x3EFF is a mirror of 0x2000..=0x2EFF
+        assert_eq!(name_table.read(0x1000), &0x11);
+    }
+
+    #[test]
+    fn set_mirroring_test() {
+        let mut name_table = NameTable::new(MirroringType::Horizontal);
+        name_table.write(0x0400, 0x11);
+        name_table.write(0x0800, 0x22);
+
+        name_table.set_mirroring(MirroringType::FourScreen);
+        assert_eq!(name_table.mirroring(), MirroringType::FourScreen);
+        assert_eq!(name_table.read(0x0000), &0x11);
+        assert_eq!(name_table.read(0x0400), &0x22);
+
+        name_table.write(0x0C00, 0x33);
+        assert_eq!(name_table.read(0x0C00), &0x33);
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::Result;
+
+use crate::nes::cartridge::header::MirroringType;
+
+use super::{
+    palette_ram::PaletteType, ppu::Ppu, sprite::build_sprite, tile_position::TilePosition,
+};
+
+const NAME_TABLE_COUNT: usize = 4;
+const NAME_TABLE_SIZE: u16 = 0x0400; // 1KB
+const ATTRIBUTE_TABLE_OFFSET: u16 = 0x03C0;
+/// 32x30 tiles in a name table
+const TILE_COLUMNS: u8 = 32;
+const TILE_ROWS: u8 = 30;
+
+/// A tile in a name table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NameTableEntry {
+    /// Index in the background pattern table
+    pub tile_index: u8,
+    /// Background palette (0-3) selected by the attribute table
+    pub palette_id: u8,
+}
+
+/* Name table dump
+https://www.nesdev.org/wiki/PPU_nametables
+
+All the 4 logical name tables as the PPU sees them with the current mirroring.
+They are rendered into a map of 2x2 tables (512x480 px), in the layout of the PPU address space.
+
++-------+-------+
+| $2000 | $2400 |
++-------+-------+
+| $2800 | $2C00 |
++-------+-------+
+*/
+pub struct NameTableDump {
+    pub mirroring: MirroringType,
+    /// Entries of the tables, row by row. The order of the tables is $2000, $2400, $2800 and $2C00.
+    pub tables: Vec<Vec<NameTableEntry>>,
+    /// RGB pixels of the map
+    pub pixels: Vec<u8>,
+}
+
+impl NameTableDump {
+    pub const WIDTH: usize = 512;
+    pub const HEIGHT: usize = 480;
+
+    pub fn new(ppu: &Ppu) -> Result<Self> {
+        let mut pixels = vec![0u8; Self::WIDTH * Self::HEIGHT * 3];
+        let mut tables = Vec::with_capacity(NAME_TABLE_COUNT);
+
+        let backdrop = *ppu.palette_ram.read(0x0000);
+        let pattern_offset = ppu
+            .ppu_registers
+            .ppu_ctrl
+            .background_pattern_table_address()
+            / 16;
+
+        for table in 0..NAME_TABLE_COUNT {
+            let table_offset = table as u16 * NAME_TABLE_SIZE;
+            let origin_x = table % 2 * Self::WIDTH / 2;
+            let origin_y = table / 2 * Self::HEIGHT / 2;
+            let mut entries = Vec::with_capacity(TILE_COLUMNS as usize * TILE_ROWS as usize);
+
+            for y in 0..TILE_ROWS {
+                for x in 0..TILE_COLUMNS {
+                    let position = TilePosition::new(x, y);
+                    let tile_index = *ppu
+                        .name_table
+                        .read(table_offset + position.get_tile_number());
+                    let attribute = ppu.name_table.read(
+                        table_offset + ATTRIBUTE_TABLE_OFFSET + position.get_attribute_id() as u16,
+                    );
+                    let palette_id = position.get_palette_id(attribute);
+
+                    let palettes = ppu
+                        .palette_ram
+                        .get_palettes(palette_id, PaletteType::Background);
+                    let pattern = ppu
+                        .pattern_table
+                        .get_character_pattern((pattern_offset + tile_index as u16) as usize)?;
+                    let sprite = build_sprite(&pattern)?;
+
+                    for (sprite_y, sprite_line) in sprite.iter().enumerate() {
+                        for (sprite_x, color_number) in sprite_line.iter().enumerate() {
+                            // Color number 0 is transparent, so the backdrop color is displayed.
+                            let palette_number = match color_number {
+                                0 => backdrop,
+                                _ => palettes.get(*color_number as usize).get_palette_number(),
+                            };
+                            let (r, g, b) = ppu.system_palette.get_color_code(palette_number, 0);
+
+                            let pixel_x = origin_x + x as usize * 8 + sprite_x;
+                            let pixel_y = origin_y + y as usize * 8 + sprite_y;
+                            let offset = (pixel_y * Self::WIDTH + pixel_x) * 3;
+                            pixels[offset..offset + 3].copy_from_slice(&[r, g, b]);
+                        }
+                    }
+
+                    entries.push(NameTableEntry {
+                        tile_index,
+                        palette_id,
+                    });
+                }
+            }
+
+            tables.push(entries);
+        }
+
+        Ok(NameTableDump {
+            mirroring: ppu.name_table.mirroring(),
+            tables,
+            pixels,
+        })
+    }
+
+    pub fn get_entry(&self, table: usize, x: usize, y: usize) -> NameTableEntry {
+        self.tables[table][y * TILE_COLUMNS as usize + x]
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> (u8, u8, u8) {
+        let offset = (y * Self::WIDTH + x) * 3;
+        (
+            self.pixels[offset],
+            self.pixels[offset + 1],
+            self.pixels[offset + 2],
+        )
+    }
+}
+
+#[cfg(test)]
+mod name_table_dump_test {
+    use crate::nes::ppu::{
+        name_table::NameTable, palette::NES_COLORS, pattern_table::PatternTable,
+        registers::ppu_control::PpuCtrl,
+    };
+
+    use super::*;
+
+    fn build_ppu(mirroring: MirroringType) -> Ppu {
+        // Tile 1 is filled with the color number 3, and tile 0x101 with 1.
+        let mut pattern = vec![0u8; 0x2000];
+        pattern[0x10..0x20].fill(0xFF);
+        pattern[0x1010..0x1018].fill(0xFF);
+        let pattern_table = PatternTable::from_vec(pattern).unwrap();
+
+        let mut ppu = Ppu::new(pattern_table, NameTable::new(mirroring));
+        for (address, palette_number) in [(0x00, 0x0F), (0x01, 0x11), (0x07, 0x27), (0x0F, 0x30)] {
+            ppu.palette_ram.write(address, palette_number);
+        }
+        ppu
+    }
+
+    fn to_tuple(color: [u8; 3]) -> (u8, u8, u8) {
+        (color[0], color[1], color[2])
+    }
+
+    #[test]
+    fn mirroring_test() {
+        let mut ppu = build_ppu(MirroringType::Vertical);
+        // Tile (1, 0) of $2000 is mirrored to $2800.
+        ppu.name_table.write(0x0001, 0x01);
+
+        let dump = NameTableDump::new(&ppu).unwrap();
+        assert_eq!(dump.mirroring, MirroringType::Vertical);
+        assert_eq!(dump.tables.len(), 4);
+        for table in [0, 2] {
+            assert_eq!(dump.get_entry(table, 1, 0).tile_index, 1);
+        }
+        for table in [1, 3] {
+            assert_eq!(dump.get_entry(table, 1, 0).tile_index, 0);
+        }
+
+        // $2800 is the bottom left table.
+        let backdrop = to_tuple(NES_COLORS[0x0F]);
+        assert_eq!(dump.get_pixel(8, 240), dump.get_pixel(8, 0));
+        assert_ne!(dump.get_pixel(8, 240), backdrop);
+        assert_eq!(dump.get_pixel(264, 0), backdrop);
+    }
+
+    #[test]
+    fn attribute_palette_test() {
+        let mut ppu = build_ppu(MirroringType::Horizontal);
+        // The attribute 0b11 << 2 selects the palette 3 for the block 1. (x: 2-3, y: 0-1)
+        ppu.name_table.write(0x0003, 0x01);
+        ppu.name_table.write(ATTRIBUTE_TABLE_OFFSET, 0b00001100);
+
+        let dump = NameTableDump::new(&ppu).unwrap();
+        assert_eq!(
+            dump.get_entry(0, 3, 0),
+            NameTableEntry {
+                tile_index: 1,
+                palette_id: 3
+            }
+        );
+        assert_eq!(dump.get_entry(0, 0, 0).palette_id, 0);
+        assert_eq!(dump.get_pixel(24, 0), to_tuple(NES_COLORS[0x30]));
+    }
+
+    #[test]
+    fn background_pattern_table_test() {
+        let mut ppu = build_ppu(MirroringType::Horizontal);
+        ppu.name_table.write(0x0000, 0x01);
+        ppu.ppu_registers.ppu_ctrl = PpuCtrl::BACKGROUND_PATTERN_TABLE_ADDRESS;
+
+        let dump = NameTableDump::new(&ppu).unwrap();
+        // Tile 0x101 with the color number 1 of the palette 0.
+        assert_eq!(dump.get_pixel(0, 0), to_tuple(NES_COLORS[0x11]));
+    }
+}
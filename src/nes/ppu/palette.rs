@@ -1,3 +1,7 @@
+/// Attenuation of the channels which are not emphasized.
+/// ref: https://www.nesdev.org/wiki/NTSC_video#Color_Tint_Bits
+const EMPHASIS_ATTENUATION: f32 = 0.816328;
+
 #[derive(Debug)]
 pub struct Palette(u8);
 
@@ -16,6 +20,35 @@ impl Palette {
     }
 }
 
+/// Index of the 512 colors with emphasis.
+/// The upper 3 bits are emphasis (0bBGR) and the lower 6 bits are a palette number.
+pub fn color_index(palette_number: u8, emphasis: u8) -> usize {
+    ((emphasis as usize & 0b111) << 6) | (palette_number as usize & 0x3F)
+}
+
+/// Build the 512 colors (8 emphasis patterns x 64 colors) from 64 base colors.
+pub fn build_emphasis_colors(base_colors: &[[u8; 3]; 64]) -> [[u8; 3]; 512] {
+    let mut colors = [[0u8; 3]; 512];
+
+    for (i, color) in colors.iter_mut().enumerate() {
+        let emphasis = (i >> 6) as u8;
+        let base_color = base_colors[i & 0x3F];
+
+        for channel in 0..3 {
+            // channel 0: red, 1: green, 2: blue
+            // Each emphasis bit attenuates the other two channels.
+            let is_attenuated = emphasis & !(1 << channel) != 0;
+            color[channel] = if is_attenuated {
+                (base_color[channel] as f32 * EMPHASIS_ATTENUATION).round() as u8
+            } else {
+                base_color[channel]
+            };
+        }
+    }
+
+    colors
+}
+
 impl PartialEq for Palette {
     fn eq(&self, other: &Palette) -> bool {
         self.0 == other.0
@@ -138,6 +171,24 @@ mod palette_test {
         let palette = Palette::new(3);
         assert_eq!(palette.get_color_code(), (0x44, 0x00, 0x96));
     }
+
+    #[test]
+    fn color_index_test() {
+        assert_eq!(color_index(0x3F, 0b000), 0x03F);
+        assert_eq!(color_index(0x01, 0b101), 0x141);
+    }
+
+    #[test]
+    fn build_emphasis_colors_test() {
+        let colors = build_emphasis_colors(&NES_COLORS);
+
+        // No emphasis is the same as the base colors.
+        assert_eq!(colors[color_index(0x20, 0b000)], [0xFF, 0xFF, 0xFF]);
+        // Emphasize blue: red and green are attenuated.
+        assert_eq!(colors[color_index(0x20, 0b100)], [0xD0, 0xD0, 0xFF]);
+        // Emphasize all: every channel is attenuated.
+        assert_eq!(colors[color_index(0x20, 0b111)], [0xD0, 0xD0, 0xD0]);
+    }
 }
 
 #[cfg(test)]
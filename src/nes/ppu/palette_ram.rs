@@ -19,15 +19,31 @@ impl PaletteRam {
     }
 
     pub fn write(&mut self, addr: u16, data: u8) {
+        // Palette RAM is 6 bits wide. The upper 2 bits are not stored.
+        let data = data & 0x3F;
         self.0.write(addr, data);
+
+        // $3F10/$3F14/$3F18/$3F1C are mirrors of $3F00/$3F04/$3F08/$3F0C.
+        // Write to both so that the ranges of each palette stay consistent.
+        if let Some(mirror_addr) = PaletteRam::mirror_address(addr) {
+            self.0.write(mirror_addr, data);
+        }
     }
 
     pub fn read(&self, addr: u16) -> &u8 {
         self.0.read(addr)
     }
 
-    pub fn read_range(&self, range: Range<usize>) -> &[u8] {
-        &self.0.read_range(range)
+    fn mirror_address(addr: u16) -> Option<u16> {
+        match addr {
+            0x0000 | 0x0004 | 0x0008 | 0x000C => Some(addr + 0x10),
+            0x0010 | 0x0014 | 0x0018 | 0x001C => Some(addr - 0x10),
+            _ => None,
+        }
+    }
+
+    pub fn read_range(&self, range: Range<usize>) -> Vec<u8> {
+        self.0.read_range(range)
     }
 
     pub fn get_palettes(&self, palette_id: u8, palette_type: PaletteType) -> PaletteGroup {
@@ -63,9 +79,33 @@ mod palette_ram_test {
     #[test]
     fn can_be_read_and_write_test() {
         let mut ram = PaletteRam::new();
-        ram.write(0x0002, 0x4F);
+        ram.write(0x0002, 0x2F);
+
+        assert_eq!(ram.read(0x0002), &0x2F);
+    }
+
+    #[test]
+    fn write_masks_upper_2_bits_test() {
+        let mut ram = PaletteRam::new();
+        ram.write(0x0002, 0xFF);
+
+        assert_eq!(ram.read(0x0002), &0x3F);
+    }
+
+    #[test]
+    fn sprite_backdrop_mirror_test() {
+        let mut ram = PaletteRam::new();
+        for (addr, mirror_addr) in [(0x10, 0x00), (0x14, 0x04), (0x18, 0x08), (0x1C, 0x0C)] {
+            ram.write(addr, 0x21);
+            assert_eq!(ram.read(mirror_addr), &0x21);
+
+            ram.write(mirror_addr, 0x0F);
+            assert_eq!(ram.read(addr), &0x0F);
+        }
 
-        assert_eq!(ram.read(0x0002), &0x4F);
+        // Other entries are not mirrored.
+        ram.write(0x0011, 0x30);
+        assert_eq!(ram.read(0x0001), &0x00);
     }
 
     #[test]
@@ -1,39 +1,67 @@
 use anyhow::Result;
 
-use crate::nes::ram::Ram;
-
-pub struct PatternTable(Ram);
+use crate::nes::{
+    cartridge::header::MirroringType,
+    mapper::{
+        character_memory::{CharacterMemory, DEFAULT_CHARACTER_RAM_SIZE},
+        nrom::Nrom,
+        share, SharedMapper,
+    },
+};
+
+/// 0x0000..=0x1FFF, 2 tables of 256 patterns.
+const PATTERN_TABLE_SIZE: usize = 0x2000;
+const PATTERN_SIZE: usize = 16;
+
+/// PPU 0x0000..=0x1FFF. The contents are provided by the mapper of the cartridge.
+pub struct PatternTable(SharedMapper);
+
+impl PatternTable {
+    pub fn new(mapper: SharedMapper) -> Self {
+        PatternTable(mapper)
+    }
 
-impl<'a> PatternTable {
-    pub fn new(data: Ram) -> Result<Self> {
-        if data.len() % 16 != 0 {
+    /// Pattern table of a NROM cartridge which has `data` as CHR-ROM.
+    /// Empty `data` means the cartridge has 8KB CHR-RAM.
+    pub fn from_vec(data: Vec<u8>) -> Result<Self> {
+        if data.len() % PATTERN_SIZE != 0 {
             return Err(anyhow!("PatternTable data must be multiple of 16."));
         }
 
-        Ok(PatternTable(data))
+        let character = CharacterMemory::new(data, DEFAULT_CHARACTER_RAM_SIZE);
+        let nrom = Nrom::new(
+            vec![0; 0x4000],
+            character,
+            0x2000,
+            MirroringType::Horizontal,
+        );
+        Ok(Self::new(share(nrom)))
     }
 
-    pub fn from_vec(data: Vec<u8>) -> Result<Self> {
-        Self::new(Ram::from_vec(data))
+    pub fn read(&self, addr: u16) -> u8 {
+        self.0.borrow_mut().ppu_read(addr)
     }
 
-    pub fn read(&'a self, addr: u16) -> &'a u8 {
-        self.0.read(addr)
+    pub fn write(&mut self, addr: u16, data: u8) {
+        self.0.borrow_mut().ppu_write(addr, data);
     }
 
-    pub fn get_character_pattern(&'a self, index: usize) -> Result<&'a [u8]> {
-        if self.0.len() < (index + 1) * 16 {
+    pub fn get_character_pattern(&self, index: usize) -> Result<[u8; PATTERN_SIZE]> {
+        if PATTERN_TABLE_SIZE < (index + 1) * PATTERN_SIZE {
             return Err(anyhow!(
-                "Out-of-range access to PatternTable. RAM size {} / index: {}",
-                self.0.len() / 16,
+                "Out-of-range access to PatternTable. size {} / index: {}",
+                PATTERN_TABLE_SIZE / PATTERN_SIZE,
                 index
             ));
         }
 
-        let start = index * 16;
-        let end = start + 16;
+        let start = index * PATTERN_SIZE;
+        let mut pattern = [0; PATTERN_SIZE];
+        for (i, byte) in pattern.iter_mut().enumerate() {
+            *byte = self.read((start + i) as u16);
+        }
 
-        Ok(&self.0.read_range(start..end))
+        Ok(pattern)
     }
 }
 
@@ -43,11 +71,10 @@ mod pattern_table_test {
 
     #[test]
     fn pattern_table_data_must_be_multiple_of_16_test() {
-        let valid_pattern_table = PatternTable::new(Ram::from_vec(vec![0; 32]));
+        let valid_pattern_table = PatternTable::from_vec(vec![0; 32]);
         assert!(valid_pattern_table.is_ok());
-        assert_eq!(valid_pattern_table.unwrap().0.len(), 32);
 
-        let invalid_pattern_table = PatternTable::new(Ram::from_vec(vec![0; 33]));
+        let invalid_pattern_table = PatternTable::from_vec(vec![0; 33]);
         assert!(invalid_pattern_table.is_err());
     }
 
@@ -67,12 +94,21 @@ mod pattern_table_test {
         let pattern_table = PatternTable::from_vec(character_rom).unwrap();
 
         let result = pattern_table.get_character_pattern(1).unwrap();
-        assert_eq!(result, &pattern_data);
+        assert_eq!(result.to_vec(), pattern_data);
     }
 
     #[test]
     fn should_be_return_error_to_out_of_range_access_test() {
-        let pattern_table = PatternTable::from_vec(Vec::new()).unwrap();
-        assert!(pattern_table.get_character_pattern(0).is_err());
+        let pattern_table = PatternTable::from_vec(vec![0; PATTERN_TABLE_SIZE]).unwrap();
+        assert!(pattern_table.get_character_pattern(511).is_ok());
+        assert!(pattern_table.get_character_pattern(512).is_err());
+    }
+
+    #[test]
+    fn write_character_ram_test() {
+        // No CHR-ROM. The cartridge has CHR-RAM.
+        let mut pattern_table = PatternTable::from_vec(Vec::new()).unwrap();
+        pattern_table.write(0x1000, 0x12);
+        assert_eq!(pattern_table.read(0x1000), 0x12);
     }
 }
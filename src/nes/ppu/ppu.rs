@@ -2,15 +2,18 @@
 
 use super::{
     background::Background,
+    name_table::NameTable,
     palette_ram::{PaletteRam, PaletteType},
     pattern_table::PatternTable,
-    registers::{PpuRegisters, PpuRegistration, ppu_status::PpuStatus, ppu_data::PpuData, ppu_address::PpuAddress},
+    registers::{PpuRegisters, PpuRegistration, ppu_status::PpuStatus},
     render::RenderContext,
     sprite::{build_sprite, Sprite},
+    system_palette::SystemPalette,
     tile::Tile,
     tile_position::TilePosition,
 };
 use crate::nes::{
+    cartridge::header::MirroringType,
     ppu::registers::{ppu_control::PpuCtrl, ppu_mask::PpuMask},
     ram::Ram,
 };
@@ -21,15 +24,19 @@ pub struct Ppu {
     pub line: u16,
     pub background: Background,
     pub pattern_table: PatternTable,
-    pub vram: Ram,
+    pub name_table: NameTable,
     pub palette_ram: PaletteRam,
-    pub ppu_address: PpuAddress,
     pub ppu_registers: PpuRegisters,
-    pub ppu_data: PpuData,
     pub sprite_ram: Ram,
+    pub system_palette: SystemPalette,
 }
 
 const CLOCK_TO_RENDER_LINE: u16 = 341;
+/// The sprite patterns of the next line are fetched from this cycle.
+const SPRITE_FETCH_CYCLE: u16 = 257;
+/// Tile $FF, which is fetched when a line has no sprite. It is in $1000 for 8x16 sprites.
+const EMPTY_SPRITE_TILE: u16 = 0xFF;
+const PRE_RENDER_LINE: u16 = 261;
 const SPRITE_RAM_SIZE: u16 = 1024 * 4; // 4KB
 
 pub enum PpuRunResult {
@@ -39,24 +46,28 @@ pub enum PpuRunResult {
 }
 
 impl Ppu {
-    pub fn new(pattern_table: PatternTable, vram: Ram) -> Self {
+    pub fn new(pattern_table: PatternTable, name_table: NameTable) -> Self {
         Self {
             cycle: 0,
             line: 0,
             background: Background::new(),
             pattern_table,
-            vram,
+            name_table,
             palette_ram: PaletteRam::new(),
-            ppu_address: PpuAddress::new(),
             ppu_registers: PpuRegisters::new(),
-            ppu_data: PpuData::new(),
             sprite_ram: Ram::new(SPRITE_RAM_SIZE),
+            system_palette: SystemPalette::default(),
         }
     }
 
     pub fn run(&mut self, cycle: u16) -> PpuRunResult {
+        let previous_cycle = self.cycle;
         self.cycle += cycle;
 
+        if previous_cycle < SPRITE_FETCH_CYCLE && SPRITE_FETCH_CYCLE <= self.cycle {
+            self.fetch_line_pattern(self.sprite_fetch_address());
+        }
+
         if self.cycle < CLOCK_TO_RENDER_LINE {
             return PpuRunResult::CountUpCycle;
         }
@@ -86,13 +97,10 @@ impl Ppu {
                 name table: 1, 3 -> start x position is 32.
             */
 
-            // borrowで無理やりやってるので治す
-            let nametable_id = self.ppu_registers.get_nametable_address();
-            let pos_x_start = (nametable_id as u16 % 2) * 32;
-            let pos_x_end = pos_x_start + 32;
+            // The selected name table is resolved to physical VRAM by the mirroring in `build_tile`.
             let pos_y = (self.line / 8) as u8;
 
-            for pos_x in pos_x_start..pos_x_end {
+            for pos_x in 0..32 {
                 let tile_pos = TilePosition::new(pos_x as u8, pos_y);
                 let tile = self.build_tile(tile_pos).unwrap();
                 self.background.push(tile);
@@ -100,28 +108,46 @@ impl Ppu {
         }
 
         // is not finished building all the background lines.
-        if self.line < 262 {
-            return PpuRunResult::FinishedBuildBackgroundLine;
-        }
+        let result = match self.line < 262 {
+            true => PpuRunResult::FinishedBuildBackgroundLine,
+            false => {
+                self.line = 0;
+                self.ppu_registers.io_latch.decay();
+                PpuRunResult::FinishedBuildAllBackgroundLine
+            }
+        };
+
+        // The next line has started.
+        self.fetch_line_pattern(
+            self.ppu_registers
+                .ppu_ctrl
+                .background_pattern_table_address(),
+        );
 
-        self.line = 0;
-        PpuRunResult::FinishedBuildAllBackgroundLine
+        result
     }
 
     pub fn build_tile(&self, position: TilePosition) -> Result<Tile> {
         let attributes_id = position.get_attribute_id();
 
+        // Offset of the selected name table from 0x2000.
+        let name_table_offset = self.ppu_registers.get_nametable_address() as u16 - 0x2000;
+
         // ToDo: refactoring here.
-        let attribute_addr = attributes_id as u16 + 0x03C0; // 0x03C0 is name table size.
-        let attribute = self.vram.read(attribute_addr);
+        let attribute_addr = name_table_offset + attributes_id as u16 + 0x03C0; // 0x03C0 is name table size.
+        let attribute = self.name_table.read(attribute_addr);
 
         let palette_id = position.get_palette_id(attribute);
         let palettes = self
             .palette_ram
             .get_palettes(palette_id, PaletteType::Background);
-        let sprite_number = self.vram.read(position.get_tile_number() as u16);
+        let sprite_number = self
+            .name_table
+            .read(name_table_offset + position.get_tile_number());
 
-        let sprite = self.build_sprite_with_index(*sprite_number)?;
+        let pattern_index =
+            self.ppu_registers.ppu_ctrl.background_pattern_table_address() / 16 + *sprite_number as u16;
+        let sprite = self.build_sprite_with_index(pattern_index)?;
 
         Ok(Tile {
             sprite,
@@ -130,9 +156,40 @@ impl Ppu {
         })
     }
 
-    pub fn build_sprite_with_index(&self, index: u8) -> Result<Sprite> {
+    /// `index` is the pattern index in both pattern tables. (0-511)
+    pub fn build_sprite_with_index(&self, index: u16) -> Result<Sprite> {
         let pattern_data = self.pattern_table.get_character_pattern(index as usize)?;
-        build_sprite(pattern_data)
+        build_sprite(&pattern_data)
+    }
+
+    /* Pattern fetches of a line
+    https://www.nesdev.org/wiki/PPU_rendering
+
+    While rendering, the PPU fetches the background patterns from cycle 1 and the sprite patterns from cycle 257
+    on the visible lines and the pre-render line. Mappers watch the address bus (ex: A12 of MMC3),
+    so a fetch is emulated at these timings even though the tiles are built per 8 lines.
+    */
+    fn fetch_line_pattern(&self, address: u16) {
+        let mask = &self.ppu_registers.ppu_mask;
+        let is_rendering = mask.intersects(PpuMask::SHOW_BACKGROUND | PpuMask::SHOW_SPRITES);
+        let is_rendering_line = self.line < 240 || self.line == PRE_RENDER_LINE;
+
+        if is_rendering && is_rendering_line {
+            self.pattern_table.read(address);
+        }
+    }
+
+    fn sprite_fetch_address(&self) -> u16 {
+        let ctrl = &self.ppu_registers.ppu_ctrl;
+        match ctrl.contains(PpuCtrl::SPRITE_SIZE) {
+            // Bit 0 of the tile selects the table of 8x16 sprites.
+            true => 0x1000 + (EMPTY_SPRITE_TILE & !1) * 16,
+            false => ctrl.sprite_pattern_table_address() + EMPTY_SPRITE_TILE * 16,
+        }
+    }
+
+    pub fn set_mirroring(&mut self, mirroring: MirroringType) {
+        self.name_table.set_mirroring(mirroring);
     }
 
     pub fn reset_background(&mut self) {
@@ -153,28 +210,37 @@ impl Ppu {
 
 impl PpuRegistration for Ppu {
     fn read(&mut self, address: u16) -> u8 {
-        println!("PPU registers read | Address: {:x}", address);
-        match address {
-            0x0002 => self.read_status(),
+        let io_latch = self.ppu_registers.io_latch.value();
+        let data = match address {
+            // Only the upper 3 bits are driven by PPUSTATUS.
+            0x0002 => (self.read_status() & 0b11100000) | (io_latch & 0b00011111),
             0x0004 => *self.ppu_registers.oam.read(&self.sprite_ram),
             0x0007 => {
-                let ppu_address = self.ppu_address.read();
-                let ppu_data = self.ppu_data.read(ppu_address, &mut self.pattern_table, &mut self.vram);
+                let addr = self.ppu_registers.ppu_addr.read();
+                let data = self.ppu_registers.ppu_data.read(
+                    addr,
+                    &mut self.pattern_table,
+                    &mut self.name_table,
+                    &self.palette_ram,
+                    &self.ppu_registers.ppu_mask,
+                );
+                self.ppu_registers.increment_vram();
 
-                ppu_data
-            },
-            _ => panic!("unimplemented read address: {}", address),
-        }
+                data
+            }
+            // Write-only registers return the I/O latch.
+            _ => return io_latch,
+        };
+
+        self.ppu_registers.io_latch.refresh(data)
     }
 
     fn write(&mut self, address: u16, data: u8) {
-        println!(
-            "PPU registers write | Address: {:x} Data: {:x}",
-            address, data
-        );
+        self.ppu_registers.io_latch.refresh(data);
+
         match address {
-            0x0000 => self.ppu_registers.ppu_ctrl = PpuCtrl::from_bits(data).unwrap(),
-            0x0001 => self.ppu_registers.ppu_mask = PpuMask::from_bits(data).unwrap(),
+            0x0000 => self.ppu_registers.ppu_ctrl = PpuCtrl::from_bits_truncate(data),
+            0x0001 => self.ppu_registers.ppu_mask = PpuMask::from_bits_truncate(data),
             0x0003 => self.ppu_registers.oam.write(data),
             0x0005 => self.ppu_registers.ppu_scroll.write(data),
             0x0006 => self.ppu_registers.ppu_addr.write(data as u16),
@@ -183,12 +249,14 @@ impl PpuRegistration for Ppu {
                 self.ppu_registers.ppu_data.write(
                     addr,
                     data,
+                    &mut self.pattern_table,
                     &mut self.palette_ram,
-                    &mut self.vram,
+                    &mut self.name_table,
                 );
                 self.ppu_registers.increment_vram();
             }
-            _ => panic!("unimplemented write address: {} / data: {}", address, data),
+            // PPUSTATUS is read-only, and OAMDATA is not implemented yet. Only the latch is filled.
+            _ => {}
         }
     }
 }
@@ -197,13 +265,31 @@ impl<'a> RenderContext<'a> for Ppu {
     fn get_background(&'a self) -> &'a Background {
         &self.background
     }
+
+    fn get_ppu_mask(&'a self) -> &'a PpuMask {
+        &self.ppu_registers.ppu_mask
+    }
+
+    fn get_backdrop_palette_number(&'a self) -> u8 {
+        *self.palette_ram.read(0x0000)
+    }
+
+    fn get_system_palette(&'a self) -> &'a SystemPalette {
+        &self.system_palette
+    }
 }
 
 #[cfg(test)]
 mod ppu_test {
     use crate::nes::{
-        ppu::{palette::PaletteGroup, palette_ram::PaletteRam, sprite::build_sprite, registers::ppu_status::PpuStatus},
-        ram::Ram,
+        cartridge::header::MirroringType,
+        mapper::{
+            build_rom,
+            character_memory::CharacterMemory,
+            mmc3::{Mmc3, Mmc3Revision},
+            share, SharedMapper,
+        },
+        ppu::{palette::PaletteGroup, palette_ram::PaletteRam, sprite::build_sprite, registers::{ppu_control::PpuCtrl, ppu_status::PpuStatus}},
     };
 
     use super::*;
@@ -231,7 +317,7 @@ mod ppu_test {
         ];
 
         let pattern_table = PatternTable::from_vec(word_vec.clone()).unwrap();
-        let ppu = Ppu::new(pattern_table, Ram::new(0x4000));
+        let ppu = Ppu::new(pattern_table, NameTable::new(MirroringType::Horizontal));
         let result = ppu.build_sprite_with_index(0).unwrap();
 
         let expect = build_sprite(&word_vec).unwrap();
@@ -239,11 +325,12 @@ mod ppu_test {
     }
 
     #[test]
-    fn build_sprite_with_index_out_of_pattern_table_range_test() {
-        let pattern_table = PatternTable::from_vec(vec![0; 16]).unwrap();
-        let ppu = Ppu::new(pattern_table, Ram::new(0x4000));
-        let result = ppu.build_sprite_with_index(1);
-        assert!(result.is_err());
+    fn build_sprite_with_index_mirrors_small_character_rom_test() {
+        // CHR-ROM smaller than the pattern table is mirrored by the mapper.
+        let pattern_table = PatternTable::from_vec(vec![0xFF; 16]).unwrap();
+        let ppu = Ppu::new(pattern_table, NameTable::new(MirroringType::Horizontal));
+        let result = ppu.build_sprite_with_index(1).unwrap();
+        assert_eq!(result, build_sprite(&[0xFF; 16]).unwrap());
     }
 
     #[test]
@@ -275,8 +362,8 @@ mod ppu_test {
         ];
 
         // write a palette id: 0 in attribute id: 0
-        let mut vram = Ram::new(0x0FFF);
-        vram.write(0x03C0, 0x00);
+        let mut name_table = NameTable::new(MirroringType::Horizontal);
+        name_table.write(0x03C0, 0x00);
 
         // write a palettes number in palette id: 0
         let mut palette_ram = PaletteRam::new();
@@ -289,7 +376,7 @@ mod ppu_test {
         let pattern_table = PatternTable::from_vec(word_vec.clone()).unwrap();
 
         // create ppu context
-        let mut ppu = Ppu::new(pattern_table, vram);
+        let mut ppu = Ppu::new(pattern_table, name_table);
         ppu.palette_ram = palette_ram;
 
         let tile_pos = TilePosition::new(0, 0);
@@ -310,9 +397,9 @@ mod ppu_test {
 
     #[test]
     fn read_status_test() {
-        let dummy_ram1 = Ram::new(0x4000);
-        let dummy_ram2 = Ram::new(0x4000);
-        let mut ppu = Ppu::new(PatternTable::new(dummy_ram1).unwrap(), dummy_ram2);
+        let pattern_table = PatternTable::from_vec(vec![0; 0x2000]).unwrap();
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
 
         // Setup flag to be cleared
         ppu.ppu_registers.ppu_addr.is_lower_addr = true;
@@ -330,18 +417,143 @@ mod ppu_test {
         assert_eq!(ppu.ppu_registers.ppu_status.contains(PpuStatus::SPRITE_ZERO_HIT), false);
     }
 
+    #[test]
+    fn read_write_only_register_test() {
+        let pattern_table = PatternTable::from_vec(vec![0; 0x2000]).unwrap();
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
+
+        // Write-only registers return the last value written to any register.
+        ppu.write(0x0005, 0x5A);
+        assert_eq!(ppu.read(0x0000), 0x5A);
+        assert_eq!(ppu.read(0x0006), 0x5A);
+
+        // PPUSTATUS fills the lower 5 bits with the latch.
+        ppu.ppu_registers.ppu_status.insert(PpuStatus::VBLANK_STARTED);
+        assert_eq!(ppu.read(0x0002), 0b10011010);
+    }
+
+    #[test]
+    fn ignore_read_only_register_write_test() {
+        let pattern_table = PatternTable::from_vec(vec![0; 0x2000]).unwrap();
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
+
+        ppu.write(0x0002, 0xFF);
+        assert_eq!(ppu.ppu_registers.ppu_status.bits(), 0x00);
+    }
+
     #[test]
     fn read_ppu_data_test() {
-        let dummy_ram1 = Ram::new(0x4000);
-        let dummy_ram2 = Ram::new(0x4000);
-        let mut ppu = Ppu::new(PatternTable::new(dummy_ram1).unwrap(), dummy_ram2);
+        let pattern_table = PatternTable::from_vec(vec![0; 0x2000]).unwrap();
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
 
-        ppu.vram.write(0x0000, 0x20);
-        ppu.ppu_address.addr = 0x2000; // PPU 0x2000 -> VRAM 0x0000
-        ppu.ppu_data.buf = 0x10;
+        ppu.name_table.write(0x0000, 0x20);
+        ppu.ppu_registers.ppu_addr.addr = 0x2000; // PPU 0x2000 -> VRAM 0x0000
+        ppu.ppu_registers.ppu_data.buf = 0x10;
 
         let data = ppu.read(0x0007);
         assert_eq!(data, 0x10);
-        assert_eq!(ppu.ppu_data.buf, 0x20);
+        assert_eq!(ppu.ppu_registers.ppu_data.buf, 0x20);
+        assert_eq!(ppu.ppu_registers.ppu_addr.addr, 0x2001);
+    }
+
+    #[test]
+    fn read_ppu_data_palette_with_grayscale_test() {
+        let pattern_table = PatternTable::from_vec(vec![0; 0x2000]).unwrap();
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
+
+        ppu.palette_ram.write(0x0001, 0x2A);
+        ppu.ppu_registers.ppu_mask = PpuMask::GRAYSCALE;
+        ppu.ppu_registers.ppu_addr.addr = 0x3F01;
+
+        assert_eq!(ppu.read(0x0007), 0x20);
+    }
+
+    #[test]
+    fn build_tile_from_mirrored_name_table_test() {
+        let word_vec = vec![
+            // channel 1
+            0b11100111, 0b11100111, 0b11100111, 0b11111111, 0b11111111, 0b11100111, 0b11100111,
+            0b11100111, // channel 2
+            0b11000110, 0b11000110, 0b11000110, 0b11111110, 0b11000110, 0b11000110, 0b11000110,
+            0b00000000,
+        ];
+
+        // sprite index 1 is 'H', index 0 is empty.
+        let pattern_table = PatternTable::from_vec([vec![0; 16], word_vec.clone()].concat()).unwrap();
+        let mut ppu = Ppu::new(pattern_table, NameTable::new(MirroringType::Vertical));
+        ppu.name_table.write(0x0000, 0x01);
+
+        // name table 2(0x2800) is a mirror of name table 0(0x2000) in vertical mirroring.
+        ppu.ppu_registers.ppu_ctrl = PpuCtrl::from_bits(0b00000010).unwrap();
+        let tile = ppu.build_tile(TilePosition::new(0, 0)).unwrap();
+        assert_eq!(tile.sprite, build_sprite(&word_vec).unwrap());
+
+        // switching to horizontal mirroring at runtime.
+        ppu.set_mirroring(MirroringType::Horizontal);
+        let tile = ppu.build_tile(TilePosition::new(0, 0)).unwrap();
+        assert_eq!(tile.sprite, build_sprite(&[0; 16]).unwrap());
+    }
+
+    #[test]
+    fn build_tile_from_background_pattern_table_test() {
+        let word_vec = vec![
+            // channel 1
+            0b11100111, 0b11100111, 0b11100111, 0b11111111, 0b11111111, 0b11100111, 0b11100111,
+            0b11100111, // channel 2
+            0b11000110, 0b11000110, 0b11000110, 0b11111110, 0b11000110, 0b11000110, 0b11000110,
+            0b00000000,
+        ];
+
+        // sprite index 0 is empty in $0000, and 'H' in $1000.
+        let pattern_table = PatternTable::from_vec([vec![0; 0x1000], word_vec.clone()].concat()).unwrap();
+        let mut ppu = Ppu::new(pattern_table, NameTable::new(MirroringType::Vertical));
+
+        let tile = ppu.build_tile(TilePosition::new(0, 0)).unwrap();
+        assert_eq!(tile.sprite, build_sprite(&[0; 16]).unwrap());
+
+        ppu.ppu_registers.ppu_ctrl = PpuCtrl::BACKGROUND_PATTERN_TABLE_ADDRESS;
+        let tile = ppu.build_tile(TilePosition::new(0, 0)).unwrap();
+        assert_eq!(tile.sprite, build_sprite(&word_vec).unwrap());
+    }
+
+    /// Run the PPU and the mapper like `Nes::step`, with instructions of 2 CPU cycles.
+    fn run_lines(ppu: &mut Ppu, mapper: &SharedMapper, count: u16) {
+        let target = ppu.line + count;
+        while ppu.line < target {
+            mapper.borrow_mut().clock(2);
+            ppu.run(6);
+        }
+    }
+
+    #[test]
+    fn clock_mmc3_irq_counter_by_rendering_test() {
+        let mapper = share(Mmc3::new(
+            build_rom(0x2000, 4),
+            CharacterMemory::rom(build_rom(0x0400, 8)),
+            0x2000,
+            MirroringType::Vertical,
+            Mmc3Revision::RevB,
+        ));
+        mapper.borrow_mut().cpu_write(0xC000, 2); // latch
+        mapper.borrow_mut().cpu_write(0xC001, 0); // reload
+        mapper.borrow_mut().cpu_write(0xE001, 0); // enable
+
+        // Background from $0000, sprites from $1000. A12 rises once per line.
+        let mut ppu = Ppu::new(PatternTable::new(mapper.clone()), NameTable::new(MirroringType::Vertical));
+        ppu.ppu_registers.ppu_ctrl = PpuCtrl::SPRITE_PATTERN_TABLE_ADDRESS;
+
+        // No pattern is fetched while rendering is disabled.
+        run_lines(&mut ppu, &mapper, 3);
+        assert!(!mapper.borrow().irq());
+
+        ppu.ppu_registers.ppu_mask = PpuMask::SHOW_BACKGROUND;
+        run_lines(&mut ppu, &mapper, 2); // reload: 2, 1
+        assert!(!mapper.borrow().irq());
+        run_lines(&mut ppu, &mapper, 1); // 0
+        assert!(mapper.borrow().irq());
     }
 }
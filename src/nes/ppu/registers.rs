@@ -1,3 +1,4 @@
+pub mod io_latch;
 pub mod ppu_address;
 pub mod ppu_control;
 pub mod ppu_data;
@@ -7,6 +8,7 @@ pub mod ppu_status;
 pub mod oam;
 
 use self::{
+    io_latch::IoLatch,
     ppu_address::PpuAddress,
     ppu_control::{BaseNameTableAddress, PpuCtrl},
     ppu_data::PpuData,
@@ -27,6 +29,7 @@ pub struct PpuRegisters {
     pub ppu_scroll: PpuScroll,
     pub ppu_status: PpuStatus,
     pub oam: Oam,
+    pub io_latch: IoLatch,
 }
 
 impl PpuRegisters {
@@ -39,6 +42,7 @@ impl PpuRegisters {
             ppu_scroll: PpuScroll::new(),
             ppu_status: PpuStatus::empty(),
             oam: Oam::new(),
+            io_latch: IoLatch::new(),
         }
     }
 
@@ -0,0 +1,74 @@
+/* PPU open bus (I/O latch)
+https://www.nesdev.org/wiki/Open_bus_behavior#PPU_open_bus
+
+The PPU has its own data bus to the CPU. Writing to any PPU register fills the latch,
+and reading a write-only register returns the latch.
+The latch decays to 0 if it is not refreshed for about 600ms.
+*/
+
+/// About 600ms in NTSC frames.
+const DECAY_FRAMES: u8 = 36;
+
+pub struct IoLatch {
+    value: u8,
+    frames_since_refresh: u8,
+}
+
+impl IoLatch {
+    pub fn new() -> Self {
+        IoLatch {
+            value: 0,
+            frames_since_refresh: 0,
+        }
+    }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    pub fn refresh(&mut self, value: u8) -> u8 {
+        self.value = value;
+        self.frames_since_refresh = 0;
+        value
+    }
+
+    /// Called at the end of every frame.
+    pub fn decay(&mut self) {
+        self.frames_since_refresh = self.frames_since_refresh.saturating_add(1);
+        if self.frames_since_refresh >= DECAY_FRAMES {
+            self.value = 0;
+        }
+    }
+}
+
+impl Default for IoLatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod io_latch_test {
+    use super::*;
+
+    #[test]
+    fn refresh_test() {
+        let mut io_latch = IoLatch::new();
+        assert_eq!(io_latch.refresh(0x12), 0x12);
+        assert_eq!(io_latch.value(), 0x12);
+    }
+
+    #[test]
+    fn decay_test() {
+        let mut io_latch = IoLatch::new();
+        io_latch.refresh(0x12);
+
+        for _ in 0..DECAY_FRAMES - 1 {
+            io_latch.decay();
+        }
+        assert_eq!(io_latch.value(), 0x12);
+
+        io_latch.decay();
+        assert_eq!(io_latch.value(), 0x00);
+    }
+}
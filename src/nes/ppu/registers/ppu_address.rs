@@ -1,3 +1,5 @@
+const PPU_ADDRESS_MASK: u16 = 0x3FFF;
+
 pub struct PpuAddress {
     pub addr: u16,
     pub is_lower_addr: bool,
@@ -15,15 +17,16 @@ impl PpuAddress {
         self.addr
     }
 
+    /// The PPU address space is 14 bits, and the address wraps around at 0x3FFF.
     pub fn increment(&mut self, offset: u8) {
-        self.addr += offset as u16;
+        self.addr = (self.addr + offset as u16) & PPU_ADDRESS_MASK;
     }
 
     pub fn write(&mut self, data: u16) {
         if self.is_lower_addr {
-            self.addr += data;
+            self.addr = (self.addr & 0xFF00) | (data & 0x00FF);
         } else {
-            self.addr = (data) << 8;
+            self.addr = (data << 8) & PPU_ADDRESS_MASK;
         }
 
         self.is_lower_addr = !self.is_lower_addr;
@@ -51,6 +54,17 @@ mod ppu_addr_test {
         assert_eq!(ppu_addr.addr, 0x2345);
     }
 
+    #[test]
+    fn wrap_around_test() {
+        let mut ppu_addr = PpuAddress::new();
+        ppu_addr.write(0x7F);
+        ppu_addr.write(0xFF);
+        assert_eq!(ppu_addr.addr, 0x3FFF);
+
+        ppu_addr.increment(1);
+        assert_eq!(ppu_addr.addr, 0x0000);
+    }
+
     #[test]
     fn reset_latch_test() {
         let mut ppu_addr = PpuAddress::new();
@@ -31,6 +31,21 @@ impl PpuCtrl {
         }
     }
 
+    pub fn background_pattern_table_address(&self) -> u16 {
+        match self.contains(PpuCtrl::BACKGROUND_PATTERN_TABLE_ADDRESS) {
+            true => 0x1000,
+            false => 0x0000,
+        }
+    }
+
+    /// Ignored by 8x16 sprites, which select the table by the tile index.
+    pub fn sprite_pattern_table_address(&self) -> u16 {
+        match self.contains(PpuCtrl::SPRITE_PATTERN_TABLE_ADDRESS) {
+            true => 0x1000,
+            false => 0x0000,
+        }
+    }
+
     pub fn get_vram_increment_offset(&self) -> u8 {
         if self.contains(PpuCtrl::VRAM_ADDRESS_INCREMENT) {
             32
@@ -56,4 +71,22 @@ mod ppu_control_tests {
         assert_eq!(type2.base_name_table_address(), BaseNameTableAddress::TYPE2);
         assert_eq!(type3.base_name_table_address(), BaseNameTableAddress::TYPE3);
     }
+
+    #[test]
+    fn background_pattern_table_address_test() {
+        let ctrl = PpuCtrl::from_bits(0b00000000).unwrap();
+        assert_eq!(ctrl.background_pattern_table_address(), 0x0000);
+
+        let ctrl = PpuCtrl::from_bits(0b00010000).unwrap();
+        assert_eq!(ctrl.background_pattern_table_address(), 0x1000);
+    }
+
+    #[test]
+    fn sprite_pattern_table_address_test() {
+        let ctrl = PpuCtrl::from_bits(0b00000000).unwrap();
+        assert_eq!(ctrl.sprite_pattern_table_address(), 0x0000);
+
+        let ctrl = PpuCtrl::from_bits(0b00001000).unwrap();
+        assert_eq!(ctrl.sprite_pattern_table_address(), 0x1000);
+    }
 }
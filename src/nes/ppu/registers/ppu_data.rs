@@ -1,8 +1,10 @@
-use crate::nes::{
-    ppu::{palette_ram::PaletteRam, pattern_table::PatternTable},
-    ram::Ram,
+use crate::nes::ppu::{
+    name_table::NameTable, palette_ram::PaletteRam, pattern_table::PatternTable,
+    registers::ppu_mask::PpuMask,
 };
 
+const PPU_ADDRESS_MASK: u16 = 0x3FFF;
+
 enum MapType {
     PatternTable,
     Vram,
@@ -13,14 +15,14 @@ enum MapType {
 
 struct PpuMemoryMapRule;
 impl PpuMemoryMapRule {
+    /// 0x4000..=0xFFFF mirrors 0x0000..=0x3FFF.
     fn address_to_map_type(addr: u16) -> MapType {
-        match addr {
+        match addr & PPU_ADDRESS_MASK {
             0x0000..=0x1FFF => MapType::PatternTable,
             0x2000..=0x2FFF => MapType::Vram,
             0x3000..=0x3EFF => MapType::VramMirror,
             0x3F00..=0x3F1F => MapType::Palette,
-            0x3F20..=0x3FFF => MapType::PaletteMirror,
-            _ => panic!("Access to out of PPU memory."),
+            _ => MapType::PaletteMirror, // 0x3F20..=0x3FFF
         }
     }
 }
@@ -34,27 +36,45 @@ impl PpuData {
         PpuData { buf: 0 }
     }
 
-    pub fn write(&mut self, address: u16, data: u8, palette_ram: &mut PaletteRam, vram: &mut Ram) {
-        println!("PPU data write | Address: {:x} Data: {:x}", address, data);
+    pub fn write(
+        &mut self,
+        address: u16,
+        data: u8,
+        pattern_table: &mut PatternTable,
+        palette_ram: &mut PaletteRam,
+        name_table: &mut NameTable,
+    ) {
+        let address = address & PPU_ADDRESS_MASK;
         let calibrated_addr = self.calibrate_address(address);
 
         match PpuMemoryMapRule::address_to_map_type(address) {
-            MapType::PatternTable => panic!("attempt to write to chr rom space {}", address),
-            MapType::Vram | MapType::VramMirror => vram.write(calibrated_addr, data),
+            // Only CHR-RAM is written. The mapper ignores writes to CHR-ROM.
+            MapType::PatternTable => pattern_table.write(calibrated_addr, data),
+            MapType::Vram | MapType::VramMirror => name_table.write(calibrated_addr, data),
             MapType::Palette | MapType::PaletteMirror => palette_ram.write(calibrated_addr, data),
         };
     }
 
-    pub fn read(&mut self, addr: u16, pattern_table: &mut PatternTable, vram: &mut Ram) -> u8 {
+    pub fn read(
+        &mut self,
+        addr: u16,
+        pattern_table: &mut PatternTable,
+        name_table: &mut NameTable,
+        palette_ram: &PaletteRam,
+        ppu_mask: &PpuMask,
+    ) -> u8 {
         let buf = self.buf;
+        let addr = addr & PPU_ADDRESS_MASK;
         let calibrated_addr = self.calibrate_address(addr);
 
         match PpuMemoryMapRule::address_to_map_type(addr) {
-            MapType::PatternTable => self.buf = *pattern_table.read(calibrated_addr),
-            MapType::Vram | MapType::VramMirror => self.buf = *vram.read(calibrated_addr),
+            MapType::PatternTable => self.buf = pattern_table.read(calibrated_addr),
+            MapType::Vram | MapType::VramMirror => self.buf = *name_table.read(calibrated_addr),
             MapType::Palette | MapType::PaletteMirror => {
-                self.buf = *vram.read(calibrated_addr);
-                return self.buf;
+                // Palette is returned immediately,
+                // and the buffer is filled with the name table "underneath" it. (0x3F00 -> 0x2F00)
+                self.buf = *name_table.read(addr - 0x1000);
+                return palette_ram.read(calibrated_addr) & ppu_mask.color_mask();
             }
         };
 
@@ -64,8 +84,9 @@ impl PpuData {
     fn calibrate_address(&self, addr: u16) -> u16 {
         match PpuMemoryMapRule::address_to_map_type(addr) {
             MapType::PatternTable => addr,
+            // Name table mirroring is resolved by `NameTable`.
             MapType::Vram => addr - 0x2000,
-            MapType::VramMirror => addr - 0x3000,
+            MapType::VramMirror => addr - 0x2000,
             MapType::Palette => addr - 0x3F00,
             // 0x3F20 ~ 0x3FFF = mirror of palette x 7
             // 0x3F20 ~ 0x3F3F -> 0x3F00 ~ 0x3F1F
@@ -79,21 +100,30 @@ impl PpuData {
 #[cfg(test)]
 mod ppu_data_test {
     use crate::nes::{
+        cartridge::header::MirroringType,
         ppu::{
-            palette_ram::PaletteRam, pattern_table::PatternTable, ppu::Ppu,
-            registers::ppu_data::PpuData,
+            name_table::NameTable,
+            palette_ram::PaletteRam,
+            pattern_table::PatternTable,
+            ppu::Ppu,
+            registers::{ppu_data::PpuData, ppu_mask::PpuMask},
         },
-        ram::Ram,
     };
 
     #[test]
     fn read_pattern_test() {
         let pattern_table = PatternTable::from_vec(vec![0xFF; 16]).unwrap();
-        let vram = Ram::new(0x20);
-        let mut ppu = Ppu::new(pattern_table, vram);
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
 
         let mut ppu_data = PpuData { buf: 0xEE };
-        let read_data = ppu_data.read(0x0000, &mut ppu.pattern_table, &mut ppu.vram);
+        let read_data = ppu_data.read(
+            0x0000,
+            &mut ppu.pattern_table,
+            &mut ppu.name_table,
+            &ppu.palette_ram,
+            &PpuMask::empty(),
+        );
 
         assert_eq!(read_data, 0xEE);
         assert_eq!(ppu_data.buf, 0xFF);
@@ -102,13 +132,19 @@ mod ppu_data_test {
     #[test]
     fn read_vram_test() {
         let pattern_table = PatternTable::from_vec(vec![0xFF; 16]).unwrap();
-        let vram = Ram::new(0x20);
-        let mut ppu = Ppu::new(pattern_table, vram);
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
 
-        ppu.vram.write(0x00, 0xFF);
+        ppu.name_table.write(0x00, 0xFF);
 
         let mut ppu_data = PpuData { buf: 0xEE };
-        let read_data = ppu_data.read(0x2000, &mut ppu.pattern_table, &mut ppu.vram);
+        let read_data = ppu_data.read(
+            0x2000,
+            &mut ppu.pattern_table,
+            &mut ppu.name_table,
+            &ppu.palette_ram,
+            &PpuMask::empty(),
+        );
 
         assert_eq!(read_data, 0xEE);
         assert_eq!(ppu_data.buf, 0xFF);
@@ -117,13 +153,19 @@ mod ppu_data_test {
     #[test]
     fn read_vram_mirror_test() {
         let pattern_table = PatternTable::from_vec(vec![0xFF; 16]).unwrap();
-        let vram = Ram::new(0x20);
-        let mut ppu = Ppu::new(pattern_table, vram);
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
 
-        ppu.vram.write(0x00, 0xFF);
+        ppu.name_table.write(0x00, 0xFF);
 
         let mut ppu_data = PpuData { buf: 0xEE };
-        let read_data = ppu_data.read(0x3000, &mut ppu.pattern_table, &mut ppu.vram);
+        let read_data = ppu_data.read(
+            0x3000,
+            &mut ppu.pattern_table,
+            &mut ppu.name_table,
+            &ppu.palette_ram,
+            &PpuMask::empty(),
+        );
 
         assert_eq!(read_data, 0xEE);
         assert_eq!(ppu_data.buf, 0xFF);
@@ -132,88 +174,167 @@ mod ppu_data_test {
     #[test]
     fn read_palette_test() {
         let pattern_table = PatternTable::from_vec(vec![0xFF; 16]).unwrap();
-        let vram = Ram::new(0x20);
-        let mut ppu = Ppu::new(pattern_table, vram);
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
 
-        ppu.vram.write(0x00, 0xFF);
-        ppu.palette_ram.write(0x00, 0xEE);
+        // 0x2F00 is "underneath" the palette 0x3F00.
+        ppu.name_table.write(0x0F00, 0xFF);
+        ppu.palette_ram.write(0x00, 0x2E);
 
         let mut ppu_data = PpuData { buf: 0xDD };
-        let read_data = ppu_data.read(0x3F00, &mut ppu.pattern_table, &mut ppu.vram);
-
-        assert_eq!(read_data, 0xFF);
+        let read_data = ppu_data.read(
+            0x3F00,
+            &mut ppu.pattern_table,
+            &mut ppu.name_table,
+            &ppu.palette_ram,
+            &PpuMask::empty(),
+        );
+
+        assert_eq!(read_data, 0x2E);
         assert_eq!(ppu_data.buf, 0xFF);
     }
 
     #[test]
     fn read_palette_mirror_test() {
         let pattern_table = PatternTable::from_vec(vec![0xFF; 16]).unwrap();
-        let vram = Ram::new(0x20);
-        let mut ppu = Ppu::new(pattern_table, vram);
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
 
-        ppu.vram.write(0x00, 0xFF);
-        ppu.palette_ram.write(0x00, 0xEE);
+        ppu.name_table.write(0x0F20, 0xFF);
+        ppu.palette_ram.write(0x00, 0x2E);
 
         let mut ppu_data = PpuData { buf: 0xEE };
-        let read_data = ppu_data.read(0x3F20, &mut ppu.pattern_table, &mut ppu.vram);
-
-        assert_eq!(read_data, 0xFF);
+        let read_data = ppu_data.read(
+            0x3F20,
+            &mut ppu.pattern_table,
+            &mut ppu.name_table,
+            &ppu.palette_ram,
+            &PpuMask::empty(),
+        );
+
+        assert_eq!(read_data, 0x2E);
         assert_eq!(ppu_data.buf, 0xFF);
     }
 
     #[test]
-    #[should_panic]
+    fn read_palette_with_grayscale_test() {
+        let pattern_table = PatternTable::from_vec(vec![0xFF; 16]).unwrap();
+        let name_table = NameTable::new(MirroringType::Horizontal);
+        let mut ppu = Ppu::new(pattern_table, name_table);
+
+        ppu.palette_ram.write(0x11, 0x2E);
+
+        let mut ppu_data = PpuData::new();
+        let read_data = ppu_data.read(
+            0x3F11,
+            &mut ppu.pattern_table,
+            &mut ppu.name_table,
+            &ppu.palette_ram,
+            &PpuMask::GRAYSCALE,
+        );
+
+        assert_eq!(read_data, 0x20);
+    }
+
+    #[test]
     fn write_pattern_test() {
+        let mut pattern_table = PatternTable::from_vec(vec![0x00; 16]).unwrap();
         let mut palette_ram = PaletteRam::new();
-        let mut vram = Ram::new(0x20);
+        let mut name_table = NameTable::new(MirroringType::Horizontal);
 
         let mut ppu_data = PpuData::new();
 
-        // Cannot write to pattern table
-        ppu_data.write(0x0000, 0xFF, &mut palette_ram, &mut vram);
+        // Cannot write to CHR-ROM
+        ppu_data.write(
+            0x0000,
+            0xFF,
+            &mut pattern_table,
+            &mut palette_ram,
+            &mut name_table,
+        );
+        assert_eq!(pattern_table.read(0x0000), 0x00);
+
+        // CHR-RAM can be written
+        let mut pattern_table = PatternTable::from_vec(Vec::new()).unwrap();
+        ppu_data.write(
+            0x0000,
+            0xFF,
+            &mut pattern_table,
+            &mut palette_ram,
+            &mut name_table,
+        );
+        assert_eq!(pattern_table.read(0x0000), 0xFF);
     }
 
     #[test]
     fn write_vram_test() {
+        let mut pattern_table = PatternTable::from_vec(Vec::new()).unwrap();
         let mut palette_ram = PaletteRam::new();
-        let mut vram = Ram::new(0x20);
+        let mut name_table = NameTable::new(MirroringType::Horizontal);
 
         let mut ppu_data = PpuData::new();
-        ppu_data.write(0x2000, 0xFF, &mut palette_ram, &mut vram);
-
-        assert_eq!(vram.read(0x0000), &0xFF);
+        ppu_data.write(
+            0x2000,
+            0xFF,
+            &mut pattern_table,
+            &mut palette_ram,
+            &mut name_table,
+        );
+
+        assert_eq!(name_table.read(0x0000), &0xFF);
     }
 
     #[test]
     fn write_vram_mirror_test() {
+        let mut pattern_table = PatternTable::from_vec(Vec::new()).unwrap();
         let mut palette_ram = PaletteRam::new();
-        let mut vram = Ram::new(0x20);
+        let mut name_table = NameTable::new(MirroringType::Horizontal);
 
         let mut ppu_data = PpuData::new();
-        ppu_data.write(0x3000, 0xFF, &mut palette_ram, &mut vram);
-
-        assert_eq!(vram.read(0x0000), &0xFF);
+        ppu_data.write(
+            0x3000,
+            0xFF,
+            &mut pattern_table,
+            &mut palette_ram,
+            &mut name_table,
+        );
+
+        assert_eq!(name_table.read(0x0000), &0xFF);
     }
 
     #[test]
     fn write_palette_test() {
+        let mut pattern_table = PatternTable::from_vec(Vec::new()).unwrap();
         let mut palette_ram = PaletteRam::new();
-        let mut vram = Ram::new(0x20);
+        let mut name_table = NameTable::new(MirroringType::Horizontal);
 
         let mut ppu_data = PpuData::new();
-        ppu_data.write(0x3F00, 0xFF, &mut palette_ram, &mut vram);
-
-        assert_eq!(palette_ram.read(0x0000), &0xFF);
+        ppu_data.write(
+            0x3F00,
+            0xFF,
+            &mut pattern_table,
+            &mut palette_ram,
+            &mut name_table,
+        );
+
+        assert_eq!(palette_ram.read(0x0000), &0x3F);
     }
 
     #[test]
     fn write_palette_mirror_test() {
+        let mut pattern_table = PatternTable::from_vec(Vec::new()).unwrap();
         let mut palette_ram = PaletteRam::new();
-        let mut vram = Ram::new(0x20);
+        let mut name_table = NameTable::new(MirroringType::Horizontal);
 
         let mut ppu_data = PpuData::new();
-        ppu_data.write(0x3F20, 0xFF, &mut palette_ram, &mut vram);
-
-        assert_eq!(palette_ram.read(0x0000), &0xFF);
+        ppu_data.write(
+            0x3F20,
+            0xFF,
+            &mut pattern_table,
+            &mut palette_ram,
+            &mut name_table,
+        );
+
+        assert_eq!(palette_ram.read(0x0000), &0x3F);
     }
 }
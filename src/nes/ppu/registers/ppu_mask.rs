@@ -12,3 +12,78 @@ bitflags! {
         const EMPHASIZE_BLUE              = 0b10000000; // Emphasize blue*
       }
 }
+
+impl PpuMask {
+    /// Mask applied to palette values. Grayscale mode keeps only the luminance bits (column 0x00, 0x10, 0x20, 0x30).
+    pub fn color_mask(&self) -> u8 {
+        if self.contains(PpuMask::GRAYSCALE) {
+            0x30
+        } else {
+            0x3F
+        }
+    }
+
+    /// Emphasis bits (0bBGR).
+    pub fn emphasis(&self) -> u8 {
+        self.bits >> 5
+    }
+
+    /// Rendering is disabled if both background and sprites are hidden.
+    pub fn is_rendering_enabled(&self) -> bool {
+        self.intersects(PpuMask::SHOW_BACKGROUND | PpuMask::SHOW_SPRITES)
+    }
+
+    pub fn is_background_visible_at(&self, x: usize) -> bool {
+        self.contains(PpuMask::SHOW_BACKGROUND)
+            && (x >= 8 || self.contains(PpuMask::SHOW_BACKGROUND_IN_LEFTMOST))
+    }
+
+    pub fn is_sprite_visible_at(&self, x: usize) -> bool {
+        self.contains(PpuMask::SHOW_SPRITES)
+            && (x >= 8 || self.contains(PpuMask::SHOW_SPRITES_IN_LEFTMOST))
+    }
+}
+
+#[cfg(test)]
+mod ppu_mask_tests {
+    use super::PpuMask;
+
+    #[test]
+    fn color_mask_test() {
+        assert_eq!(PpuMask::empty().color_mask(), 0x3F);
+        assert_eq!(PpuMask::GRAYSCALE.color_mask(), 0x30);
+    }
+
+    #[test]
+    fn emphasis_test() {
+        assert_eq!(PpuMask::empty().emphasis(), 0b000);
+        assert_eq!(PpuMask::EMPHASIZE_RED.emphasis(), 0b001);
+        assert_eq!(PpuMask::EMPHASIZE_GREEN.emphasis(), 0b010);
+        assert_eq!(PpuMask::EMPHASIZE_BLUE.emphasis(), 0b100);
+    }
+
+    #[test]
+    fn is_rendering_enabled_test() {
+        assert!(!PpuMask::empty().is_rendering_enabled());
+        assert!(PpuMask::SHOW_BACKGROUND.is_rendering_enabled());
+        assert!(PpuMask::SHOW_SPRITES.is_rendering_enabled());
+    }
+
+    #[test]
+    fn leftmost_clipping_test() {
+        let mask = PpuMask::SHOW_BACKGROUND | PpuMask::SHOW_SPRITES;
+        assert!(!mask.is_background_visible_at(7));
+        assert!(mask.is_background_visible_at(8));
+        assert!(!mask.is_sprite_visible_at(7));
+        assert!(mask.is_sprite_visible_at(8));
+
+        let mask = mask | PpuMask::SHOW_BACKGROUND_IN_LEFTMOST | PpuMask::SHOW_SPRITES_IN_LEFTMOST;
+        assert!(mask.is_background_visible_at(0));
+        assert!(mask.is_sprite_visible_at(0));
+
+        // Leftmost flags have no effect if the layer is hidden.
+        let mask = PpuMask::SHOW_BACKGROUND_IN_LEFTMOST;
+        assert!(!mask.is_background_visible_at(0));
+        assert!(!mask.is_background_visible_at(8));
+    }
+}
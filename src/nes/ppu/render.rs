@@ -1,7 +1,14 @@
-use super::{background::Background, frame::Frame};
+use super::{
+    background::Background, frame::Frame, palette::color_index, registers::ppu_mask::PpuMask,
+    system_palette::SystemPalette,
+};
 
 pub trait RenderContext<'a> {
     fn get_background(&'a self) -> &'a Background;
+    fn get_ppu_mask(&'a self) -> &'a PpuMask;
+    /// Palette number of the universal background color. ($3F00)
+    fn get_backdrop_palette_number(&'a self) -> u8;
+    fn get_system_palette(&'a self) -> &'a SystemPalette;
 }
 
 pub fn rendering_frame<'a, T>(ctx: &'a T) -> Frame
@@ -9,8 +16,26 @@ where
     T: RenderContext<'a>,
 {
     let background = ctx.get_background();
+    let ppu_mask = ctx.get_ppu_mask();
+    let color_mask = ppu_mask.color_mask();
+    let emphasis = ppu_mask.emphasis();
+    let system_palette = ctx.get_system_palette();
     let mut frame = Frame::new();
 
+    // Fill with the backdrop color. It is also displayed while rendering is disabled.
+    let backdrop_index = color_index(ctx.get_backdrop_palette_number() & color_mask, emphasis);
+    let backdrop_color = system_palette.get_color_code_by_index(backdrop_index);
+    for y in 0..Frame::HIGHT {
+        for x in 0..Frame::WIDTH {
+            frame.set_pixel(x, y, backdrop_color);
+            frame.set_palette_index(x, y, backdrop_index as u16);
+        }
+    }
+
+    if !ppu_mask.is_rendering_enabled() {
+        return frame;
+    }
+
     for tile in background.iter() {
         let sprite = &tile.sprite;
         let position = &tile.position;
@@ -35,10 +60,18 @@ where
             for (x, palette_number) in sprite_line.iter().enumerate() {
                 let point_x = start_pos_x + x;
                 let point_y = start_pos_y + y;
+
+                // Color number 0 is transparent, so the backdrop color is displayed.
+                if *palette_number == 0 || !ppu_mask.is_background_visible_at(point_x) {
+                    continue;
+                }
+
                 let palette = tile.palettes.get(*palette_number as usize);
-                let color = palette.get_color_code();
+                let index = color_index(palette.get_palette_number() & color_mask, emphasis);
+                let color = system_palette.get_color_code_by_index(index);
 
                 frame.set_pixel(point_x, point_y, color);
+                frame.set_palette_index(point_x, point_y, index as u16);
             }
         }
     }
@@ -50,8 +83,11 @@ where
 mod render_tests {
     use crate::nes::ppu::{
         background::Background,
+        frame::Frame,
         palette::{PaletteGroup, NES_COLORS},
+        registers::ppu_mask::PpuMask,
         sprite::build_sprite,
+        system_palette::SystemPalette,
         tile::Tile,
         tile_position::TilePosition,
     };
@@ -59,12 +95,45 @@ mod render_tests {
     use super::{rendering_frame, RenderContext};
     struct TestRenderContext {
         data: Background,
+        ppu_mask: PpuMask,
+        backdrop: u8,
+        system_palette: SystemPalette,
     }
 
     impl<'a> RenderContext<'a> for TestRenderContext {
         fn get_background(&'a self) -> &'a Background {
             &self.data
         }
+
+        fn get_ppu_mask(&'a self) -> &'a PpuMask {
+            &self.ppu_mask
+        }
+
+        fn get_backdrop_palette_number(&'a self) -> u8 {
+            self.backdrop
+        }
+
+        fn get_system_palette(&'a self) -> &'a SystemPalette {
+            &self.system_palette
+        }
+    }
+
+    fn build_background(pos_x: u8, pos_y: u8, palette_numbers: &[u8; 4]) -> Background {
+        // 8x8 tile filled with color number 3.
+        let sprite = build_sprite(&[0xFF; 16]).unwrap();
+        let tile = Tile {
+            sprite,
+            position: TilePosition::new(pos_x, pos_y),
+            palettes: PaletteGroup::build(palette_numbers),
+        };
+
+        let mut background = Background::new();
+        background.push(tile);
+        background
+    }
+
+    fn to_tuple(color: [u8; 3]) -> (u8, u8, u8) {
+        (color[0], color[1], color[2])
     }
 
     #[test]
@@ -106,7 +175,12 @@ mod render_tests {
         background.push(tile);
 
         // Rendering
-        let ctx = TestRenderContext { data: background };
+        let ctx = TestRenderContext {
+            data: background,
+            ppu_mask: PpuMask::SHOW_BACKGROUND | PpuMask::SHOW_BACKGROUND_IN_LEFTMOST,
+            backdrop: 2,
+            system_palette: SystemPalette::default(),
+        };
         let frame = rendering_frame(&ctx);
 
         let c0 = NES_COLORS[2];
@@ -140,4 +214,55 @@ mod render_tests {
             }
         }
     }
+
+    #[test]
+    fn rendering_disabled_shows_backdrop_test() {
+        let ctx = TestRenderContext {
+            data: build_background(0, 0, &[0x01, 0x02, 0x03, 0x04]),
+            ppu_mask: PpuMask::empty(),
+            backdrop: 0x21,
+            system_palette: SystemPalette::default(),
+        };
+        let frame = rendering_frame(&ctx);
+
+        let backdrop = to_tuple(NES_COLORS[0x21]);
+        assert_eq!(frame.get_pixel(0, 0), backdrop);
+        assert_eq!(frame.get_pixel(Frame::WIDTH - 1, Frame::HIGHT - 1), backdrop);
+    }
+
+    #[test]
+    fn leftmost_background_clipping_test() {
+        let mut ctx = TestRenderContext {
+            data: build_background(0, 0, &[0x01, 0x02, 0x03, 0x04]),
+            ppu_mask: PpuMask::SHOW_BACKGROUND,
+            backdrop: 0x21,
+            system_palette: SystemPalette::default(),
+        };
+        let frame = rendering_frame(&ctx);
+        assert_eq!(frame.get_pixel(7, 0), to_tuple(NES_COLORS[0x21]));
+
+        ctx.ppu_mask.insert(PpuMask::SHOW_BACKGROUND_IN_LEFTMOST);
+        let frame = rendering_frame(&ctx);
+        assert_eq!(frame.get_pixel(7, 0), to_tuple(NES_COLORS[0x04]));
+    }
+
+    #[test]
+    fn grayscale_and_emphasis_test() {
+        let ctx = TestRenderContext {
+            data: build_background(1, 0, &[0x01, 0x02, 0x03, 0x16]),
+            ppu_mask: PpuMask::SHOW_BACKGROUND | PpuMask::GRAYSCALE | PpuMask::EMPHASIZE_BLUE,
+            backdrop: 0x2C,
+            system_palette: SystemPalette::default(),
+        };
+        let frame = rendering_frame(&ctx);
+
+        // 0x16 -> 0x10, 0x2C -> 0x20 with emphasis blue.
+        let palette = SystemPalette::default();
+        assert_eq!(frame.get_pixel(8, 0), palette.get_color_code(0x10, 0b100));
+        assert_eq!(frame.get_pixel(0, 0), palette.get_color_code(0x20, 0b100));
+
+        // Raw palette indices are kept along with RGB.
+        assert_eq!(frame.get_palette_index(8, 0), 0x110);
+        assert_eq!(frame.get_palette_index(0, 0), 0x120);
+    }
 }
@@ -0,0 +1,132 @@
+use std::f32::consts::PI;
+
+/* NTSC composite signal generated by the PPU
+https://www.nesdev.org/wiki/NTSC_video
+
+The PPU outputs a square wave per pixel. The wave alternates between a low and a high voltage,
+and the position of the wave in the 12 phases of the color subcarrier decides the hue.
+
+pixel (9 bits): emphasis(3 bits: BGR) | luma(2 bits) | hue(4 bits)
+*/
+
+/// Voltage of the low/high level for each luma.
+const SIGNAL_LOW: [f32; 4] = [0.350, 0.518, 0.962, 1.550];
+const SIGNAL_HIGH: [f32; 4] = [1.094, 1.506, 1.962, 1.962];
+const SIGNAL_BLACK: f32 = 0.518;
+const SIGNAL_WHITE: f32 = 1.962;
+
+/// Signal is attenuated in the phases of emphasized colors.
+const EMPHASIS_ATTENUATION: f32 = 0.746;
+
+/// Count of phases in one cycle of the color subcarrier.
+pub const PHASE_COUNT: usize = 12;
+
+/// Phase offset (in phases) which makes the hues match the colors of a real TV.
+pub const DEFAULT_HUE_SHIFT: f32 = 3.9;
+
+fn in_color_phase(hue: u16, phase: usize) -> bool {
+    (hue as usize + phase) % PHASE_COUNT < 6
+}
+
+/// Signal level of `pixel` at `phase`. 0.0 is black and 1.0 is white.
+pub fn signal_level(pixel: u16, phase: usize) -> f32 {
+    let hue = pixel & 0x0F;
+    let luma = if hue > 0x0D { 1 } else { (pixel >> 4) & 0x03 } as usize;
+    let emphasis = (pixel >> 6) & 0b111;
+
+    let (low, high) = match hue {
+        // Hue 0 is gray (no wave), hue 0x0D-0x0F are black (no wave).
+        0x00 => (SIGNAL_HIGH[luma], SIGNAL_HIGH[luma]),
+        0x0D..=0x0F => (SIGNAL_LOW[luma], SIGNAL_LOW[luma]),
+        _ => (SIGNAL_LOW[luma], SIGNAL_HIGH[luma]),
+    };
+
+    let mut signal = if in_color_phase(hue, phase) {
+        high
+    } else {
+        low
+    };
+
+    let is_attenuated = (emphasis & 0b001 != 0 && in_color_phase(0x0C, phase))
+        || (emphasis & 0b010 != 0 && in_color_phase(0x04, phase))
+        || (emphasis & 0b100 != 0 && in_color_phase(0x08, phase));
+    if is_attenuated && hue < 0x0E {
+        signal *= EMPHASIS_ATTENUATION;
+    }
+
+    (signal - SIGNAL_BLACK) / (SIGNAL_WHITE - SIGNAL_BLACK)
+}
+
+/// Phase angle of the color subcarrier.
+pub fn phase_angle(phase: f32, hue_shift: f32) -> f32 {
+    PI * (phase + hue_shift) / 6.0
+}
+
+/// Convert YIQ to RGB.
+pub fn yiq_to_rgb(y: f32, i: f32, q: f32) -> (u8, u8, u8) {
+    let to_u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    (
+        to_u8(y + 0.946882 * i + 0.623557 * q),
+        to_u8(y - 0.274788 * i - 0.635691 * q),
+        to_u8(y - 1.108545 * i + 1.709007 * q),
+    )
+}
+
+/// Decode a whole cycle of the signal of `pixel` without artifacts.
+pub fn decode_color(pixel: u16, saturation: f32, hue_shift: f32) -> (u8, u8, u8) {
+    let (mut y, mut i, mut q) = (0.0, 0.0, 0.0);
+
+    for phase in 0..PHASE_COUNT {
+        let level = signal_level(pixel, phase);
+        let angle = phase_angle(phase as f32, hue_shift);
+
+        y += level;
+        i += level * angle.cos();
+        q += level * angle.sin();
+    }
+
+    let count = PHASE_COUNT as f32;
+    yiq_to_rgb(y / count, i / count * saturation, q / count * saturation)
+}
+
+#[cfg(test)]
+mod signal_test {
+    use super::*;
+
+    #[test]
+    fn signal_level_test() {
+        // 0x0F is black, 0x20 is white.
+        for phase in 0..PHASE_COUNT {
+            assert_eq!(signal_level(0x0F, phase), 0.0);
+            assert_eq!(signal_level(0x20, phase), 1.0);
+        }
+
+        // Hue 1-12 make a square wave.
+        let high_count = (0..PHASE_COUNT)
+            .filter(|phase| signal_level(0x16, *phase) > signal_level(0x1D, *phase))
+            .count();
+        assert_eq!(high_count, 6);
+    }
+
+    #[test]
+    fn emphasis_attenuates_signal_test() {
+        let attenuated = (0..PHASE_COUNT)
+            .filter(|phase| signal_level(0x20 | (0b001 << 6), *phase) < 1.0)
+            .count();
+        assert_eq!(attenuated, 6);
+
+        // Black is not affected by emphasis.
+        assert_eq!(signal_level(0x0F | (0b111 << 6), 0), 0.0);
+    }
+
+    #[test]
+    fn decode_color_test() {
+        assert_eq!(decode_color(0x0F, 1.0, 0.0), (0, 0, 0));
+        assert_eq!(decode_color(0x20, 1.0, 0.0), (255, 255, 255));
+
+        // gray has no saturation.
+        let (r, g, b) = decode_color(0x10, 1.0, 0.0);
+        assert!(r == g && g == b);
+    }
+}
@@ -52,9 +52,30 @@ pub fn build_sprite(data: &[u8]) -> Result<Sprite> {
     Ok(sprite_data)
 }
 
+/// Split a sprite into the two channels. The reverse of `build_sprite`.
+pub fn encode_sprite(sprite: &Sprite) -> Result<Vec<u8>> {
+    ensure!(
+        sprite.len() == SPRITE_HEIGHT && sprite.iter().all(|row| row.len() == SPRITE_WIDTH),
+        "invalid size of sprite."
+    );
+
+    let mut data = vec![0u8; 16];
+    for (y, row) in sprite.iter().enumerate() {
+        for (x, color_number) in row.iter().enumerate() {
+            ensure!(*color_number < 4, "invalid color number {}.", color_number);
+
+            let shift_size = SPRITE_WIDTH - 1 - x;
+            data[y] |= (color_number & 0b00000001) << shift_size;
+            data[y + 8] |= (color_number >> 1 & 0b00000001) << shift_size;
+        }
+    }
+
+    Ok(data)
+}
+
 #[cfg(test)]
 mod sprite_tests {
-    use super::build_sprite;
+    use super::{build_sprite, encode_sprite};
 
     #[test]
     fn create_sprite() {
@@ -113,4 +134,17 @@ mod sprite_tests {
         let sprite = build_sprite(&data);
         assert!(sprite.is_err());
     }
+
+    #[test]
+    fn encode_sprite_test() {
+        let data: Vec<u8> = (0..16).map(|i| i * 17).collect();
+        let sprite = build_sprite(&data).unwrap();
+        assert_eq!(encode_sprite(&sprite).unwrap(), data);
+    }
+
+    #[test]
+    fn encode_invalid_sprite() {
+        assert!(encode_sprite(&vec![vec![0; 8]; 7]).is_err());
+        assert!(encode_sprite(&vec![vec![4; 8]; 8]).is_err());
+    }
 }
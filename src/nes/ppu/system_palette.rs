@@ -0,0 +1,171 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+
+use super::{
+    palette::{build_emphasis_colors, color_index, NES_COLORS},
+    signal::{decode_color, DEFAULT_HUE_SHIFT},
+};
+
+/// 64 colors x RGB
+const PALETTE_FILE_SIZE: usize = 64 * 3;
+/// 8 emphasis patterns x 64 colors x RGB
+const EMPHASIS_PALETTE_FILE_SIZE: usize = 512 * 3;
+
+/// Names of the built-in palettes. The first one is the default.
+pub const BUILTIN_PALETTE_NAMES: [&str; 3] = ["default", "composite", "monochrome"];
+
+/* System palette
+https://www.nesdev.org/wiki/.pal
+
+Map from a palette number (with emphasis) to RGB.
+.pal files are a sequence of RGB triplets.
+- 192 bytes: 64 colors. Emphasis colors are generated from them.
+- 1536 bytes: 512 colors. The index is (emphasis << 6 | palette number).
+*/
+pub struct SystemPalette {
+    colors: [[u8; 3]; 512],
+}
+
+impl SystemPalette {
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let colors = match data.len() {
+            PALETTE_FILE_SIZE => {
+                let mut base_colors = [[0u8; 3]; 64];
+                for (color, rgb) in base_colors.iter_mut().zip(data.chunks(3)) {
+                    color.copy_from_slice(rgb);
+                }
+                build_emphasis_colors(&base_colors)
+            }
+            EMPHASIS_PALETTE_FILE_SIZE => {
+                let mut colors = [[0u8; 3]; 512];
+                for (color, rgb) in colors.iter_mut().zip(data.chunks(3)) {
+                    color.copy_from_slice(rgb);
+                }
+                colors
+            }
+            size => bail!(
+                "invalid palette size: {} bytes (must be {} or {} bytes)",
+                size,
+                PALETTE_FILE_SIZE,
+                EMPHASIS_PALETTE_FILE_SIZE
+            ),
+        };
+
+        Ok(SystemPalette { colors })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = fs::read(path.as_ref())?;
+        Self::from_bytes(&data)
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        let colors = match name {
+            "default" => build_emphasis_colors(&NES_COLORS),
+            "composite" => build_colors(|pixel| decode_color(pixel, 1.0, DEFAULT_HUE_SHIFT)),
+            "monochrome" => build_colors(|pixel| decode_color(pixel, 0.0, DEFAULT_HUE_SHIFT)),
+            _ => return None,
+        };
+
+        Some(SystemPalette { colors })
+    }
+
+    /// `value` is a name of the built-in palette or a path to a .pal file.
+    pub fn from_name_or_path(value: &str) -> Result<Self> {
+        match Self::builtin(value) {
+            Some(palette) => Ok(palette),
+            None => Self::load(value),
+        }
+    }
+
+    pub fn get_color_code(&self, palette_number: u8, emphasis: u8) -> (u8, u8, u8) {
+        self.get_color_code_by_index(color_index(palette_number, emphasis))
+    }
+
+    /// `index` is (emphasis << 6 | palette number).
+    pub fn get_color_code_by_index(&self, index: usize) -> (u8, u8, u8) {
+        let colors = self.colors[index & 0x1FF];
+        (colors[0], colors[1], colors[2])
+    }
+}
+
+impl Default for SystemPalette {
+    fn default() -> Self {
+        SystemPalette::builtin(BUILTIN_PALETTE_NAMES[0]).unwrap()
+    }
+}
+
+fn build_colors<F>(f: F) -> [[u8; 3]; 512]
+where
+    F: Fn(u16) -> (u8, u8, u8),
+{
+    let mut colors = [[0u8; 3]; 512];
+    for (pixel, color) in colors.iter_mut().enumerate() {
+        let (r, g, b) = f(pixel as u16);
+        *color = [r, g, b];
+    }
+    colors
+}
+
+#[cfg(test)]
+mod system_palette_test {
+    use super::*;
+
+    #[test]
+    fn default_palette_test() {
+        let palette = SystemPalette::default();
+        assert_eq!(palette.get_color_code(0x03, 0b000), (0x44, 0x00, 0x96));
+
+        // Emphasize red: green and blue are attenuated.
+        assert_eq!(palette.get_color_code(0x20, 0b001), (0xFF, 0xD0, 0xD0));
+        // Emphasize green and blue: each one attenuates the other, so all are attenuated.
+        assert_eq!(palette.get_color_code(0x20, 0b110), (0xD0, 0xD0, 0xD0));
+        // Emphasize all: every channel is attenuated.
+        assert_eq!(palette.get_color_code(0x20, 0b111), (0xD0, 0xD0, 0xD0));
+    }
+
+    #[test]
+    fn from_64_colors_bytes_test() {
+        let mut data = vec![0u8; PALETTE_FILE_SIZE];
+        data[3..6].copy_from_slice(&[0x10, 0x20, 0x30]);
+
+        let palette = SystemPalette::from_bytes(&data).unwrap();
+        assert_eq!(palette.get_color_code(0x01, 0b000), (0x10, 0x20, 0x30));
+    }
+
+    #[test]
+    fn from_512_colors_bytes_test() {
+        let mut data = vec![0u8; EMPHASIS_PALETTE_FILE_SIZE];
+        let index = color_index(0x01, 0b010);
+        data[index * 3..index * 3 + 3].copy_from_slice(&[0x10, 0x20, 0x30]);
+
+        let palette = SystemPalette::from_bytes(&data).unwrap();
+        assert_eq!(palette.get_color_code(0x01, 0b010), (0x10, 0x20, 0x30));
+        assert_eq!(palette.get_color_code(0x01, 0b000), (0x00, 0x00, 0x00));
+    }
+
+    #[test]
+    fn fails_from_invalid_size_bytes_test() {
+        assert!(SystemPalette::from_bytes(&[0u8; 191]).is_err());
+        assert!(SystemPalette::from_bytes(&[0u8; 1537]).is_err());
+    }
+
+    #[test]
+    fn builtin_palettes_test() {
+        for name in BUILTIN_PALETTE_NAMES {
+            assert!(SystemPalette::builtin(name).is_some());
+        }
+        assert!(SystemPalette::builtin("unknown").is_none());
+
+        let monochrome = SystemPalette::builtin("monochrome").unwrap();
+        let (r, g, b) = monochrome.get_color_code(0x16, 0b000);
+        assert!(r == g && g == b);
+    }
+
+    #[test]
+    fn from_name_or_path_test() {
+        assert!(SystemPalette::from_name_or_path("composite").is_ok());
+        assert!(SystemPalette::from_name_or_path("not/exist.pal").is_err());
+    }
+}
@@ -15,36 +15,29 @@ impl<'a> Ram {
         Self { data }
     }
 
+    /// Out-of-range addresses mirror the RAM, like chips with fewer address lines.
     pub fn read(&'a self, address: u16) -> &'a u8 {
-        match self.data.get(address as usize) {
-            Some(data) => data,
-            None => panic!(
-                "Out-of-range access to RAM. RAM size {:X} / address: {:X}",
-                self.data.len(),
-                address
-            ),
+        match self.data.is_empty() {
+            true => &0,
+            false => &self.data[address as usize % self.data.len()],
         }
     }
 
-    pub fn read_range(&'a self, range: Range<usize>) -> &'a [u8] {
-        match self.data.get(range.clone()) {
-            Some(data) => data,
-            None => panic!(
-                "Out-of-range access to RAM. RAM size {:X} / range: {:?}",
-                self.data.len(),
-                range
-            ),
+    /// Out-of-range addresses mirror the RAM, like `read`.
+    pub fn read_range(&self, range: Range<usize>) -> Vec<u8> {
+        match self.data.is_empty() {
+            true => vec![0; range.len()],
+            false => range
+                .map(|address| self.data[address % self.data.len()])
+                .collect(),
         }
     }
 
+    /// Out-of-range addresses mirror the RAM, like chips with fewer address lines.
     pub fn write(&mut self, address: u16, value: u8) {
-        match self.data.get_mut(address as usize) {
-            Some(data) => *data = value,
-            None => panic!(
-                "Out-of-range access to RAM. RAM size {:x} / address: {:x}",
-                self.data.len(),
-                address
-            ),
+        if !self.data.is_empty() {
+            let length = self.data.len();
+            self.data[address as usize % length] = value;
         }
     }
 
@@ -52,9 +45,23 @@ impl<'a> Ram {
         self.data.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn to_vec(&self) -> Vec<u8> {
         self.data.clone()
     }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Overwrite from the beginning. The size of the RAM does not change.
+    pub fn load(&mut self, data: &[u8]) {
+        let length = data.len().min(self.data.len());
+        self.data[..length].copy_from_slice(&data[..length]);
+    }
 }
 
 #[cfg(test)]
@@ -68,10 +75,13 @@ mod ram_tests {
     }
 
     #[test]
-    #[should_panic]
-    fn outrange_read_should_panic_test() {
-        let ram = Ram::new(1);
-        ram.read(0x001);
+    fn outrange_read_mirrors_test() {
+        let mut ram = Ram::new(2);
+        ram.write(0x001, 0x20);
+        assert_eq!(ram.read(0x003), &0x20);
+
+        let empty_ram = Ram::new(0);
+        assert_eq!(empty_ram.read(0x001), &0x00);
     }
 
     #[test]
@@ -81,10 +91,14 @@ mod ram_tests {
     }
 
     #[test]
-    #[should_panic]
-    fn outrange_read_range_should_panic_test() {
-        let ram = Ram::new(1);
-        ram.read_range(1..2);
+    fn outrange_read_range_mirrors_test() {
+        let mut ram = Ram::new(2);
+        ram.write(0x000, 0x10);
+        ram.write(0x001, 0x20);
+        assert_eq!(ram.read_range(1..4), &[0x20, 0x10, 0x20]);
+
+        let empty_ram = Ram::new(0);
+        assert_eq!(empty_ram.read_range(0..2), &[0x00, 0x00]);
     }
 
     #[test]
@@ -96,9 +110,22 @@ mod ram_tests {
     }
 
     #[test]
-    #[should_panic]
-    fn outrange_write_should_panic_test() {
-        let mut ram = Ram::new(1);
-        ram.write(0x001, 0x20);
+    fn load_test() {
+        let mut ram = Ram::new(2);
+        ram.load(&[0x01, 0x02, 0x03]);
+        assert_eq!(ram.as_slice(), &[0x01, 0x02]);
+
+        ram.load(&[0x04]);
+        assert_eq!(ram.as_slice(), &[0x04, 0x02]);
+    }
+
+    #[test]
+    fn outrange_write_mirrors_test() {
+        let mut ram = Ram::new(2);
+        ram.write(0x003, 0x20);
+        assert_eq!(ram.read(0x001), &0x20);
+
+        let mut empty_ram = Ram::new(0);
+        empty_ram.write(0x001, 0x20);
     }
 }
@@ -0,0 +1,64 @@
+use super::FilteredImage;
+
+/// Resize the image by `scale` with bilinear interpolation.
+pub fn bilinear(image: &FilteredImage, scale: f32) -> FilteredImage {
+    let width = ((image.width as f32 * scale).round() as usize).max(1);
+    let height = ((image.height as f32 * scale).round() as usize).max(1);
+    let mut output = FilteredImage::new(width, height);
+
+    // Position in the source image of the center of an output pixel.
+    let source_position = |position: usize, length: usize| {
+        let p = ((position as f32 + 0.5) / scale - 0.5).clamp(0.0, (length - 1) as f32);
+        let p0 = p.floor() as usize;
+        let p1 = (p0 + 1).min(length - 1);
+        (p0, p1, p - p0 as f32)
+    };
+
+    for y in 0..height {
+        let (y0, y1, fy) = source_position(y, image.height);
+
+        for x in 0..width {
+            let (x0, x1, fx) = source_position(x, image.width);
+
+            let lerp = |a: u8, b: u8, t: f32| a as f32 + (b as f32 - a as f32) * t;
+            let sample = |f: fn((u8, u8, u8)) -> u8| {
+                let top = lerp(f(image.get_pixel(x0, y0)), f(image.get_pixel(x1, y0)), fx);
+                let bottom = lerp(f(image.get_pixel(x0, y1)), f(image.get_pixel(x1, y1)), fx);
+                (top + (bottom - top) * fy).round() as u8
+            };
+
+            output.set_pixel(x, y, (sample(|c| c.0), sample(|c| c.1), sample(|c| c.2)));
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod bilinear_test {
+    use super::*;
+
+    #[test]
+    fn output_size_test() {
+        let image = FilteredImage::new(4, 3);
+        let output = bilinear(&image, 2.5);
+        assert_eq!((output.width, output.height), (10, 8));
+    }
+
+    #[test]
+    fn interpolate_test() {
+        let mut image = FilteredImage::new(2, 1);
+        image.set_pixel(1, 0, (200, 100, 0));
+
+        let output = bilinear(&image, 4.0);
+
+        // Edges keep the original colors.
+        assert_eq!(output.get_pixel(0, 0), (0, 0, 0));
+        assert_eq!(output.get_pixel(7, 0), (200, 100, 0));
+
+        // The middle is interpolated.
+        let (r, g, _) = output.get_pixel(4, 0);
+        assert!(r > 0 && r < 200);
+        assert!(g > 0 && g < 100);
+    }
+}
@@ -0,0 +1,101 @@
+use super::FilteredImage;
+
+/// Each pixel is drawn with 3x3 pixels, so that a scanline gap and an RGB triad fit in a pixel.
+pub const CRT_SCALE: usize = 3;
+/// Brightness of the channels which are masked by the aperture grille.
+const APERTURE_MASK_BRIGHTNESS: f32 = 0.7;
+
+pub struct CrtSettings {
+    /// Darkness of the gaps between scanlines. 0.0 (none) - 1.0 (black)
+    pub scanline_intensity: f32,
+    /// Emulate the RGB stripes of an aperture grille.
+    pub aperture_mask: bool,
+}
+
+/* CRT filter
+
+Scale each pixel to 3x3, darken the last line of every pixel as the gap between scanlines,
+and optionally leave only one channel in each column like the phosphors of an aperture grille.
+
+column: 0 1 2
+        R G B   <- line 0
+        R G B   <- line 1
+        r g b   <- line 2 (scanline gap)
+*/
+pub fn crt(image: &FilteredImage, settings: &CrtSettings) -> FilteredImage {
+    let mut output = FilteredImage::new(image.width * CRT_SCALE, image.height * CRT_SCALE);
+    let scanline_brightness = 1.0 - settings.scanline_intensity.clamp(0.0, 1.0);
+
+    for y in 0..output.height {
+        let line_brightness = if y % CRT_SCALE == CRT_SCALE - 1 {
+            scanline_brightness
+        } else {
+            1.0
+        };
+
+        for x in 0..output.width {
+            let (r, g, b) = image.get_pixel(x / CRT_SCALE, y / CRT_SCALE);
+            let mut brightness = [line_brightness; 3];
+
+            if settings.aperture_mask {
+                for (channel, value) in brightness.iter_mut().enumerate() {
+                    if channel != x % 3 {
+                        *value *= APERTURE_MASK_BRIGHTNESS;
+                    }
+                }
+            }
+
+            let apply = |v: u8, brightness: f32| (v as f32 * brightness).round() as u8;
+            output.set_pixel(
+                x,
+                y,
+                (
+                    apply(r, brightness[0]),
+                    apply(g, brightness[1]),
+                    apply(b, brightness[2]),
+                ),
+            );
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod crt_test {
+    use super::*;
+
+    fn build_white_image() -> FilteredImage {
+        let mut image = FilteredImage::new(2, 2);
+        image.data.fill(200);
+        image
+    }
+
+    #[test]
+    fn scanline_test() {
+        let settings = CrtSettings {
+            scanline_intensity: 0.5,
+            aperture_mask: false,
+        };
+        let output = crt(&build_white_image(), &settings);
+
+        assert_eq!((output.width, output.height), (6, 6));
+        assert_eq!(output.get_pixel(0, 0), (200, 200, 200));
+        assert_eq!(output.get_pixel(0, 1), (200, 200, 200));
+        assert_eq!(output.get_pixel(0, 2), (100, 100, 100));
+        assert_eq!(output.get_pixel(0, 5), (100, 100, 100));
+    }
+
+    #[test]
+    fn aperture_mask_test() {
+        let settings = CrtSettings {
+            scanline_intensity: 0.0,
+            aperture_mask: true,
+        };
+        let output = crt(&build_white_image(), &settings);
+
+        assert_eq!(output.get_pixel(0, 0), (200, 140, 140));
+        assert_eq!(output.get_pixel(1, 0), (140, 200, 140));
+        assert_eq!(output.get_pixel(2, 0), (140, 140, 200));
+    }
+}
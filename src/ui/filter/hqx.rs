@@ -0,0 +1,127 @@
+use super::{scale_nx::neighbors, FilteredImage};
+
+/// Thresholds of YUV to decide that 2 colors are similar. (same as hqx)
+const THRESHOLD_Y: i32 = 48;
+const THRESHOLD_U: i32 = 7;
+const THRESHOLD_V: i32 = 6;
+
+/* hq2x-style filter
+https://en.wikipedia.org/wiki/Hqx
+
+Like Scale2x, but compares colors by similarity in YUV space and blends the colors on edges
+instead of copying them, which makes smooth anti-aliased edges.
+
+A B C
+D E F
+G H I
+*/
+pub fn hq2x(image: &FilteredImage) -> FilteredImage {
+    let mut output = FilteredImage::new(image.width * 2, image.height * 2);
+
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let [a, b, c, d, e, f, g, h, i] = neighbors(image, x, y);
+
+            // (sides of the corner, diagonal of the corner) for each output pixel.
+            let corners = [(d, b, a), (b, f, c), (h, d, g), (f, h, i)];
+
+            for (n, (side1, side2, diagonal)) in corners.iter().enumerate() {
+                let pixel = interpolate_corner(e, *side1, *side2, *diagonal);
+                output.set_pixel(x * 2 + n % 2, y * 2 + n / 2, pixel);
+            }
+        }
+    }
+
+    output
+}
+
+fn interpolate_corner(
+    e: (u8, u8, u8),
+    side1: (u8, u8, u8),
+    side2: (u8, u8, u8),
+    diagonal: (u8, u8, u8),
+) -> (u8, u8, u8) {
+    if is_similar(side1, side2) && !is_similar(e, side1) {
+        // An edge passes through the corner.
+        blend(&[(e, 2), (side1, 1), (side2, 1)])
+    } else if !is_similar(e, diagonal) && is_similar(e, side1) && is_similar(e, side2) {
+        // Soften a corner of the shape.
+        blend(&[(e, 3), (diagonal, 1)])
+    } else {
+        e
+    }
+}
+
+fn to_yuv(rgb: (u8, u8, u8)) -> (i32, i32, i32) {
+    let (r, g, b) = (rgb.0 as f32, rgb.1 as f32, rgb.2 as f32);
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    let u = -0.169 * r - 0.331 * g + 0.5 * b + 128.0;
+    let v = 0.5 * r - 0.419 * g - 0.081 * b + 128.0;
+    (y as i32, u as i32, v as i32)
+}
+
+fn is_similar(color1: (u8, u8, u8), color2: (u8, u8, u8)) -> bool {
+    let (y1, u1, v1) = to_yuv(color1);
+    let (y2, u2, v2) = to_yuv(color2);
+
+    (y1 - y2).abs() <= THRESHOLD_Y && (u1 - u2).abs() <= THRESHOLD_U && (v1 - v2).abs() <= THRESHOLD_V
+}
+
+/// Weighted average of colors.
+pub(super) fn blend(colors: &[((u8, u8, u8), u32)]) -> (u8, u8, u8) {
+    let total: u32 = colors.iter().map(|(_, weight)| weight).sum();
+    let channel = |f: fn(&(u8, u8, u8)) -> u8| {
+        let sum: u32 = colors.iter().map(|(c, w)| f(c) as u32 * w).sum();
+        ((sum + total / 2) / total) as u8
+    };
+
+    (channel(|c| c.0), channel(|c| c.1), channel(|c| c.2))
+}
+
+#[cfg(test)]
+mod hqx_test {
+    use super::*;
+
+    const BLACK: (u8, u8, u8) = (0, 0, 0);
+    const WHITE: (u8, u8, u8) = (255, 255, 255);
+
+    #[test]
+    fn is_similar_test() {
+        assert!(is_similar(BLACK, (10, 10, 10)));
+        assert!(!is_similar(BLACK, WHITE));
+        assert!(!is_similar((255, 0, 0), (0, 0, 255)));
+    }
+
+    #[test]
+    fn blend_test() {
+        assert_eq!(blend(&[(BLACK, 1), (WHITE, 1)]), (128, 128, 128));
+        assert_eq!(blend(&[(WHITE, 3), (BLACK, 1)]), (191, 191, 191));
+    }
+
+    #[test]
+    fn hq2x_flat_image_test() {
+        let mut image = FilteredImage::new(3, 3);
+        image.data.fill(0x80);
+
+        let output = hq2x(&image);
+        assert_eq!((output.width, output.height), (6, 6));
+        assert!(output.data.iter().all(|v| *v == 0x80));
+    }
+
+    #[test]
+    fn hq2x_blends_edges_test() {
+        // White triangle on the bottom-left.
+        let mut image = FilteredImage::new(2, 2);
+        image.set_pixel(0, 1, WHITE);
+
+        let output = hq2x(&image);
+
+        // The corner on the edge is blended, not copied.
+        let (r, g, b) = output.get_pixel(1, 2);
+        assert!(r > 0 && r < 255);
+        assert!(r == g && g == b);
+
+        // The inside of the shape is kept.
+        assert_eq!(output.get_pixel(0, 3), WHITE);
+    }
+}
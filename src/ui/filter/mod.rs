@@ -0,0 +1,177 @@
+pub mod bilinear;
+pub mod crt;
+pub mod hqx;
+pub mod ntsc;
+pub mod scale_nx;
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::nes::ppu::frame::{convert_rgb24, Frame, PixelFormat};
+
+use self::crt::CrtSettings;
+
+/// RGB24 image produced by a filter. Its size may differ from `Frame`.
+pub struct FilteredImage {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+impl FilteredImage {
+    pub fn new(width: usize, height: usize) -> Self {
+        FilteredImage {
+            width,
+            height,
+            data: vec![0; width * height * 3],
+        }
+    }
+
+    pub fn from_frame(frame: &Frame) -> Self {
+        FilteredImage {
+            width: Frame::WIDTH,
+            height: Frame::HIGHT,
+            data: frame.data.clone(),
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, rgb: (u8, u8, u8)) {
+        let base = (y * self.width + x) * 3;
+        self.data[base] = rgb.0;
+        self.data[base + 1] = rgb.1;
+        self.data[base + 2] = rgb.2;
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> (u8, u8, u8) {
+        let base = (y * self.width + x) * 3;
+        (self.data[base], self.data[base + 1], self.data[base + 2])
+    }
+
+    /// Bytes per row in `format`.
+    pub fn pitch(&self, format: PixelFormat) -> usize {
+        self.width * format.bytes_per_pixel()
+    }
+
+    pub fn to_pixel_format(&self, format: PixelFormat) -> Vec<u8> {
+        convert_rgb24(&self.data, format)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        image::save_buffer_with_format(
+            path,
+            &self.data,
+            self.width as u32,
+            self.height as u32,
+            image::ColorType::Rgb8,
+            image::ImageFormat::Png,
+        )?;
+
+        Ok(())
+    }
+}
+
+pub const SCALE_FILTER_NAMES: [&str; 6] = ["none", "scale2x", "scale3x", "hq2x", "bilinear", "crt"];
+
+/// Software filter applied to the image before it is uploaded to the texture.
+pub enum ScaleFilter {
+    None,
+    Scale2x,
+    Scale3x,
+    Hq2x,
+    Bilinear(f32),
+    Crt(CrtSettings),
+}
+
+impl ScaleFilter {
+    pub const BILINEAR_SCALE: f32 = 3.0;
+
+    pub fn from_name(name: &str, crt_settings: CrtSettings) -> Option<Self> {
+        let filter = match name {
+            "none" => ScaleFilter::None,
+            "scale2x" => ScaleFilter::Scale2x,
+            "scale3x" => ScaleFilter::Scale3x,
+            "hq2x" => ScaleFilter::Hq2x,
+            "bilinear" => ScaleFilter::Bilinear(Self::BILINEAR_SCALE),
+            "crt" => ScaleFilter::Crt(crt_settings),
+            _ => return None,
+        };
+
+        Some(filter)
+    }
+
+    /// Size of the output for an input of `width` x `height`.
+    pub fn output_size(&self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            ScaleFilter::None => (width, height),
+            ScaleFilter::Scale2x | ScaleFilter::Hq2x => (width * 2, height * 2),
+            ScaleFilter::Scale3x => (width * 3, height * 3),
+            ScaleFilter::Bilinear(scale) => (
+                ((width as f32 * scale).round() as usize).max(1),
+                ((height as f32 * scale).round() as usize).max(1),
+            ),
+            ScaleFilter::Crt(_) => (width * crt::CRT_SCALE, height * crt::CRT_SCALE),
+        }
+    }
+
+    pub fn apply(&self, image: FilteredImage) -> FilteredImage {
+        match self {
+            ScaleFilter::None => image,
+            ScaleFilter::Scale2x => scale_nx::scale2x(&image),
+            ScaleFilter::Scale3x => scale_nx::scale3x(&image),
+            ScaleFilter::Hq2x => hqx::hq2x(&image),
+            ScaleFilter::Bilinear(scale) => bilinear::bilinear(&image, *scale),
+            ScaleFilter::Crt(settings) => crt::crt(&image, settings),
+        }
+    }
+}
+
+#[cfg(test)]
+mod filtered_image_test {
+    use super::*;
+
+    #[test]
+    fn from_frame_test() {
+        let mut frame = Frame::new();
+        frame.set_pixel(3, 4, (1, 2, 3));
+
+        let image = FilteredImage::from_frame(&frame);
+        assert_eq!(image.width, Frame::WIDTH);
+        assert_eq!(image.height, Frame::HIGHT);
+        assert_eq!(image.get_pixel(3, 4), (1, 2, 3));
+        assert_eq!(image.pitch(PixelFormat::Rgb24), Frame::WIDTH * 3);
+        assert_eq!(image.pitch(PixelFormat::Argb8888), Frame::WIDTH * 4);
+    }
+}
+
+#[cfg(test)]
+mod scale_filter_test {
+    use super::*;
+
+    fn crt_settings() -> CrtSettings {
+        CrtSettings {
+            scanline_intensity: 0.5,
+            aperture_mask: false,
+        }
+    }
+
+    #[test]
+    fn from_name_test() {
+        for name in SCALE_FILTER_NAMES {
+            assert!(ScaleFilter::from_name(name, crt_settings()).is_some());
+        }
+        assert!(ScaleFilter::from_name("xbrz", crt_settings()).is_none());
+    }
+
+    #[test]
+    fn output_size_test() {
+        let image = FilteredImage::new(4, 3);
+
+        for name in SCALE_FILTER_NAMES {
+            let filter = ScaleFilter::from_name(name, crt_settings()).unwrap();
+            let (width, height) = filter.output_size(image.width, image.height);
+            let output = filter.apply(FilteredImage::new(image.width, image.height));
+            assert_eq!((output.width, output.height), (width, height), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use crate::nes::ppu::{
+    frame::Frame,
+    signal::{phase_angle, signal_level, yiq_to_rgb, DEFAULT_HUE_SHIFT, PHASE_COUNT},
+};
+
+use super::FilteredImage;
+
+/// The PPU outputs 8 samples (phases of the color subcarrier) per pixel.
+const SAMPLES_PER_PIXEL: usize = 8;
+/// Each pixel is decoded into 2 output pixels.
+const OUTPUT_PIXELS_PER_PIXEL: usize = 2;
+const SAMPLES_PER_OUTPUT_PIXEL: usize = SAMPLES_PER_PIXEL / OUTPUT_PIXELS_PER_PIXEL;
+
+/// Phase advance per scanline. 341 pixels x 8 samples = 2728 = 4 (mod 12)
+const LINE_PHASE_SHIFT: usize = 4;
+/// Phase advance per frame, which makes the artifacts crawl.
+const FRAME_PHASE_SHIFT: usize = 4;
+
+/// Samples averaged to get luma. A full cycle removes the color subcarrier completely.
+const COMPOSITE_LUMA_WINDOW: usize = 6;
+const SEPARATED_LUMA_WINDOW: usize = PHASE_COUNT;
+/// Samples averaged to demodulate chroma (2 cycles).
+const CHROMA_WINDOW: usize = PHASE_COUNT * 2;
+
+pub const NTSC_PRESET_NAMES: [&str; 3] = ["composite", "svideo", "monochrome"];
+
+pub struct NtscSettings {
+    /// 0.0 (soft) - 1.0 (sharp)
+    pub sharpness: f32,
+    pub saturation: f32,
+    /// Hue offset in phases (1 phase = 30 degrees).
+    pub hue_shift: f32,
+    /// Move the artifacts every frame like a real NES.
+    pub dot_crawl: bool,
+    /// Luma and chroma are carried separately (S-Video), so the subcarrier does not leak into luma.
+    pub separate_luma: bool,
+}
+
+impl NtscSettings {
+    pub fn preset(name: &str) -> Option<Self> {
+        let settings = match name {
+            "composite" => NtscSettings {
+                sharpness: 0.0,
+                saturation: 1.0,
+                hue_shift: DEFAULT_HUE_SHIFT,
+                dot_crawl: true,
+                separate_luma: false,
+            },
+            "svideo" => NtscSettings {
+                sharpness: 0.5,
+                saturation: 1.0,
+                hue_shift: DEFAULT_HUE_SHIFT,
+                dot_crawl: false,
+                separate_luma: true,
+            },
+            "monochrome" => NtscSettings {
+                sharpness: 0.5,
+                saturation: 0.0,
+                hue_shift: DEFAULT_HUE_SHIFT,
+                dot_crawl: false,
+                separate_luma: true,
+            },
+            _ => return None,
+        };
+
+        Some(settings)
+    }
+}
+
+/* NTSC filter
+https://www.nesdev.org/wiki/NTSC_video
+
+Generate the composite signal from the raw palette indices of `Frame`, and decode it like a TV.
+Decoding a square wave with box filters makes the same artifacts (color fringes on edges,
+checkerboard patterns and dot crawl) as a real NES.
+*/
+pub struct NtscFilter {
+    settings: NtscSettings,
+    frame_count: usize,
+}
+
+impl NtscFilter {
+    pub const OUTPUT_WIDTH: usize = Frame::WIDTH * OUTPUT_PIXELS_PER_PIXEL;
+    pub const OUTPUT_HEIGHT: usize = Frame::HIGHT;
+
+    pub fn new(settings: NtscSettings) -> Self {
+        NtscFilter {
+            settings,
+            frame_count: 0,
+        }
+    }
+
+    pub fn apply(&mut self, frame: &Frame) -> FilteredImage {
+        let mut image = FilteredImage::new(Self::OUTPUT_WIDTH, Self::OUTPUT_HEIGHT);
+
+        let frame_phase = if self.settings.dot_crawl {
+            (self.frame_count * FRAME_PHASE_SHIFT) % PHASE_COUNT
+        } else {
+            0
+        };
+
+        // cos/sin of each phase for demodulation.
+        let carrier: Vec<(f32, f32)> = (0..PHASE_COUNT)
+            .map(|phase| {
+                let angle = phase_angle(phase as f32, self.settings.hue_shift);
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+
+        for y in 0..Frame::HIGHT {
+            let line_phase = frame_phase + y * LINE_PHASE_SHIFT;
+            let line = LineSignal::generate(frame, y, line_phase, &carrier);
+
+            for x in 0..Self::OUTPUT_WIDTH {
+                let rgb = self.decode(
+                    &line,
+                    x * SAMPLES_PER_OUTPUT_PIXEL + SAMPLES_PER_OUTPUT_PIXEL / 2,
+                );
+                image.set_pixel(x, y, rgb);
+            }
+        }
+
+        self.frame_count += 1;
+        image
+    }
+
+    fn decode(&self, line: &LineSignal, center: usize) -> (u8, u8, u8) {
+        let luma_window = if self.settings.separate_luma {
+            SEPARATED_LUMA_WINDOW
+        } else {
+            COMPOSITE_LUMA_WINDOW
+        };
+
+        let luma = line.average_luma(center, luma_window);
+        let sharp_luma = line.average_luma(center, SAMPLES_PER_OUTPUT_PIXEL);
+        let luma = luma + (sharp_luma - luma) * self.settings.sharpness;
+
+        let (i, q) = line.average_chroma(center, CHROMA_WINDOW);
+
+        yiq_to_rgb(
+            luma,
+            i * self.settings.saturation,
+            q * self.settings.saturation,
+        )
+    }
+}
+
+/// Prefix sums of the signal of a scanline, to average any range of samples in O(1).
+struct LineSignal {
+    luma: Vec<f32>,
+    i: Vec<f32>,
+    q: Vec<f32>,
+}
+
+impl LineSignal {
+    fn generate(frame: &Frame, y: usize, line_phase: usize, carrier: &[(f32, f32)]) -> Self {
+        let length = Frame::WIDTH * SAMPLES_PER_PIXEL;
+        let mut line = LineSignal {
+            luma: vec![0.0; length + 1],
+            i: vec![0.0; length + 1],
+            q: vec![0.0; length + 1],
+        };
+
+        for x in 0..Frame::WIDTH {
+            let pixel = frame.get_palette_index(x, y);
+
+            for sample in 0..SAMPLES_PER_PIXEL {
+                let n = x * SAMPLES_PER_PIXEL + sample;
+                let phase = (line_phase + n) % PHASE_COUNT;
+                let level = signal_level(pixel, phase);
+                let (cos, sin) = carrier[phase];
+
+                line.luma[n + 1] = line.luma[n] + level;
+                line.i[n + 1] = line.i[n] + level * cos;
+                line.q[n + 1] = line.q[n] + level * sin;
+            }
+        }
+
+        line
+    }
+
+    fn range(&self, center: usize, window: usize) -> (usize, usize) {
+        let length = self.luma.len() - 1;
+        let start = center.saturating_sub(window / 2);
+        let end = (start + window).min(length);
+        (start, end)
+    }
+
+    fn average_luma(&self, center: usize, window: usize) -> f32 {
+        let (start, end) = self.range(center, window);
+        (self.luma[end] - self.luma[start]) / (end - start) as f32
+    }
+
+    fn average_chroma(&self, center: usize, window: usize) -> (f32, f32) {
+        let (start, end) = self.range(center, window);
+        let count = (end - start) as f32;
+        (
+            (self.i[end] - self.i[start]) / count,
+            (self.q[end] - self.q[start]) / count,
+        )
+    }
+}
+
+#[cfg(test)]
+mod ntsc_filter_test {
+    use super::*;
+
+    fn fill(frame: &mut Frame, index: u16) {
+        for y in 0..Frame::HIGHT {
+            for x in 0..Frame::WIDTH {
+                frame.set_palette_index(x, y, index);
+            }
+        }
+    }
+
+    #[test]
+    fn presets_test() {
+        for name in NTSC_PRESET_NAMES {
+            assert!(NtscSettings::preset(name).is_some());
+        }
+        assert!(NtscSettings::preset("unknown").is_none());
+    }
+
+    #[test]
+    fn output_size_test() {
+        let mut filter = NtscFilter::new(NtscSettings::preset("composite").unwrap());
+        let image = filter.apply(&Frame::new());
+
+        assert_eq!(image.width, NtscFilter::OUTPUT_WIDTH);
+        assert_eq!(image.height, NtscFilter::OUTPUT_HEIGHT);
+        assert_eq!(image.data.len(), image.width * image.height * 3);
+    }
+
+    #[test]
+    fn decode_flat_colors_test() {
+        let mut filter = NtscFilter::new(NtscSettings::preset("composite").unwrap());
+
+        let mut frame = Frame::new();
+        fill(&mut frame, 0x0F);
+        let image = filter.apply(&frame);
+        assert_eq!(image.get_pixel(100, 100), (0, 0, 0));
+
+        fill(&mut frame, 0x20);
+        let image = filter.apply(&frame);
+        assert_eq!(image.get_pixel(100, 100), (255, 255, 255));
+
+        // 0x16 is red.
+        fill(&mut frame, 0x16);
+        let (r, g, b) = filter.apply(&frame).get_pixel(100, 100);
+        assert!(r > g && r > b);
+    }
+
+    #[test]
+    fn monochrome_has_no_color_test() {
+        let mut filter = NtscFilter::new(NtscSettings::preset("monochrome").unwrap());
+
+        let mut frame = Frame::new();
+        fill(&mut frame, 0x16);
+        let (r, g, b) = filter.apply(&frame).get_pixel(100, 100);
+        assert!(r == g && g == b);
+    }
+
+    #[test]
+    fn edges_make_artifacts_test() {
+        let mut filter = NtscFilter::new(NtscSettings::preset("composite").unwrap());
+
+        // Vertical stripes of black and white make color fringes.
+        let mut frame = Frame::new();
+        for y in 0..Frame::HIGHT {
+            for x in 0..Frame::WIDTH {
+                frame.set_palette_index(x, y, if x % 2 == 0 { 0x0F } else { 0x20 });
+            }
+        }
+
+        let image = filter.apply(&frame);
+        let has_color = (0..image.width).any(|x| {
+            let (r, g, b) = image.get_pixel(x, 100);
+            r != g || g != b
+        });
+        assert!(has_color);
+    }
+
+    #[test]
+    fn dot_crawl_test() {
+        let mut frame = Frame::new();
+        for y in 0..Frame::HIGHT {
+            for x in 0..Frame::WIDTH {
+                frame.set_palette_index(x, y, if x % 2 == 0 { 0x0F } else { 0x20 });
+            }
+        }
+
+        let mut filter = NtscFilter::new(NtscSettings::preset("composite").unwrap());
+        let first = filter.apply(&frame);
+        let second = filter.apply(&frame);
+        assert_ne!(first.data, second.data);
+
+        let mut filter = NtscFilter::new(NtscSettings::preset("svideo").unwrap());
+        let first = filter.apply(&frame);
+        let second = filter.apply(&frame);
+        assert_eq!(first.data, second.data);
+    }
+}
@@ -0,0 +1,139 @@
+use super::FilteredImage;
+
+/* Scale2x / Scale3x
+https://www.scale2x.it/algorithm
+
+Enlarge pixel art without blurring, by extending edges of the neighbor pixels.
+
+A B C
+D E F
+G H I
+*/
+pub fn scale2x(image: &FilteredImage) -> FilteredImage {
+    let mut output = FilteredImage::new(image.width * 2, image.height * 2);
+
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let [_, b, _, d, e, f, _, h, _] = neighbors(image, x, y);
+
+            let e0 = if d == b && b != f && d != h { d } else { e };
+            let e1 = if b == f && b != d && f != h { f } else { e };
+            let e2 = if d == h && d != b && h != f { d } else { e };
+            let e3 = if h == f && d != h && b != f { f } else { e };
+
+            output.set_pixel(x * 2, y * 2, e0);
+            output.set_pixel(x * 2 + 1, y * 2, e1);
+            output.set_pixel(x * 2, y * 2 + 1, e2);
+            output.set_pixel(x * 2 + 1, y * 2 + 1, e3);
+        }
+    }
+
+    output
+}
+
+pub fn scale3x(image: &FilteredImage) -> FilteredImage {
+    let mut output = FilteredImage::new(image.width * 3, image.height * 3);
+
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let [a, b, c, d, e, f, g, h, i] = neighbors(image, x, y);
+
+            let pixels = if b != h && d != f {
+                [
+                    if d == b { d } else { e },
+                    if (d == b && e != c) || (b == f && e != a) { b } else { e },
+                    if b == f { f } else { e },
+                    if (d == b && e != g) || (d == h && e != a) { d } else { e },
+                    e,
+                    if (b == f && e != i) || (h == f && e != c) { f } else { e },
+                    if d == h { d } else { e },
+                    if (d == h && e != i) || (h == f && e != g) { h } else { e },
+                    if h == f { f } else { e },
+                ]
+            } else {
+                [e; 9]
+            };
+
+            for (n, pixel) in pixels.iter().enumerate() {
+                output.set_pixel(x * 3 + n % 3, y * 3 + n / 3, *pixel);
+            }
+        }
+    }
+
+    output
+}
+
+/// 3x3 pixels around (x, y). The edges of the image are repeated.
+pub(super) fn neighbors(image: &FilteredImage, x: usize, y: usize) -> [(u8, u8, u8); 9] {
+    let mut pixels = [(0, 0, 0); 9];
+
+    for (n, pixel) in pixels.iter_mut().enumerate() {
+        let px = (x + n % 3).saturating_sub(1).min(image.width - 1);
+        let py = (y + n / 3).saturating_sub(1).min(image.height - 1);
+        *pixel = image.get_pixel(px, py);
+    }
+
+    pixels
+}
+
+#[cfg(test)]
+mod scale_nx_test {
+    use super::*;
+
+    const BLACK: (u8, u8, u8) = (0, 0, 0);
+    const WHITE: (u8, u8, u8) = (255, 255, 255);
+
+    /// White triangle on the bottom-left.
+    ///  . .
+    ///  # .
+    fn build_diagonal_image() -> FilteredImage {
+        let mut image = FilteredImage::new(2, 2);
+        image.set_pixel(0, 1, WHITE);
+        image
+    }
+
+    #[test]
+    fn scale2x_test() {
+        let output = scale2x(&build_diagonal_image());
+        assert_eq!((output.width, output.height), (4, 4));
+
+        // The edge between (0, 1) and (1, 0) is smoothed.
+        assert_eq!(output.get_pixel(0, 2), WHITE);
+        assert_eq!(output.get_pixel(1, 2), BLACK);
+        assert_eq!(output.get_pixel(0, 3), WHITE);
+        assert_eq!(output.get_pixel(1, 3), WHITE);
+        assert_eq!(output.get_pixel(0, 1), BLACK);
+    }
+
+    #[test]
+    fn scale2x_flat_image_test() {
+        let mut image = FilteredImage::new(3, 3);
+        image.data.fill(0x80);
+
+        let output = scale2x(&image);
+        assert!(output.data.iter().all(|v| *v == 0x80));
+    }
+
+    #[test]
+    fn scale3x_test() {
+        let output = scale3x(&build_diagonal_image());
+        assert_eq!((output.width, output.height), (6, 6));
+
+        assert_eq!(output.get_pixel(1, 4), WHITE);
+        assert_eq!(output.get_pixel(2, 3), BLACK);
+        assert_eq!(output.get_pixel(2, 5), WHITE);
+    }
+
+    #[test]
+    fn neighbors_repeat_edges_test() {
+        let image = build_diagonal_image();
+        let [a, b, _, d, e, _, g, h, _] = neighbors(&image, 0, 1);
+
+        assert_eq!(e, WHITE);
+        assert_eq!(d, WHITE);
+        assert_eq!(g, WHITE);
+        assert_eq!(h, WHITE);
+        assert_eq!(a, BLACK);
+        assert_eq!(b, BLACK);
+    }
+}
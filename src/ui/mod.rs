@@ -1 +1,5 @@
-
+pub mod filter;
+pub mod overscan;
+pub mod recorder;
+pub mod screenshot;
+pub mod window;
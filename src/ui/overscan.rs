@@ -0,0 +1,172 @@
+use anyhow::Result;
+
+use crate::nes::ppu::frame::Frame;
+
+use super::filter::FilteredImage;
+
+pub const OVERSCAN_PRESET_NAMES: [&str; 3] = ["none", "ntsc", "pal"];
+
+/* Overscan
+https://www.nesdev.org/wiki/Overscan
+
+TVs hide the edges of the picture. Lines which the TV hides are cropped from the image.
+Crops are counted in pixels of `Frame`, so they are applied to any filtered image in proportion.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Overscan {
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Overscan {
+    pub fn new(top: usize, bottom: usize, left: usize, right: usize) -> Result<Self> {
+        // Saturated sums of huge crops are also too large.
+        let vertical = top.saturating_add(bottom);
+        let horizontal = left.saturating_add(right);
+        if vertical >= Frame::HIGHT || horizontal >= Frame::WIDTH {
+            bail!(
+                "overscan crops the whole frame: top {} bottom {} left {} right {} ({}x{})",
+                top,
+                bottom,
+                left,
+                right,
+                Frame::WIDTH,
+                Frame::HIGHT
+            );
+        }
+
+        Ok(Overscan {
+            top,
+            bottom,
+            left,
+            right,
+        })
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let overscan = match name {
+            "none" => Overscan::default(),
+            // NTSC TVs hide the top and bottom 8 lines.
+            "ntsc" => Overscan {
+                top: 8,
+                bottom: 8,
+                left: 0,
+                right: 0,
+            },
+            // PAL TVs show almost all lines.
+            "pal" => Overscan {
+                top: 1,
+                bottom: 1,
+                left: 0,
+                right: 0,
+            },
+            _ => return None,
+        };
+
+        Some(overscan)
+    }
+
+    /// Size of the visible area in pixels of `Frame`.
+    pub fn visible_size(&self) -> (usize, usize) {
+        (
+            Frame::WIDTH - self.left - self.right,
+            Frame::HIGHT - self.top - self.bottom,
+        )
+    }
+
+    /// Size of an image of `width` x `height` after cropping.
+    pub fn cropped_size(&self, width: usize, height: usize) -> (usize, usize) {
+        let (left, top, right, bottom) = self.scaled_crops(width, height);
+        (width - left - right, height - top - bottom)
+    }
+
+    /// Crop the edges of `image`, which is `Frame` scaled by any filter.
+    pub fn crop(&self, image: &FilteredImage) -> FilteredImage {
+        let (left, top, _, _) = self.scaled_crops(image.width, image.height);
+        let (width, height) = self.cropped_size(image.width, image.height);
+
+        let mut output = FilteredImage::new(width, height);
+        for y in 0..height {
+            let source = ((top + y) * image.width + left) * 3;
+            let destination = y * width * 3;
+            output.data[destination..destination + width * 3]
+                .copy_from_slice(&image.data[source..source + width * 3]);
+        }
+
+        output
+    }
+
+    /// (left, top, right, bottom) crops for an image of `width` x `height`.
+    fn scaled_crops(&self, width: usize, height: usize) -> (usize, usize, usize, usize) {
+        let scale_x = |v: usize| v * width / Frame::WIDTH;
+        let scale_y = |v: usize| v * height / Frame::HIGHT;
+
+        (
+            scale_x(self.left),
+            scale_y(self.top),
+            scale_x(self.right),
+            scale_y(self.bottom),
+        )
+    }
+}
+
+#[cfg(test)]
+mod overscan_test {
+    use super::*;
+
+    #[test]
+    fn preset_test() {
+        for name in OVERSCAN_PRESET_NAMES {
+            assert!(Overscan::preset(name).is_some());
+        }
+        assert_eq!(Overscan::preset("secam"), None);
+
+        assert_eq!(Overscan::preset("none").unwrap().visible_size(), (256, 240));
+        assert_eq!(Overscan::preset("ntsc").unwrap().visible_size(), (256, 224));
+    }
+
+    #[test]
+    fn new_test() {
+        assert!(Overscan::new(8, 8, 8, 8).is_ok());
+        assert!(Overscan::new(120, 120, 0, 0).is_err());
+        assert!(Overscan::new(0, 0, 200, 56).is_err());
+    }
+
+    #[test]
+    fn new_with_huge_crops_test() {
+        assert!(Overscan::new(usize::MAX, 1, 0, 0).is_err());
+        assert!(Overscan::new(0, usize::MAX, 0, 0).is_err());
+        assert!(Overscan::new(0, 0, 1, usize::MAX).is_err());
+        assert!(Overscan::new(0, 0, usize::MAX, usize::MAX).is_err());
+    }
+
+    #[test]
+    fn crop_test() {
+        let mut frame = Frame::new();
+        frame.set_pixel(2, 1, (1, 2, 3));
+        frame.set_pixel(255, 239, (4, 5, 6));
+
+        let overscan = Overscan::new(1, 2, 2, 3).unwrap();
+        let output = overscan.crop(&FilteredImage::from_frame(&frame));
+
+        assert_eq!((output.width, output.height), (251, 237));
+        assert_eq!(output.get_pixel(0, 0), (1, 2, 3));
+        assert_eq!(output.data.len(), 251 * 237 * 3);
+    }
+
+    #[test]
+    fn crop_scaled_image_test() {
+        // ex) The NTSC filter doubles the width.
+        let mut image = FilteredImage::new(Frame::WIDTH * 2, Frame::HIGHT);
+        image.set_pixel(4, 8, (1, 2, 3));
+
+        let overscan = Overscan::new(8, 8, 2, 2).unwrap();
+        let output = overscan.crop(&image);
+
+        assert_eq!((output.width, output.height), (504, 224));
+        assert_eq!(overscan.cropped_size(image.width, image.height), (504, 224));
+        assert_eq!(output.get_pixel(0, 0), (1, 2, 3));
+    }
+}
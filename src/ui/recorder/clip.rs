@@ -0,0 +1,157 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use anyhow::Result;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, RgbaImage,
+};
+
+use crate::nes::ppu::frame::Frame;
+
+use super::{FRAME_RATE_DENOMINATOR, FRAME_RATE_NUMERATOR};
+
+/// 10 seconds. The frames are kept in memory until the clip is written.
+pub const MAX_CLIP_FRAMES: usize = 600;
+
+/// 1000 / 60099 seconds: the NTSC frame time which fits into the u16 fraction of APNG.
+const APNG_FRAME_DELAY: (u16, u16) = (1000, 60099);
+
+/// Quality of the color quantization of GIF (1: best - 30: fastest)
+const GIF_SPEED: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClipFormat {
+    Gif,
+    Apng,
+}
+
+/* Animated clip
+Short clips for sharing. The audio is not included.
+
+GIF delays are in 1/100 seconds, so every second frame is kept (about 30 fps)
+and the delays alternate between 3 and 4 to follow the NTSC frame rate.
+APNG keeps all the frames.
+*/
+pub struct ClipWriter {
+    format: ClipFormat,
+    // RGB24 frames
+    frames: Vec<Vec<u8>>,
+    frame_count: usize,
+}
+
+impl ClipWriter {
+    pub fn new(format: ClipFormat) -> Self {
+        ClipWriter {
+            format,
+            frames: Vec::new(),
+            frame_count: 0,
+        }
+    }
+
+    pub fn push_frame(&mut self, frame: &Frame) {
+        if self.is_full() {
+            return;
+        }
+
+        let keep = match self.format {
+            ClipFormat::Gif => self.frame_count.is_multiple_of(2),
+            ClipFormat::Apng => true,
+        };
+        if keep {
+            self.frames.push(frame.data.clone());
+        }
+        self.frame_count += 1;
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.frame_count >= MAX_CLIP_FRAMES
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        match self.format {
+            ClipFormat::Gif => self.save_gif(writer),
+            ClipFormat::Apng => self.save_apng(writer),
+        }
+    }
+
+    fn save_gif(&self, writer: BufWriter<File>) -> Result<()> {
+        let mut encoder = GifEncoder::new_with_speed(writer, GIF_SPEED);
+        encoder.set_repeat(Repeat::Infinite)?;
+
+        for (i, data) in self.frames.iter().enumerate() {
+            let rgba: Vec<u8> = data
+                .chunks(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xFF])
+                .collect();
+            let image =
+                RgbaImage::from_raw(Frame::WIDTH as u32, Frame::HIGHT as u32, rgba).unwrap();
+            let delay = Delay::from_numer_denom_ms(gif_delay(i) * 10, 1);
+            encoder.encode_frame(image::Frame::from_parts(image, 0, 0, delay))?;
+        }
+
+        Ok(())
+    }
+
+    fn save_apng(&self, writer: BufWriter<File>) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, Frame::WIDTH as u32, Frame::HIGHT as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        // 0: loop forever
+        encoder.set_animated(self.frames.len() as u32, 0)?;
+
+        let mut writer = encoder.write_header()?;
+        for data in &self.frames {
+            writer.set_frame_delay(APNG_FRAME_DELAY.0, APNG_FRAME_DELAY.1)?;
+            writer.write_image_data(data)?;
+        }
+        writer.finish()?;
+
+        Ok(())
+    }
+}
+
+/// Delay of the nth GIF frame in 1/100 seconds, which covers 2 NES frames.
+/// The rounding error does not accumulate because the delay is the difference of the start times.
+fn gif_delay(n: usize) -> u32 {
+    let centiseconds = |frame: u64| -> u64 {
+        (frame * 100 * FRAME_RATE_DENOMINATOR + FRAME_RATE_NUMERATOR / 2) / FRAME_RATE_NUMERATOR
+    };
+    let start = n as u64 * 2;
+    (centiseconds(start + 2) - centiseconds(start)) as u32
+}
+
+#[cfg(test)]
+mod clip_writer_test {
+    use super::*;
+
+    #[test]
+    fn gif_delay_test() {
+        assert_eq!(gif_delay(0), 3);
+        assert_eq!(gif_delay(1), 4);
+
+        // 60 GIF frames are 120 NES frames: 1.997 seconds
+        let total: u32 = (0..60).map(gif_delay).sum();
+        assert_eq!(total, 200);
+    }
+
+    #[test]
+    fn push_frame_test() {
+        let frame = Frame::new();
+
+        let mut gif = ClipWriter::new(ClipFormat::Gif);
+        let mut apng = ClipWriter::new(ClipFormat::Apng);
+        for _ in 0..5 {
+            gif.push_frame(&frame);
+            apng.push_frame(&frame);
+        }
+        assert_eq!(gif.frames.len(), 3);
+        assert_eq!(apng.frames.len(), 5);
+
+        for _ in 0..MAX_CLIP_FRAMES {
+            apng.push_frame(&frame);
+        }
+        assert!(apng.is_full());
+        assert_eq!(apng.frames.len(), MAX_CLIP_FRAMES);
+    }
+}
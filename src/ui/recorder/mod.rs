@@ -0,0 +1,239 @@
+pub mod clip;
+pub mod wav;
+pub mod y4m;
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::nes::ppu::frame::Frame;
+
+use self::{
+    clip::{ClipFormat, ClipWriter},
+    wav::WavWriter,
+    y4m::Y4mWriter,
+};
+
+/// NTSC frame rate: 39375000 / 655171 = 60.0988 fps
+pub const FRAME_RATE_NUMERATOR: u64 = 39375000;
+pub const FRAME_RATE_DENOMINATOR: u64 = 655171;
+
+pub const SAMPLE_RATE: u32 = 44100;
+const CHANNELS: u16 = 1;
+
+pub const RECORD_EXTENSIONS: [&str; 5] = ["y4m", "rgb", "raw", "gif", "png"];
+
+/* Record format
+Decided by the extension of the output path.
+
+y4m      : YUV4MPEG2 video + WAV audio
+rgb, raw : RGB24 frames without a header + WAV audio
+           (ex: ffmpeg -f rawvideo -pixel_format rgb24 -video_size 256x240 -framerate 39375000/655171 -i video.rgb)
+gif      : Animated GIF clip
+png      : Animated PNG clip
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordFormat {
+    Y4m,
+    Raw,
+    Gif,
+    Apng,
+}
+
+impl RecordFormat {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "y4m" => Some(RecordFormat::Y4m),
+            "rgb" | "raw" => Some(RecordFormat::Raw),
+            "gif" => Some(RecordFormat::Gif),
+            "png" | "apng" => Some(RecordFormat::Apng),
+            _ => None,
+        }
+    }
+}
+
+enum VideoWriter {
+    Y4m(Y4mWriter<BufWriter<File>>),
+    Raw(BufWriter<File>),
+}
+
+enum Output {
+    Video {
+        video: VideoWriter,
+        audio: WavWriter<BufWriter<File>>,
+    },
+    Clip(ClipWriter),
+}
+
+/* Recorder
+Streams the frames and the audio samples into files.
+
+Every emulated frame is passed to `record_frame`, and the time stamps come from
+the frame count instead of the wall clock. So no frame is dropped or duplicated
+even if the emulator runs faster (or slower) than real time.
+
+The audio track is kept in sync with the video: each frame is padded with silence
+up to the samples of the NTSC frame time (about 733.8 samples at 44.1kHz).
+*/
+pub struct Recorder {
+    path: PathBuf,
+    output: Output,
+    frame_count: u64,
+}
+
+impl Recorder {
+    /// The WAV file of the video formats is written next to the video. (ex: video.y4m -> video.wav)
+    pub fn start<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let format = RecordFormat::from_path(&path).ok_or_else(|| {
+            anyhow!(
+                "Unknown record format: {} (expected {})",
+                path.display(),
+                RECORD_EXTENSIONS.join(", ")
+            )
+        })?;
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+
+        let output = match format {
+            RecordFormat::Y4m | RecordFormat::Raw => {
+                let writer = BufWriter::new(File::create(&path)?);
+                let video = match format {
+                    RecordFormat::Y4m => VideoWriter::Y4m(Y4mWriter::new(writer)?),
+                    _ => VideoWriter::Raw(writer),
+                };
+                let audio_file = File::create(audio_path(&path))?;
+                let audio = WavWriter::new(BufWriter::new(audio_file), SAMPLE_RATE, CHANNELS)?;
+                Output::Video { video, audio }
+            }
+            RecordFormat::Gif => Output::Clip(ClipWriter::new(ClipFormat::Gif)),
+            RecordFormat::Apng => Output::Clip(ClipWriter::new(ClipFormat::Apng)),
+        };
+
+        Ok(Recorder {
+            path,
+            output,
+            frame_count: 0,
+        })
+    }
+
+    /// `samples` are the mono samples at SAMPLE_RATE generated during the frame.
+    pub fn record_frame(&mut self, frame: &Frame, samples: &[i16]) -> Result<()> {
+        self.frame_count += 1;
+
+        match &mut self.output {
+            Output::Video { video, audio } => {
+                match video {
+                    VideoWriter::Y4m(writer) => writer.write_frame(frame)?,
+                    VideoWriter::Raw(writer) => writer.write_all(&frame.data)?,
+                }
+
+                audio.write_samples(samples)?;
+                let missing = samples_until(self.frame_count).saturating_sub(audio.sample_count());
+                audio.write_samples(&vec![0; missing as usize])?;
+            }
+            Output::Clip(clip) => clip.push_frame(frame),
+        }
+
+        Ok(())
+    }
+
+    /// Clips have a limit of the length. The recording should be finished when it is full.
+    pub fn is_full(&self) -> bool {
+        match &self.output {
+            Output::Video { .. } => false,
+            Output::Clip(clip) => clip.is_full(),
+        }
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Returns the paths of the written files.
+    pub fn finish(self) -> Result<Vec<PathBuf>> {
+        match self.output {
+            Output::Video { video, audio } => {
+                match video {
+                    VideoWriter::Y4m(writer) => writer.finish()?,
+                    VideoWriter::Raw(mut writer) => writer.flush()?,
+                }
+                audio.finish()?;
+                Ok(vec![self.path.clone(), audio_path(&self.path)])
+            }
+            Output::Clip(clip) => {
+                clip.save(&self.path)?;
+                Ok(vec![self.path])
+            }
+        }
+    }
+}
+
+fn audio_path(video_path: &Path) -> PathBuf {
+    video_path.with_extension("wav")
+}
+
+/// Total audio samples at the end of the frame.
+fn samples_until(frame_count: u64) -> u32 {
+    (frame_count * SAMPLE_RATE as u64 * FRAME_RATE_DENOMINATOR / FRAME_RATE_NUMERATOR) as u32
+}
+
+#[cfg(test)]
+mod recorder_test {
+    use super::*;
+
+    #[test]
+    fn from_path_test() {
+        assert_eq!(RecordFormat::from_path("a.y4m"), Some(RecordFormat::Y4m));
+        assert_eq!(RecordFormat::from_path("a.RGB"), Some(RecordFormat::Raw));
+        assert_eq!(RecordFormat::from_path("a.raw"), Some(RecordFormat::Raw));
+        assert_eq!(RecordFormat::from_path("a.gif"), Some(RecordFormat::Gif));
+        assert_eq!(RecordFormat::from_path("a.png"), Some(RecordFormat::Apng));
+        assert_eq!(RecordFormat::from_path("a.mp4"), None);
+        assert_eq!(RecordFormat::from_path("a"), None);
+    }
+
+    #[test]
+    fn samples_until_test() {
+        assert_eq!(samples_until(0), 0);
+        assert_eq!(samples_until(1), 733);
+        // About 1 hour of NTSC frames (3600.0045 seconds)
+        assert_eq!(samples_until(216_356), 158_760_198);
+    }
+
+    #[test]
+    fn record_video_test() {
+        let directory = std::env::temp_dir().join("baby-nes-recorder-test");
+        let path = directory.join("video.y4m");
+
+        let mut recorder = Recorder::start(&path).unwrap();
+        let frame = Frame::new();
+        for _ in 0..3 {
+            recorder.record_frame(&frame, &[]).unwrap();
+        }
+        assert_eq!(recorder.frame_count(), 3);
+        assert!(!recorder.is_full());
+
+        let paths = recorder.finish().unwrap();
+        assert_eq!(paths, vec![path.clone(), directory.join("video.wav")]);
+
+        let video = fs::read(&path).unwrap();
+        let header_length = video.iter().position(|&b| b == b'\n').unwrap() + 1;
+        assert_eq!(video.len(), header_length + (6 + 256 * 240 * 3) * 3);
+
+        let audio = fs::read(directory.join("video.wav")).unwrap();
+        assert_eq!(audio.len(), 44 + samples_until(3) as usize * 2);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+use std::io::{self, Seek, SeekFrom, Write};
+
+const HEADER_SIZE: u32 = 44;
+const BITS_PER_SAMPLE: u16 = 16;
+
+/* WAV writer
+http://soundfile.sapp.org/doc/WaveFormat/
+
+16 bits PCM. The sizes in the header are unknown while streaming,
+so they are written as 0 and fixed by `finish`.
+*/
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    sample_count: u32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(mut writer: W, sample_rate: u32, channels: u16) -> io::Result<Self> {
+        let block_align = channels * BITS_PER_SAMPLE / 8;
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        // 1: PCM
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&channels.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        writer.write_all(&block_align.to_le_bytes())?;
+        writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        Ok(WavWriter {
+            writer,
+            sample_count: 0,
+        })
+    }
+
+    /// Interleaved samples of all channels.
+    pub fn write_samples(&mut self, samples: &[i16]) -> io::Result<()> {
+        for sample in samples {
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.sample_count += samples.len() as u32;
+        Ok(())
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Write the sizes into the header.
+    pub fn finish(mut self) -> io::Result<W> {
+        let data_size = self.sample_count * (BITS_PER_SAMPLE / 8) as u32;
+
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer
+            .write_all(&(HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&data_size.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod wav_writer_test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn write_test() {
+        let mut writer = WavWriter::new(Cursor::new(Vec::new()), 44100, 1).unwrap();
+        writer.write_samples(&[0, 1, -1]).unwrap();
+        assert_eq!(writer.sample_count(), 3);
+
+        let data = writer.finish().unwrap().into_inner();
+        assert_eq!(data.len(), HEADER_SIZE as usize + 6);
+        assert_eq!(&data[0..4], b"RIFF");
+        assert_eq!(data[4..8], (36u32 + 6).to_le_bytes());
+        assert_eq!(&data[8..16], b"WAVEfmt ");
+        assert_eq!(data[24..28], 44100u32.to_le_bytes());
+        assert_eq!(data[28..32], 88200u32.to_le_bytes());
+        assert_eq!(&data[36..40], b"data");
+        assert_eq!(data[40..44], 6u32.to_le_bytes());
+        assert_eq!(&data[44..], &[0, 0, 1, 0, 0xFF, 0xFF]);
+    }
+}
@@ -0,0 +1,89 @@
+use std::io::{self, Write};
+
+use crate::nes::ppu::frame::Frame;
+
+use super::{FRAME_RATE_DENOMINATOR, FRAME_RATE_NUMERATOR};
+
+/* YUV4MPEG2 writer
+https://wiki.multimedia.cx/index.php/YUV4MPEG2
+
+Uncompressed 4:4:4 video which most video tools can read. (ex: ffmpeg -i video.y4m video.mp4)
+The pixel aspect ratio of NTSC NES is 8:7.
+RGB is converted to YCbCr of ITU-R BT.601 (limited range).
+*/
+pub struct Y4mWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Y4mWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writeln!(
+            writer,
+            "YUV4MPEG2 W{} H{} F{}:{} Ip A8:7 C444",
+            Frame::WIDTH,
+            Frame::HIGHT,
+            FRAME_RATE_NUMERATOR,
+            FRAME_RATE_DENOMINATOR
+        )?;
+        Ok(Y4mWriter { writer })
+    }
+
+    pub fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        let pixel_count = Frame::WIDTH * Frame::HIGHT;
+        let mut planes = vec![0u8; pixel_count * 3];
+        for (i, rgb) in frame.data.chunks(3).enumerate() {
+            let [y, cb, cr] = rgb_to_ycbcr(rgb[0], rgb[1], rgb[2]);
+            planes[i] = y;
+            planes[pixel_count + i] = cb;
+            planes[pixel_count * 2 + i] = cr;
+        }
+
+        self.writer.write_all(b"FRAME\n")?;
+        self.writer.write_all(&planes)
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn rgb_to_ycbcr(r: u8, g: u8, b: u8) -> [u8; 3] {
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    let y = ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16;
+    let cb = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+    let cr = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+    [y as u8, cb as u8, cr as u8]
+}
+
+#[cfg(test)]
+mod y4m_writer_test {
+    use super::*;
+
+    #[test]
+    fn rgb_to_ycbcr_test() {
+        assert_eq!(rgb_to_ycbcr(0, 0, 0), [16, 128, 128]);
+        assert_eq!(rgb_to_ycbcr(255, 255, 255), [235, 128, 128]);
+        assert_eq!(rgb_to_ycbcr(255, 0, 0), [82, 90, 240]);
+    }
+
+    #[test]
+    fn write_frame_test() {
+        let mut frame = Frame::new();
+        frame.set_pixel(1, 0, (255, 255, 255));
+
+        let mut writer = Y4mWriter::new(Vec::new()).unwrap();
+        writer.write_frame(&frame).unwrap();
+        writer.write_frame(&frame).unwrap();
+        let data = writer.writer;
+
+        let header = b"YUV4MPEG2 W256 H240 F39375000:655171 Ip A8:7 C444\n";
+        assert_eq!(&data[..header.len()], header);
+
+        let frame_size = 6 + 256 * 240 * 3;
+        assert_eq!(data.len(), header.len() + frame_size * 2);
+
+        let planes = &data[header.len() + 6..];
+        assert_eq!(&planes[0..2], &[16, 235]);
+        assert_eq!(planes[256 * 240 + 1], 128);
+    }
+}
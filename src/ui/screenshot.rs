@@ -0,0 +1,119 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+
+use crate::nes::ppu::frame::Frame;
+
+const FILE_PREFIX: &str = "baby-nes";
+
+/* Screenshot
+Screenshots are written into a directory with the time in the file name (UTC).
+
+ex: baby-nes-20240102-030405-678.png
+*/
+pub struct Screenshot {
+    directory: PathBuf,
+}
+
+impl Screenshot {
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        Screenshot {
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path_at(&self, time: SystemTime) -> PathBuf {
+        timestamped_path(&self.directory, time, "png")
+    }
+
+    /// Save the frame as a PNG. The directory is created if it does not exist.
+    pub fn save(&self, frame: &Frame) -> Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+
+        let path = self.path_at(SystemTime::now());
+        frame.save_png(&path)?;
+        Ok(path)
+    }
+}
+
+/// Path in the directory with the time in the file name. (also used by the recordings)
+pub fn timestamped_path<P: AsRef<Path>>(
+    directory: P,
+    time: SystemTime,
+    extension: &str,
+) -> PathBuf {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = elapsed.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let second_of_day = seconds % 86400;
+
+    let file_name = format!(
+        "{}-{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}.{}",
+        FILE_PREFIX,
+        year,
+        month,
+        day,
+        second_of_day / 3600,
+        second_of_day / 60 % 60,
+        second_of_day % 60,
+        elapsed.subsec_millis(),
+        extension
+    );
+    directory.as_ref().join(file_name)
+}
+
+/// Date of the days since 1970-01-01.
+/// ref: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = match month_index < 10 {
+        true => month_index + 3,
+        false => month_index - 9,
+    } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod screenshot_test {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn civil_from_days_test() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        // Leap day
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn path_at_test() {
+        let screenshot = Screenshot::new("shots");
+        // 2024-01-02 03:04:05.678 UTC
+        let time = UNIX_EPOCH + Duration::from_millis(1_704_164_645_678);
+
+        assert_eq!(
+            screenshot.path_at(time),
+            Path::new("shots").join("baby-nes-20240102-030405-678.png")
+        );
+        assert_eq!(
+            timestamped_path("videos", time, "y4m"),
+            Path::new("videos").join("baby-nes-20240102-030405-678.y4m")
+        );
+    }
+}
@@ -0,0 +1,168 @@
+/// Width : height of a pixel on a NTSC TV.
+pub const PIXEL_ASPECT_RATIO: f32 = 8.0 / 7.0;
+
+pub const WINDOW_MODE_NAMES: [&str; 3] = ["stretch", "integer", "aspect"];
+
+/// How the image is placed in the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowMode {
+    /// Fill the whole window.
+    Stretch,
+    /// Scale by the largest integer that fits in the window, so that every pixel has the same size.
+    IntegerScale,
+    /// Keep the 8:7 pixel aspect ratio of a TV and fit in the window.
+    AspectCorrect,
+}
+
+/// Area of the window to draw the image, in window pixels.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl WindowMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "stretch" => Some(WindowMode::Stretch),
+            "integer" => Some(WindowMode::IntegerScale),
+            "aspect" => Some(WindowMode::AspectCorrect),
+            _ => None,
+        }
+    }
+
+    /// Initial window size for `scale` times the visible area of `Frame`.
+    pub fn window_size(&self, visible_size: (usize, usize), scale: f32) -> (u32, u32) {
+        let (visible_width, visible_height) = visible_size;
+        let width = match self {
+            WindowMode::AspectCorrect => visible_width as f32 * scale * PIXEL_ASPECT_RATIO,
+            _ => visible_width as f32 * scale,
+        };
+
+        (width.round() as u32, (visible_height as f32 * scale) as u32)
+    }
+
+    /// Area to draw the visible area of `Frame` (after any filter) in a window of
+    /// `window_width` x `window_height`.
+    /// The area is centered and the rest of the window is left as a black border.
+    pub fn viewport(
+        &self,
+        visible_size: (usize, usize),
+        window_width: u32,
+        window_height: u32,
+    ) -> Viewport {
+        let (visible_width, visible_height) = (visible_size.0 as u32, visible_size.1 as u32);
+        let (width, height) = match self {
+            WindowMode::Stretch => (window_width, window_height),
+            WindowMode::IntegerScale => {
+                let scale = (window_width / visible_width)
+                    .min(window_height / visible_height)
+                    .max(1);
+                (visible_width * scale, visible_height * scale)
+            }
+            WindowMode::AspectCorrect => {
+                let aspect = visible_width as f32 * PIXEL_ASPECT_RATIO / visible_height as f32;
+                let fit_width = (window_height as f32 * aspect).round() as u32;
+                if fit_width <= window_width {
+                    (fit_width, window_height)
+                } else {
+                    (window_width, (window_width as f32 / aspect).round() as u32)
+                }
+            }
+        };
+
+        Viewport {
+            x: (window_width as i32 - width as i32) / 2,
+            y: (window_height as i32 - height as i32) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod window_mode_test {
+    use super::*;
+
+    const FULL_SIZE: (usize, usize) = (256, 240);
+
+    #[test]
+    fn from_name_test() {
+        for name in WINDOW_MODE_NAMES {
+            assert!(WindowMode::from_name(name).is_some());
+        }
+        assert_eq!(WindowMode::from_name("fit"), None);
+    }
+
+    #[test]
+    fn window_size_test() {
+        assert_eq!(WindowMode::Stretch.window_size(FULL_SIZE, 3.0), (768, 720));
+        assert_eq!(
+            WindowMode::AspectCorrect.window_size(FULL_SIZE, 3.0),
+            (878, 720)
+        );
+    }
+
+    #[test]
+    fn stretch_viewport_test() {
+        let viewport = WindowMode::Stretch.viewport(FULL_SIZE, 1000, 700);
+        assert_eq!(
+            viewport,
+            Viewport {
+                x: 0,
+                y: 0,
+                width: 1000,
+                height: 700
+            }
+        );
+    }
+
+    #[test]
+    fn integer_scale_viewport_test() {
+        // 2x (512 x 480) fits, 3x (768 x 720) does not.
+        let viewport = WindowMode::IntegerScale.viewport(FULL_SIZE, 1000, 700);
+        assert_eq!(
+            viewport,
+            Viewport {
+                x: 244,
+                y: 110,
+                width: 512,
+                height: 480
+            }
+        );
+
+        // Never smaller than 1x.
+        let viewport = WindowMode::IntegerScale.viewport(FULL_SIZE, 100, 100);
+        assert_eq!((viewport.width, viewport.height), (256, 240));
+    }
+
+    #[test]
+    fn aspect_correct_viewport_test() {
+        // Limited by the height.
+        let viewport = WindowMode::AspectCorrect.viewport(FULL_SIZE, 1000, 480);
+        assert_eq!(
+            viewport,
+            Viewport {
+                x: 207,
+                y: 0,
+                width: 585,
+                height: 480
+            }
+        );
+
+        // Limited by the width.
+        let viewport = WindowMode::AspectCorrect.viewport(FULL_SIZE, 585, 1000);
+        assert_eq!((viewport.width, viewport.height), (585, 480));
+        assert_eq!(viewport.y, 260);
+    }
+
+    #[test]
+    fn cropped_viewport_test() {
+        // NTSC overscan: 256 x 224
+        let viewport = WindowMode::IntegerScale.viewport((256, 224), 768, 672);
+        assert_eq!((viewport.width, viewport.height), (768, 672));
+        assert_eq!(WindowMode::Stretch.window_size((256, 224), 3.0), (768, 672));
+    }
+}